@@ -279,6 +279,8 @@ pub fn testnet_genesis(
 			.into()],
 			root_deadline_time: Default::default(),
 		}),
+		ipse: Some(Default::default()),
+		poc: Some(Default::default()),
 	}
 }
 //
@@ -0,0 +1,47 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deadlines of the Proof-of-Capacity, after the PoC2 plots of Burst.
+//!
+//! A nonce of a plot is `NUM_SCOOPS` scoops of `SCOOP_SIZE` bytes, generated from the plot id
+//! (numeric id) and the nonce by shabal256. The generation signature and the height draw the
+//! scoop that is mined. In PoC2 the second hash of the scoop is taken from the mirror scoop, the
+//! scoop at the same distance from the end of the nonce. The target is the shabal256 of the
+//! scoop and the signature, the deadline is the target divided by the base target of the chain.
+
+use conjugate_poc::{
+	nonce::{noncegen_rust, NONCE_SIZE, NUM_SCOOPS, SCOOP_SIZE},
+	poc_hashing::{calculate_scoop, find_best_deadline_rust},
+};
+
+/// The scoop `scoop` of the nonce in PoC2: its first hash, and the second hash of its mirror.
+fn poc2_scoop(nonce: &[u8], scoop: usize) -> [u8; SCOOP_SIZE] {
+	let half = SCOOP_SIZE / 2;
+	let addr = SCOOP_SIZE * scoop;
+	let mirror_addr = SCOOP_SIZE * (NUM_SCOOPS - 1 - scoop);
+	let mut data = [0u8; SCOOP_SIZE];
+	data[..half].copy_from_slice(&nonce[addr..addr + half]);
+	data[half..].copy_from_slice(&nonce[mirror_addr + half..mirror_addr + SCOOP_SIZE]);
+	data
+}
+
+/// The target of the nonce `nonce` of the plot `numeric_id`, mined at the height `height` with
+/// the generation signature `sig`.
+pub fn calculate_target(numeric_id: u64, height: u64, sig: &[u8; 32], nonce: u64) -> u64 {
+	let scoop = calculate_scoop(height, sig) as usize;
+	let mut cache = vec![0u8; NONCE_SIZE];
+	noncegen_rust(&mut cache[..], numeric_id, nonce, 1);
+	find_best_deadline_rust(&poc2_scoop(&cache, scoop), 1, sig).0
+}
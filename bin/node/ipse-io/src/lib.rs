@@ -0,0 +1,83 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Host functions of the IPSE runtime.
+//!
+//! The proofs that are too heavy to check in Wasm are checked natively by the node, the runtime
+//! calls them like the functions of `sp-io`. The node registers `IpseHostFunctions` in its
+//! executor.
+//!
+//! - `porep`: the seal proofs and the replica proofs of sectors, see `seal`.
+//! - `poc`: the deadlines of the mining, see `deadline`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_runtime_interface::runtime_interface;
+
+#[cfg(feature = "std")]
+pub mod deadline;
+#[cfg(feature = "std")]
+pub mod seal;
+
+/// the size of a sector (byte), the unit in which the data is sealed.
+pub const SECTOR_SIZE: u64 = 128 * 1024 * 1024;
+
+/// the size of a window (byte), the nodes of a sector are labeled window by window.
+pub const WINDOW_SIZE: u64 = 32 * 1024 * 1024;
+
+/// the size of a node (byte).
+pub const NODE_SIZE: u64 = 32;
+
+/// Proof-of-Replication of sectors, see `seal`.
+#[runtime_interface]
+pub trait Porep {
+	/// Verify the seal proof of the sector whose replica commitment is `comm_r` and data
+	/// commitment is `comm_d`, the challenged nodes are drawn from `seed`.
+	fn verify_seal(
+		comm_r: [u8; 32],
+		comm_d: [u8; 32],
+		replica_id: [u8; 32],
+		seed: [u8; 32],
+		proof: &[u8],
+	) -> bool {
+		seal::verify_seal_encoded(
+			&seal::SectorParams::default(),
+			&comm_r,
+			&comm_d,
+			&replica_id,
+			&seed,
+			proof,
+		)
+	}
+
+	/// Verify that the node `index` of the replica is kept, against the replica commitment.
+	fn verify_replica(comm_r: [u8; 32], index: u64, proof: &[u8]) -> bool {
+		seal::verify_replica_encoded(&seal::SectorParams::default(), &comm_r, index, proof)
+	}
+}
+
+/// Proof-of-Capacity of plots, see `deadline`.
+#[runtime_interface]
+pub trait Poc {
+	/// The target of the nonce `nonce` of the plot `numeric_id`, mined at the height `height` with
+	/// the generation signature `sig`. The deadline is the target divided by the base target.
+	fn calculate_target(numeric_id: u64, height: u64, sig: [u8; 32], nonce: u64) -> u64 {
+		deadline::calculate_target(numeric_id, height, &sig, nonce)
+	}
+}
+
+/// The host functions that the node provides to the IPSE runtime.
+#[cfg(feature = "std")]
+pub type IpseHostFunctions = (porep::HostFunctions, poc::HostFunctions);
@@ -0,0 +1,502 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sealing of sectors, after `document/ZPK-PoRep.md`.
+//!
+//! The data of a sector is cut into nodes of `NODE_SIZE` bytes, and the nodes are labeled in
+//! `LAYERS` layers window by window. The label of a node hashes the replica id, the node and the
+//! labels of its parents, which are earlier nodes of the same window in the same layer and in
+//! the layer below. The first parent is the previous node, so the labels of a window have to be
+//! computed one by one. The label of the last layer is the key that encodes the data node into
+//! the replica node by a modular addition.
+//!
+//! Three merkle trees of sha256 commit the sector: `tree_d` over the data nodes (CommD),
+//! `tree_c` over the columns of labels of the nodes and `tree_r_last` over the replica nodes.
+//! CommR is `sha256(comm_c ++ comm_r_last)`.
+//!
+//! The seal proof opens `SEAL_CHALLENGES` nodes drawn from the seed: the data node, the
+//! replica node, and the columns of the node and of its parents, so that its labels are computed
+//! again. It grows with the log of the sector size, and can be replaced by a SNARK of the same
+//! statement behind the host function.
+
+use crate::{NODE_SIZE, SECTOR_SIZE, WINDOW_SIZE};
+use codec::{Decode, Encode};
+use sp_core::hashing::sha2_256;
+use std::collections::BTreeMap;
+
+/// how many layers of labels are computed.
+pub const LAYERS: usize = 2;
+
+/// how many parents a node has, the first node of a window has none.
+pub const PARENTS: usize = 6;
+
+/// how many nodes are opened by a seal proof.
+pub const SEAL_CHALLENGES: usize = 8;
+
+pub type Node = [u8; 32];
+
+/// The shape of a sector, the numbers of nodes are powers of two.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SectorParams {
+	pub nodes: u64,
+	pub window_nodes: u64,
+}
+
+impl Default for SectorParams {
+	fn default() -> Self {
+		SectorParams { nodes: SECTOR_SIZE / NODE_SIZE, window_nodes: WINDOW_SIZE / NODE_SIZE }
+	}
+}
+
+impl SectorParams {
+	/// the depth of the merkle trees.
+	fn depth(&self) -> usize {
+		self.nodes.trailing_zeros() as usize
+	}
+}
+
+/// The labels of a node in all the layers, and its merkle path in `tree_c`.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ColumnProof {
+	pub labels: Vec<Node>,
+	pub path: Vec<Node>,
+}
+
+/// The opening of a challenged node.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ChallengeProof {
+	pub data: Node,
+	// the merkle path of the data node in `tree_d`
+	pub data_path: Vec<Node>,
+	// the merkle path of the replica node in `tree_r_last`
+	pub replica_path: Vec<Node>,
+	pub column: ColumnProof,
+	// the columns of the parents, in the order of `parents`
+	pub parents: Vec<ColumnProof>,
+}
+
+/// The proof that a sector is sealed.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SealProof {
+	pub comm_c: Node,
+	pub comm_r_last: Node,
+	pub challenges: Vec<ChallengeProof>,
+}
+
+/// The proof that a node of the replica is kept.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ReplicaProof {
+	pub comm_c: Node,
+	pub comm_r_last: Node,
+	pub node: Node,
+	pub path: Vec<Node>,
+}
+
+fn hash(parts: &[&[u8]]) -> Node {
+	sha2_256(&parts.concat())
+}
+
+fn u64_of(node: &Node) -> u64 {
+	let mut bytes = [0u8; 8];
+	bytes.copy_from_slice(&node[..8]);
+	u64::from_le_bytes(bytes)
+}
+
+pub fn comm_r_of(comm_c: &Node, comm_r_last: &Node) -> Node {
+	hash(&[comm_c, comm_r_last])
+}
+
+fn column_hash(labels: &[Node]) -> Node {
+	sha2_256(&labels.concat())
+}
+
+/// Encode the data node with the key, a little endian addition modulo 2^256.
+fn encode(data: &Node, key: &Node) -> Node {
+	let mut replica = [0u8; 32];
+	let mut carry = 0u16;
+	for ((r, d), k) in replica.iter_mut().zip(data).zip(key) {
+		let sum = *d as u16 + *k as u16 + carry;
+		*r = sum as u8;
+		carry = sum >> 8;
+	}
+	replica
+}
+
+/// The parents of the node, the previous node and random earlier nodes of its window.
+pub fn parents(params: &SectorParams, replica_id: &Node, index: u64) -> Vec<u64> {
+	let offset = index % params.window_nodes;
+	if offset == 0 {
+		return Vec::new()
+	}
+	let mut parents = vec![index - 1];
+	for p in 1..PARENTS as u64 {
+		let h = hash(&[b"ipse/parent", replica_id, &index.to_le_bytes(), &p.to_le_bytes()]);
+		parents.push(index - offset + u64_of(&h) % offset);
+	}
+	parents
+}
+
+/// The label of the node in `layer`, `labels(layer, node)` gives the labels of its parents and
+/// of the node itself in the layer below.
+fn label(
+	replica_id: &Node,
+	layer: usize,
+	index: u64,
+	parents: &[u64],
+	labels: impl Fn(usize, u64) -> Node,
+) -> Node {
+	let mut buf = Vec::with_capacity(32 * (2 * parents.len() + 2) + 9);
+	buf.extend_from_slice(replica_id);
+	buf.push(layer as u8);
+	buf.extend_from_slice(&index.to_le_bytes());
+	for p in parents {
+		buf.extend_from_slice(&labels(layer, *p));
+	}
+	if layer > 0 {
+		buf.extend_from_slice(&labels(layer - 1, index));
+		for p in parents {
+			buf.extend_from_slice(&labels(layer - 1, *p));
+		}
+	}
+	sha2_256(&buf)
+}
+
+/// The nodes opened by the seal proof.
+pub fn seal_challenges(params: &SectorParams, replica_id: &Node, seed: &Node) -> Vec<u64> {
+	(0..SEAL_CHALLENGES as u64)
+		.map(|k| u64_of(&hash(&[b"ipse/seal", replica_id, seed, &k.to_le_bytes()])) % params.nodes)
+		.collect()
+}
+
+/// Check that `leaf` is the `index`th leaf of the tree whose root is `root`.
+fn verify_path(params: &SectorParams, root: &Node, leaf: Node, index: u64, path: &[Node]) -> bool {
+	if path.len() != params.depth() || index >= params.nodes {
+		return false
+	}
+	let mut node = leaf;
+	for (level, sibling) in path.iter().enumerate() {
+		node = if (index >> level) & 1 == 0 {
+			hash(&[&node, sibling])
+		} else {
+			hash(&[sibling, &node])
+		};
+	}
+	&node == root
+}
+
+fn verify_column(params: &SectorParams, comm_c: &Node, index: u64, column: &ColumnProof) -> bool {
+	column.labels.len() == LAYERS &&
+		verify_path(params, comm_c, column_hash(&column.labels), index, &column.path)
+}
+
+fn verify_challenge(
+	params: &SectorParams,
+	comm_d: &Node,
+	replica_id: &Node,
+	proof: &SealProof,
+	index: u64,
+	challenge: &ChallengeProof,
+) -> bool {
+	let parents = parents(params, replica_id, index);
+	if challenge.parents.len() != parents.len() ||
+		!verify_column(params, &proof.comm_c, index, &challenge.column) ||
+		!parents
+			.iter()
+			.zip(&challenge.parents)
+			.all(|(p, column)| verify_column(params, &proof.comm_c, *p, column)) ||
+		!verify_path(params, comm_d, challenge.data, index, &challenge.data_path)
+	{
+		return false
+	}
+
+	let mut columns = BTreeMap::new();
+	columns.insert(index, &challenge.column.labels);
+	for (p, column) in parents.iter().zip(&challenge.parents) {
+		columns.insert(*p, &column.labels);
+	}
+	for layer in 0..LAYERS {
+		let label = label(replica_id, layer, index, &parents, |l, i| columns[&i][l]);
+		if label != challenge.column.labels[layer] {
+			return false
+		}
+	}
+
+	let replica = encode(&challenge.data, &challenge.column.labels[LAYERS - 1]);
+	verify_path(params, &proof.comm_r_last, replica, index, &challenge.replica_path)
+}
+
+/// Verify that the sector is sealed with the replica id.
+pub fn verify_seal(
+	params: &SectorParams,
+	comm_r: &Node,
+	comm_d: &Node,
+	replica_id: &Node,
+	seed: &Node,
+	proof: &SealProof,
+) -> bool {
+	let challenges = seal_challenges(params, replica_id, seed);
+	comm_r_of(&proof.comm_c, &proof.comm_r_last) == *comm_r &&
+		proof.challenges.len() == challenges.len() &&
+		challenges
+			.iter()
+			.zip(&proof.challenges)
+			.all(|(index, c)| verify_challenge(params, comm_d, replica_id, proof, *index, c))
+}
+
+/// Verify that the node `index` of the replica is kept.
+pub fn verify_replica(
+	params: &SectorParams,
+	comm_r: &Node,
+	index: u64,
+	proof: &ReplicaProof,
+) -> bool {
+	comm_r_of(&proof.comm_c, &proof.comm_r_last) == *comm_r &&
+		verify_path(params, &proof.comm_r_last, proof.node, index, &proof.path)
+}
+
+pub fn verify_seal_encoded(
+	params: &SectorParams,
+	comm_r: &Node,
+	comm_d: &Node,
+	replica_id: &Node,
+	seed: &Node,
+	proof: &[u8],
+) -> bool {
+	SealProof::decode(&mut &proof[..])
+		.map(|proof| verify_seal(params, comm_r, comm_d, replica_id, seed, &proof))
+		.unwrap_or(false)
+}
+
+pub fn verify_replica_encoded(
+	params: &SectorParams,
+	comm_r: &Node,
+	index: u64,
+	proof: &[u8],
+) -> bool {
+	ReplicaProof::decode(&mut &proof[..])
+		.map(|proof| verify_replica(params, comm_r, index, &proof))
+		.unwrap_or(false)
+}
+
+/// A merkle tree over a power of two leaves, `levels[0]` are the leaves.
+struct MerkleTree {
+	levels: Vec<Vec<Node>>,
+}
+
+impl MerkleTree {
+	fn new(leaves: Vec<Node>) -> Self {
+		let mut levels = vec![leaves];
+		while levels[levels.len() - 1].len() > 1 {
+			let level = levels[levels.len() - 1].chunks(2).map(|p| hash(&[&p[0], &p[1]])).collect();
+			levels.push(level);
+		}
+		MerkleTree { levels }
+	}
+
+	fn root(&self) -> Node {
+		self.levels[self.levels.len() - 1][0]
+	}
+
+	fn path(&self, index: u64) -> Vec<Node> {
+		let levels = &self.levels[..self.levels.len() - 1];
+		levels.iter().enumerate().map(|(l, level)| level[((index >> l) ^ 1) as usize]).collect()
+	}
+}
+
+/// A sealed sector, the miner keeps it to answer the challenges.
+pub struct Replica {
+	params: SectorParams,
+	replica_id: Node,
+	data: Vec<Node>,
+	// the labels of every layer
+	labels: Vec<Vec<Node>>,
+	tree_d: MerkleTree,
+	tree_c: MerkleTree,
+	tree_r_last: MerkleTree,
+}
+
+/// The nodes of the data, which is padded with zeros to `nodes` nodes.
+fn data_nodes(data: &[u8], nodes: usize) -> Vec<Node> {
+	let mut data_nodes = vec![[0u8; 32]; nodes];
+	for (node, chunk) in data_nodes.iter_mut().zip(data.chunks(NODE_SIZE as usize)) {
+		node[..chunk.len()].copy_from_slice(chunk);
+	}
+	data_nodes
+}
+
+/// The CommP of the data, the root of the tree of its nodes padded with zero nodes to a power
+/// of two, which the user commits to when it orders the storage.
+///
+/// a piece sealed in a sector at an offset aligned to its nodes is a subtree of `tree_d`, whose
+/// root is the CommP.
+pub fn comm_p(data: &[u8]) -> Node {
+	let nodes = ((data.len() as u64 + NODE_SIZE - 1) / NODE_SIZE).max(1).next_power_of_two();
+	MerkleTree::new(data_nodes(data, nodes as usize)).root()
+}
+
+/// Seal the data, which is padded with zeros to the sector size.
+pub fn seal(params: SectorParams, replica_id: Node, data: &[u8]) -> Replica {
+	assert!(params.nodes.is_power_of_two() && params.window_nodes.is_power_of_two());
+	assert!(data.len() as u64 <= params.nodes * NODE_SIZE, "the data is larger than the sector");
+
+	let nodes = params.nodes as usize;
+	let data_nodes = data_nodes(data, nodes);
+
+	let mut labels: Vec<Vec<Node>> = Vec::with_capacity(LAYERS);
+	for layer in 0..LAYERS {
+		labels.push(vec![[0u8; 32]; nodes]);
+		for index in 0..params.nodes {
+			let parents = parents(&params, &replica_id, index);
+			let label = label(&replica_id, layer, index, &parents, |l, i| labels[l][i as usize]);
+			labels[layer][index as usize] = label;
+		}
+	}
+
+	let columns = (0..nodes)
+		.map(|i| column_hash(&labels.iter().map(|layer| layer[i]).collect::<Vec<_>>()))
+		.collect();
+	let replica = data_nodes.iter().zip(&labels[LAYERS - 1]).map(|(d, k)| encode(d, k)).collect();
+
+	Replica {
+		params,
+		replica_id,
+		tree_d: MerkleTree::new(data_nodes.clone()),
+		tree_c: MerkleTree::new(columns),
+		tree_r_last: MerkleTree::new(replica),
+		data: data_nodes,
+		labels,
+	}
+}
+
+impl Replica {
+	pub fn comm_d(&self) -> Node {
+		self.tree_d.root()
+	}
+
+	pub fn comm_r(&self) -> Node {
+		comm_r_of(&self.tree_c.root(), &self.tree_r_last.root())
+	}
+
+	fn column(&self, index: u64) -> ColumnProof {
+		ColumnProof {
+			labels: self.labels.iter().map(|layer| layer[index as usize]).collect(),
+			path: self.tree_c.path(index),
+		}
+	}
+
+	/// Prove the seal with the seed drawn by the chain.
+	pub fn prove_seal(&self, seed: &Node) -> SealProof {
+		let challenges = seal_challenges(&self.params, &self.replica_id, seed)
+			.into_iter()
+			.map(|index| ChallengeProof {
+				data: self.data[index as usize],
+				data_path: self.tree_d.path(index),
+				replica_path: self.tree_r_last.path(index),
+				column: self.column(index),
+				parents: parents(&self.params, &self.replica_id, index)
+					.into_iter()
+					.map(|p| self.column(p))
+					.collect(),
+			})
+			.collect();
+		SealProof { comm_c: self.tree_c.root(), comm_r_last: self.tree_r_last.root(), challenges }
+	}
+
+	/// Prove that the node `index` of the replica is kept.
+	pub fn prove_replica(&self, index: u64) -> ReplicaProof {
+		ReplicaProof {
+			comm_c: self.tree_c.root(),
+			comm_r_last: self.tree_r_last.root(),
+			node: self.tree_r_last.levels[0][index as usize],
+			path: self.tree_r_last.path(index),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const PARAMS: SectorParams = SectorParams { nodes: 64, window_nodes: 16 };
+
+	fn sealed(replica_id: Node) -> Replica {
+		let data = (0..PARAMS.nodes * NODE_SIZE - 7).map(|i| i as u8).collect::<Vec<_>>();
+		seal(PARAMS, replica_id, &data)
+	}
+
+	#[test]
+	fn seal_proof_works() {
+		let replica = sealed([1; 32]);
+		let (comm_r, comm_d) = (replica.comm_r(), replica.comm_d());
+		let proof = replica.prove_seal(&[2; 32]);
+		assert!(verify_seal(&PARAMS, &comm_r, &comm_d, &[1; 32], &[2; 32], &proof));
+		assert!(verify_seal_encoded(
+			&PARAMS,
+			&comm_r,
+			&comm_d,
+			&[1; 32],
+			&[2; 32],
+			&proof.encode()
+		));
+
+		// the proof is bound to the seed and to the replica id.
+		assert!(!verify_seal(&PARAMS, &comm_r, &comm_d, &[1; 32], &[3; 32], &proof));
+		assert!(!verify_seal(&PARAMS, &comm_r, &comm_d, &[4; 32], &[2; 32], &proof));
+		assert!(!verify_seal_encoded(&PARAMS, &comm_r, &comm_d, &[1; 32], &[2; 32], &[0; 8]));
+
+		// the data sealed with another replica id is another replica.
+		let other = sealed([4; 32]);
+		assert_eq!(other.comm_d(), comm_d);
+		assert_ne!(other.comm_r(), comm_r);
+		let proof = other.prove_seal(&[2; 32]);
+		assert!(!verify_seal(&PARAMS, &comm_r, &comm_d, &[1; 32], &[2; 32], &proof));
+	}
+
+	#[test]
+	fn comm_p_is_the_root_of_the_padded_data() {
+		let data = (0..PARAMS.nodes * NODE_SIZE - 7).map(|i| i as u8).collect::<Vec<_>>();
+		assert_eq!(comm_p(&data), sealed([1; 32]).comm_d());
+		assert_eq!(comm_p(&data[..33]), comm_p(&[&data[..33], &[0; 31][..]].concat()));
+		assert_ne!(comm_p(&data[..33]), comm_p(&data[..32]));
+	}
+
+	#[test]
+	fn forged_labels_are_rejected() {
+		let replica = sealed([1; 32]);
+		let (comm_r, comm_d) = (replica.comm_r(), replica.comm_d());
+		let mut proof = replica.prove_seal(&[2; 32]);
+		let challenge = proof
+			.challenges
+			.iter_mut()
+			.find(|c| !c.parents.is_empty())
+			.expect("a node with parents is challenged");
+		challenge.parents[0].labels[0][0] ^= 1;
+		assert!(!verify_seal(&PARAMS, &comm_r, &comm_d, &[1; 32], &[2; 32], &proof));
+	}
+
+	#[test]
+	fn replica_proof_works() {
+		let replica = sealed([1; 32]);
+		let comm_r = replica.comm_r();
+		for index in 0..PARAMS.nodes {
+			let proof = replica.prove_replica(index);
+			assert!(verify_replica(&PARAMS, &comm_r, index, &proof));
+			assert!(!verify_replica(&PARAMS, &comm_r, (index + 1) % PARAMS.nodes, &proof));
+		}
+		let mut proof = replica.prove_replica(5);
+		proof.node[0] ^= 1;
+		assert!(!verify_replica(&PARAMS, &comm_r, 5, &proof));
+	}
+}
@@ -0,0 +1,318 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! RPC methods to query the ipse storage market.
+
+use std::{convert::TryInto, sync::Arc};
+
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use node_primitives::Balance;
+use node_runtime::ipse::{
+	DataClass, Miner, MinerDetails, MinerOrder, Order, OrderId, Quote, Reputation,
+	RetrievalFrequency,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{
+	codec::Codec,
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor},
+	Permill,
+};
+
+pub use self::gen_client::Client as IpseClient;
+pub use node_runtime::ipse::IpseApi as IpseRuntimeApi;
+
+const RUNTIME_ERROR: i64 = 1;
+
+/// Ipse RPC methods.
+///
+/// The orders are returned in pages of at most `MAX_PAGE_LEN` orders, `start` is the index of
+/// the first order of the page. The balances are numbers if they fit in 64 bits, otherwise hex
+/// strings.
+#[rpc]
+pub trait IpseApi<BlockHash, AccountId, BlockNumber> {
+	/// Returns a page of the orders of the user.
+	#[rpc(name = "ipse_userOrders")]
+	fn user_orders(
+		&self,
+		user: AccountId,
+		start: u32,
+		count: u32,
+		at: Option<BlockHash>,
+	) -> Result<Vec<(OrderId, Order<AccountId, NumberOrHex, BlockNumber>)>>;
+
+	/// Returns a page of the orders stored by the miner.
+	#[rpc(name = "ipse_minerOrders")]
+	fn miner_orders(
+		&self,
+		miner: AccountId,
+		start: u32,
+		count: u32,
+		at: Option<BlockHash>,
+	) -> Result<Vec<(OrderId, Order<AccountId, NumberOrHex, BlockNumber>)>>;
+
+	/// Returns the order, or `None` if it doesn't exist.
+	#[rpc(name = "ipse_order")]
+	fn order(
+		&self,
+		order_id: OrderId,
+		at: Option<BlockHash>,
+	) -> Result<Option<Order<AccountId, NumberOrHex, BlockNumber>>>;
+
+	/// Returns the miner with its free and used capacity, or `None` if it isn't registered.
+	#[rpc(name = "ipse_miner")]
+	fn miner(
+		&self,
+		miner: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<Option<MinerDetails<AccountId, NumberOrHex>>>;
+
+	/// Returns what the miner charges for storing `size` bytes of `class` retrieved at
+	/// `frequency` for `days` days, or `None` if the miner is not able to store it.
+	///
+	/// The days are rounded up to whole settlement periods.
+	#[rpc(name = "ipse_quote")]
+	fn quote(
+		&self,
+		miner: AccountId,
+		size: u128,
+		days: u32,
+		class: DataClass,
+		frequency: RetrievalFrequency,
+		at: Option<BlockHash>,
+	) -> Result<Option<Quote<NumberOrHex>>>;
+
+	/// Returns the recommend list ranked by the reserved amount blended with the reputation.
+	#[rpc(name = "ipse_recommendList")]
+	fn recommend_list(&self, at: Option<BlockHash>) -> Result<Vec<(AccountId, NumberOrHex)>>;
+
+	/// Returns the reputation facts of the miner and its score.
+	#[rpc(name = "ipse_minerReputation")]
+	fn miner_reputation(
+		&self,
+		miner: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<(Reputation, Permill)>;
+}
+
+/// An implementation of ipse specific RPC methods.
+pub struct Ipse<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Ipse<C, B> {
+	/// Create new `Ipse` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Ipse { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block> Ipse<C, Block>
+where
+	Block: BlockT,
+	C: HeaderBackend<Block>,
+{
+	/// The block to query, the best block if it is not supplied.
+	fn block_id(&self, at: Option<<Block as BlockT>::Hash>) -> BlockId<Block> {
+		BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash))
+	}
+}
+
+impl<C, Block, AccountId> IpseApi<<Block as BlockT>::Hash, AccountId, NumberFor<Block>>
+	for Ipse<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: IpseRuntimeApi<Block, AccountId, Balance, NumberFor<Block>>,
+	AccountId: Codec,
+{
+	fn user_orders(
+		&self,
+		user: AccountId,
+		start: u32,
+		count: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<(OrderId, Order<AccountId, NumberOrHex, NumberFor<Block>>)>> {
+		self.client
+			.runtime_api()
+			.user_orders(&self.block_id(at), user, start, count)
+			.map(|orders| orders.into_iter().map(|(id, order)| (id, rpc_order(order))).collect())
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn miner_orders(
+		&self,
+		miner: AccountId,
+		start: u32,
+		count: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<(OrderId, Order<AccountId, NumberOrHex, NumberFor<Block>>)>> {
+		self.client
+			.runtime_api()
+			.miner_orders(&self.block_id(at), miner, start, count)
+			.map(|orders| orders.into_iter().map(|(id, order)| (id, rpc_order(order))).collect())
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn order(
+		&self,
+		order_id: OrderId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<Order<AccountId, NumberOrHex, NumberFor<Block>>>> {
+		self.client
+			.runtime_api()
+			.order(&self.block_id(at), order_id)
+			.map(|order| order.map(rpc_order))
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn miner(
+		&self,
+		miner: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<MinerDetails<AccountId, NumberOrHex>>> {
+		self.client
+			.runtime_api()
+			.miner(&self.block_id(at), miner)
+			.map(|details| details.map(rpc_miner_details))
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn quote(
+		&self,
+		miner: AccountId,
+		size: u128,
+		days: u32,
+		class: DataClass,
+		frequency: RetrievalFrequency,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<Quote<NumberOrHex>>> {
+		self.client
+			.runtime_api()
+			.quote(&self.block_id(at), miner, size, days, class, frequency)
+			.map(|quote| {
+				quote.map(|q| Quote {
+					periods: q.periods,
+					period_price: number_or_hex(q.period_price),
+					total_price: number_or_hex(q.total_price),
+				})
+			})
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn recommend_list(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<(AccountId, NumberOrHex)>> {
+		self.client
+			.runtime_api()
+			.recommend_list(&self.block_id(at))
+			.map(|list| {
+				list.into_iter().map(|(miner, amount)| (miner, number_or_hex(amount))).collect()
+			})
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn miner_reputation(
+		&self,
+		miner: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<(Reputation, Permill)> {
+		self.client
+			.runtime_api()
+			.miner_reputation(&self.block_id(at), miner)
+			.map_err(runtime_error_into_rpc_err)
+	}
+}
+
+/// The balance as a number if it fits in 64 bits, JavaScript loses the precision of larger
+/// numbers, so they are hex strings.
+fn number_or_hex(balance: Balance) -> NumberOrHex {
+	balance.try_into().map(NumberOrHex::Number).unwrap_or_else(|_| NumberOrHex::Hex(balance.into()))
+}
+
+fn rpc_order<AccountId, BlockNumber>(
+	order: Order<AccountId, Balance, BlockNumber>,
+) -> Order<AccountId, NumberOrHex, BlockNumber> {
+	Order {
+		miner: order.miner,
+		label: order.label,
+		cid: order.cid,
+		size: order.size,
+		user: order.user,
+		orders: order
+			.orders
+			.into_iter()
+			.map(|o| MinerOrder {
+				miner: o.miner,
+				period_price: number_or_hex(o.period_price),
+				total_price: number_or_hex(o.total_price),
+				verify_result: o.verify_result,
+				verify_ts: o.verify_ts,
+				confirm_ts: o.confirm_ts,
+				url: o.url,
+			})
+			.collect(),
+		status: order.status,
+		create_ts: order.create_ts,
+		update_ts: order.update_ts,
+		start: order.start,
+		periods: order.periods,
+		settled: order.settled,
+		class: order.class,
+		frequency: order.frequency,
+	}
+}
+
+fn rpc_miner_details<AccountId>(
+	details: MinerDetails<AccountId, Balance>,
+) -> MinerDetails<AccountId, NumberOrHex> {
+	let miner = details.info;
+	MinerDetails {
+		info: Miner {
+			account_id: miner.account_id,
+			nickname: miner.nickname,
+			region: miner.region,
+			url: miner.url,
+			public_key: miner.public_key,
+			stash_address: miner.stash_address,
+			capacity: miner.capacity,
+			unit_price: number_or_hex(miner.unit_price),
+			violation_times: miner.violation_times,
+			total_staking: number_or_hex(miner.total_staking),
+			create_ts: miner.create_ts,
+			update_ts: miner.update_ts,
+			hot: miner.hot,
+			bandwidth: miner.bandwidth,
+		},
+		free_capacity: details.free_capacity,
+		used_capacity: details.used_capacity,
+		reputation: details.reputation,
+		score: details.score,
+	}
+}
+
+/// Converts a runtime trap into an RPC error.
+fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> Error {
+	Error {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Runtime trapped".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
@@ -0,0 +1,269 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Histories kept in ring buffers with a cap.
+//!
+//! The items of a history are stored one by one, keyed by their sequence number, and a cursor
+//! keeps the sequence numbers of the oldest and the next item. So an append writes the new item
+//! and drops the oldest one, instead of decoding and rewriting the whole `Vec`.
+//!
+//! `BoundedHistory` keeps a history per key in a `StorageMap` of cursors and a
+//! `StorageDoubleMap` of items, `BoundedList` keeps a single history in a `StorageValue` cursor
+//! and a `StorageMap` of items. The cap is given on every append, so it can be a parameter of the
+//! pallet, and lowering it drops the oldest items on the next append.
+
+use codec::{Decode, Encode, FullCodec, FullEncode};
+use frame_support::{StorageDoubleMap, StorageMap, StorageValue};
+use sp_std::{marker::PhantomData, prelude::*};
+
+/// The sequence numbers of the oldest item of a history and of the next item.
+///
+/// the sequence numbers wrap around, a history never holds `u32::max_value()` items.
+#[derive(Encode, Decode, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cursor {
+	pub start: u32,
+	pub end: u32,
+}
+
+impl Cursor {
+	pub fn len(&self) -> u32 {
+		self.end.wrapping_sub(self.start)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.start == self.end
+	}
+
+	/// the sequence numbers of the items, from the oldest to the latest.
+	pub fn seqs(&self) -> impl DoubleEndedIterator<Item = u32> {
+		let start = self.start;
+		(0..self.len()).map(move |i| start.wrapping_add(i))
+	}
+
+	/// take the sequence number of a new item.
+	fn push(&mut self) -> u32 {
+		let seq = self.end;
+		self.end = self.end.wrapping_add(1);
+		seq
+	}
+
+	/// give back the sequence number of the latest item.
+	fn pop(&mut self) -> Option<u32> {
+		if self.is_empty() {
+			return None
+		}
+		self.end = self.end.wrapping_sub(1);
+		Some(self.end)
+	}
+
+	/// drop the oldest item if there are more than `cap` items, its sequence number is returned.
+	fn trim(&mut self, cap: u32) -> Option<u32> {
+		if self.len() <= cap {
+			return None
+		}
+		let seq = self.start;
+		self.start = self.start.wrapping_add(1);
+		Some(seq)
+	}
+}
+
+/// A history per key, see the module docs.
+pub struct BoundedHistory<K, V, Cursors, Items>(PhantomData<(K, V, Cursors, Items)>);
+
+impl<K, V, Cursors, Items> BoundedHistory<K, V, Cursors, Items>
+where
+	K: FullEncode,
+	V: FullCodec,
+	Cursors: StorageMap<K, Cursor, Query = Cursor>,
+	Items: StorageDoubleMap<K, u32, V, Query = Option<V>>,
+{
+	/// Append the item to the history of `key`, the oldest items are dropped to keep at most
+	/// `cap` items.
+	pub fn append(key: &K, item: V, cap: u32) {
+		let mut cursor = Cursors::get(key);
+		Items::insert(key, cursor.push(), item);
+		while let Some(seq) = cursor.trim(cap) {
+			Items::remove(key, seq);
+		}
+		Self::put_cursor(key, cursor);
+	}
+
+	/// The items of the history, from the oldest to the latest.
+	pub fn items(key: &K) -> Vec<V> {
+		Cursors::get(key).seqs().filter_map(|seq| Items::get(key, seq)).collect()
+	}
+
+	/// At most `count` latest items of the history, from the latest.
+	pub fn latest(key: &K, count: usize) -> Vec<V> {
+		Cursors::get(key).seqs().rev().take(count).filter_map(|seq| Items::get(key, seq)).collect()
+	}
+
+	pub fn last(key: &K) -> Option<V> {
+		Cursors::get(key).seqs().next_back().and_then(|seq| Items::get(key, seq))
+	}
+
+	pub fn len(key: &K) -> u32 {
+		Cursors::get(key).len()
+	}
+
+	/// Remove the latest item of the history.
+	pub fn pop(key: &K) -> Option<V> {
+		let mut cursor = Cursors::get(key);
+		let item = cursor.pop().and_then(|seq| Items::take(key, seq));
+		Self::put_cursor(key, cursor);
+		item
+	}
+
+	/// Keep the items for which `f` returns true, it rewrites the whole history.
+	pub fn retain(key: &K, mut f: impl FnMut(&V) -> bool) {
+		let items = Self::items(key);
+		if items.iter().all(|item| f(item)) {
+			return
+		}
+		Self::clear(key);
+		for item in items.into_iter().filter(|item| f(item)) {
+			Self::append(key, item, u32::max_value());
+		}
+	}
+
+	pub fn clear(key: &K) {
+		Cursors::remove(key);
+		Items::remove_prefix(key);
+	}
+
+	fn put_cursor(key: &K, cursor: Cursor) {
+		if cursor.is_empty() {
+			Cursors::remove(key);
+		} else {
+			Cursors::insert(key, cursor);
+		}
+	}
+}
+
+/// A single history, see the module docs.
+pub struct BoundedList<V, Cursor_, Items>(PhantomData<(V, Cursor_, Items)>);
+
+impl<V, Cursor_, Items> BoundedList<V, Cursor_, Items>
+where
+	V: FullCodec,
+	Cursor_: StorageValue<Cursor, Query = Cursor>,
+	Items: StorageMap<u32, V, Query = Option<V>>,
+{
+	/// Append the item, the oldest items are dropped to keep at most `cap` items.
+	pub fn append(item: V, cap: u32) {
+		let mut cursor = Cursor_::get();
+		Items::insert(cursor.push(), item);
+		while let Some(seq) = cursor.trim(cap) {
+			Items::remove(seq);
+		}
+		Self::put_cursor(cursor);
+	}
+
+	/// The items, from the oldest to the latest.
+	pub fn items() -> Vec<V> {
+		Cursor_::get().seqs().filter_map(Items::get).collect()
+	}
+
+	/// At most `count` latest items, from the latest.
+	pub fn latest(count: usize) -> Vec<V> {
+		Cursor_::get().seqs().rev().take(count).filter_map(Items::get).collect()
+	}
+
+	pub fn last() -> Option<V> {
+		Cursor_::get().seqs().next_back().and_then(Items::get)
+	}
+
+	pub fn len() -> u32 {
+		Cursor_::get().len()
+	}
+
+	/// Remove the latest item.
+	pub fn pop() -> Option<V> {
+		let mut cursor = Cursor_::get();
+		let item = cursor.pop().and_then(Items::take);
+		Self::put_cursor(cursor);
+		item
+	}
+
+	/// Keep the items for which `f` returns true, it rewrites the whole list.
+	pub fn retain(mut f: impl FnMut(&V) -> bool) {
+		let items = Self::items();
+		if items.iter().all(|item| f(item)) {
+			return
+		}
+		Self::clear();
+		for item in items.into_iter().filter(|item| f(item)) {
+			Self::append(item, u32::max_value());
+		}
+	}
+
+	pub fn clear() {
+		for seq in Cursor_::take().seqs() {
+			Items::remove(seq);
+		}
+	}
+
+	fn put_cursor(cursor: Cursor) {
+		if cursor.is_empty() {
+			Cursor_::kill();
+		} else {
+			Cursor_::put(cursor);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn cursor_keeps_the_latest_items() {
+		let mut cursor = Cursor::default();
+		for i in 0..5 {
+			assert_eq!(cursor.push(), i);
+			assert_eq!(cursor.trim(3), i.checked_sub(3));
+			assert_eq!(cursor.trim(3), None);
+		}
+		assert_eq!(cursor.seqs().collect::<Vec<_>>(), vec![2, 3, 4]);
+
+		// a lower cap drops the oldest items at once.
+		cursor.push();
+		assert_eq!(cursor.trim(1), Some(2));
+		assert_eq!(cursor.trim(1), Some(3));
+		assert_eq!(cursor.trim(1), Some(4));
+		assert_eq!(cursor.trim(1), None);
+		assert_eq!(cursor.seqs().collect::<Vec<_>>(), vec![5]);
+
+		assert_eq!(cursor.pop(), Some(5));
+		assert_eq!(cursor.pop(), None);
+		assert!(cursor.is_empty());
+	}
+
+	#[test]
+	fn cursor_wraps_around() {
+		let mut cursor = Cursor { start: u32::max_value() - 1, end: u32::max_value() - 1 };
+		for _ in 0..4 {
+			cursor.push();
+			cursor.trim(3);
+		}
+		assert_eq!(cursor.len(), 3);
+		assert_eq!(cursor.seqs().collect::<Vec<_>>(), vec![u32::max_value(), 0, 1]);
+		assert_eq!(cursor.seqs().rev().next(), Some(1));
+		assert_eq!(cursor.pop(), Some(1));
+		assert_eq!(cursor.pop(), Some(0));
+		assert_eq!(cursor.pop(), Some(u32::max_value()));
+		assert_eq!(cursor.pop(), None);
+	}
+}
@@ -0,0 +1,454 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The content identifier (CID) of the data stored in ipse.
+//!
+//! CIDv0 is the base58btc string of a sha2-256 multihash (`Qm...`).
+//! CIDv1 is `<multibase prefix><version><codec><multihash>`, the numbers are unsigned varints,
+//! its canonical string is base32 (`b...`).
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use sp_std::{fmt, vec, vec::Vec};
+
+#[cfg(feature = "std")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// the codec of CIDv0, merkle dag protobuf.
+pub const DAG_PB: u64 = 0x70;
+/// the codec of raw bytes.
+pub const RAW: u64 = 0x55;
+/// the multihash code of the identity hash, the digest is the data itself.
+pub const IDENTITY: u64 = 0x00;
+/// the multihash code of sha2-256.
+pub const SHA2_256: u64 = 0x12;
+/// the max length of a digest.
+pub const MAX_DIGEST_LEN: usize = 64;
+/// the max length of the string of a CID, the 32 bytes digests fit in any base, the 64 bytes
+/// ones in base58btc.
+///
+/// the base58 decoding is quadratic, the longer strings are rejected before being decoded.
+pub const MAX_TEXT_LEN: usize = 128;
+// a u64 varint has 9 bytes at most in multiformats.
+const MAX_VARINT_LEN: usize = 9;
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+const BASE16_ALPHABET: &[u8; 16] = b"0123456789abcdef";
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Version {
+	V0,
+	V1,
+}
+
+/// A self-describing hash, `<code><digest length><digest>`.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Multihash {
+	pub code: u64,
+	pub digest: Vec<u8>,
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Cid {
+	pub version: Version,
+	// the multicodec of the content
+	pub codec: u64,
+	pub hash: Multihash,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+	/// The multibase prefix is not supported.
+	UnknownBase,
+	/// The string has a character out of its base.
+	InvalidBase,
+	/// A varint is truncated or too long.
+	InvalidVarint,
+	/// The version is unknown, or CIDv0 has a multibase prefix.
+	InvalidVersion,
+	/// The multihash is truncated or its digest is too long.
+	InvalidMultihash,
+	/// The string is longer than `MAX_TEXT_LEN`.
+	TooLong,
+}
+
+impl Multihash {
+	pub fn new(code: u64, digest: Vec<u8>) -> Result<Self, Error> {
+		if digest.len() > MAX_DIGEST_LEN {
+			return Err(Error::InvalidMultihash)
+		}
+		Ok(Multihash { code, digest })
+	}
+
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = Vec::with_capacity(self.digest.len() + 2 * MAX_VARINT_LEN);
+		write_varint(&mut bytes, self.code);
+		write_varint(&mut bytes, self.digest.len() as u64);
+		bytes.extend_from_slice(&self.digest);
+		bytes
+	}
+
+	/// Read a multihash from the head of `input`.
+	fn read(input: &mut &[u8]) -> Result<Self, Error> {
+		let code = read_varint(input)?;
+		let len = read_varint(input)? as usize;
+		if len > MAX_DIGEST_LEN || input.len() < len {
+			return Err(Error::InvalidMultihash)
+		}
+		let (digest, rest) = input.split_at(len);
+		*input = rest;
+		Self::new(code, digest.to_vec())
+	}
+}
+
+impl Cid {
+	/// CIDv0, the hash must be sha2-256.
+	pub fn new_v0(hash: Multihash) -> Result<Self, Error> {
+		if hash.code != SHA2_256 || hash.digest.len() != 32 {
+			return Err(Error::InvalidMultihash)
+		}
+		Ok(Cid { version: Version::V0, codec: DAG_PB, hash })
+	}
+
+	pub fn new_v1(codec: u64, hash: Multihash) -> Self {
+		Cid { version: Version::V1, codec, hash }
+	}
+
+	/// Parse the string form of the CID.
+	///
+	/// CIDv1 can be in base32, base58btc or base16.
+	pub fn parse(s: &[u8]) -> Result<Self, Error> {
+		if s.len() > MAX_TEXT_LEN {
+			return Err(Error::TooLong)
+		}
+		if s.len() == 46 && s.starts_with(b"Qm") {
+			return Self::from_bytes(&base58_decode(s)?)
+		}
+		let (base, rest) = s.split_first().ok_or(Error::UnknownBase)?;
+		let bytes = match base {
+			b'b' => base32_decode(rest)?,
+			b'B' => base32_decode(&rest.to_ascii_lowercase())?,
+			b'z' => base58_decode(rest)?,
+			b'f' => base16_decode(rest)?,
+			b'F' => base16_decode(&rest.to_ascii_lowercase())?,
+			_ => return Err(Error::UnknownBase),
+		};
+		let cid = Self::from_bytes(&bytes)?;
+		if cid.version == Version::V0 {
+			return Err(Error::InvalidVersion)
+		}
+		Ok(cid)
+	}
+
+	/// Decode the binary form of the CID.
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+		// CIDv0 is just the sha2-256 multihash.
+		if bytes.len() == 34 && bytes[0] == SHA2_256 as u8 && bytes[1] == 32 {
+			let mut input = bytes;
+			return Self::new_v0(Multihash::read(&mut input)?)
+		}
+
+		let mut input = bytes;
+		if read_varint(&mut input)? != 1 {
+			return Err(Error::InvalidVersion)
+		}
+		let codec = read_varint(&mut input)?;
+		let hash = Multihash::read(&mut input)?;
+		if !input.is_empty() {
+			return Err(Error::InvalidMultihash)
+		}
+		Ok(Self::new_v1(codec, hash))
+	}
+
+	/// The CID of a hash that was kept as a raw 46 bytes array.
+	///
+	/// It is a CIDv0 string usually, the bytes that are not a CID are kept as the identity
+	/// multihash of a raw CIDv1.
+	pub fn from_legacy(hash: &[u8]) -> Self {
+		Self::parse(hash).unwrap_or_else(|_| {
+			Self::new_v1(RAW, Multihash { code: IDENTITY, digest: hash.to_vec() })
+		})
+	}
+
+	pub fn to_bytes(&self) -> Vec<u8> {
+		match self.version {
+			Version::V0 => self.hash.to_bytes(),
+			Version::V1 => {
+				let mut bytes = Vec::new();
+				write_varint(&mut bytes, 1);
+				write_varint(&mut bytes, self.codec);
+				bytes.extend_from_slice(&self.hash.to_bytes());
+				bytes
+			},
+		}
+	}
+
+	/// The string form in base58btc, CIDv1 has the multibase prefix `z`.
+	pub fn to_base58(&self) -> Vec<u8> {
+		let mut text = match self.version {
+			Version::V0 => Vec::new(),
+			Version::V1 => vec![b'z'],
+		};
+		text.extend(base58_encode(&self.to_bytes()));
+		text
+	}
+
+	/// The canonical string form, base58btc for CIDv0 and base32 for CIDv1.
+	pub fn to_text(&self) -> Vec<u8> {
+		match self.version {
+			Version::V0 => base58_encode(&self.to_bytes()),
+			Version::V1 => {
+				let mut text = vec![b'b'];
+				text.extend_from_slice(&base32_encode(&self.to_bytes()));
+				text
+			},
+		}
+	}
+}
+
+impl fmt::Display for Cid {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let text = self.to_text();
+		f.write_str(sp_std::str::from_utf8(&text).map_err(|_| fmt::Error)?)
+	}
+}
+
+#[cfg(feature = "std")]
+impl Serialize for Cid {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&self.to_string())
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for Cid {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let s = String::deserialize(deserializer)?;
+		Cid::parse(s.as_bytes()).map_err(|e| de::Error::custom(format!("invalid cid: {:?}", e)))
+	}
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut n: u64) {
+	while n >= 0x80 {
+		bytes.push((n as u8) | 0x80);
+		n >>= 7;
+	}
+	bytes.push(n as u8);
+}
+
+fn read_varint(input: &mut &[u8]) -> Result<u64, Error> {
+	let mut n = 0u64;
+	for (i, b) in input.iter().enumerate().take(MAX_VARINT_LEN) {
+		n |= ((b & 0x7f) as u64) << (7 * i);
+		if b & 0x80 == 0 {
+			*input = &input[i + 1..];
+			return Ok(n)
+		}
+	}
+	Err(Error::InvalidVarint)
+}
+
+fn base58_encode(bytes: &[u8]) -> Vec<u8> {
+	let zeros = bytes.iter().take_while(|b| **b == 0).count();
+	// little endian digits
+	let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+	for b in &bytes[zeros..] {
+		let mut carry = *b as u32;
+		for d in digits.iter_mut() {
+			carry += (*d as u32) << 8;
+			*d = (carry % 58) as u8;
+			carry /= 58;
+		}
+		while carry > 0 {
+			digits.push((carry % 58) as u8);
+			carry /= 58;
+		}
+	}
+	let mut text = vec![BASE58_ALPHABET[0]; zeros];
+	text.extend(digits.iter().rev().map(|d| BASE58_ALPHABET[*d as usize]));
+	text
+}
+
+fn base58_decode(text: &[u8]) -> Result<Vec<u8>, Error> {
+	let zeros = text.iter().take_while(|c| **c == BASE58_ALPHABET[0]).count();
+	// little endian bytes
+	let mut bytes: Vec<u8> = Vec::with_capacity(text.len() * 733 / 1000 + 1);
+	for c in &text[zeros..] {
+		let mut carry =
+			BASE58_ALPHABET.iter().position(|a| a == c).ok_or(Error::InvalidBase)? as u32;
+		for b in bytes.iter_mut() {
+			carry += (*b as u32) * 58;
+			*b = carry as u8;
+			carry >>= 8;
+		}
+		while carry > 0 {
+			bytes.push(carry as u8);
+			carry >>= 8;
+		}
+	}
+	let mut decoded = vec![0u8; zeros];
+	decoded.extend(bytes.iter().rev());
+	Ok(decoded)
+}
+
+fn base32_encode(bytes: &[u8]) -> Vec<u8> {
+	let mut text = Vec::with_capacity((bytes.len() * 8 + 4) / 5);
+	let (mut buffer, mut bits) = (0u32, 0u32);
+	for b in bytes {
+		buffer = (buffer << 8) | *b as u32;
+		bits += 8;
+		while bits >= 5 {
+			bits -= 5;
+			text.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize]);
+		}
+	}
+	if bits > 0 {
+		text.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize]);
+	}
+	text
+}
+
+fn base32_decode(text: &[u8]) -> Result<Vec<u8>, Error> {
+	let mut bytes = Vec::with_capacity(text.len() * 5 / 8);
+	let (mut buffer, mut bits) = (0u32, 0u32);
+	for c in text {
+		let v = BASE32_ALPHABET.iter().position(|a| a == c).ok_or(Error::InvalidBase)? as u32;
+		buffer = (buffer << 5) | v;
+		bits += 5;
+		if bits >= 8 {
+			bits -= 8;
+			bytes.push((buffer >> bits) as u8);
+		}
+	}
+	// the padding bits must be zero.
+	if bits >= 5 || buffer & ((1 << bits) - 1) != 0 {
+		return Err(Error::InvalidBase)
+	}
+	Ok(bytes)
+}
+
+fn base16_decode(text: &[u8]) -> Result<Vec<u8>, Error> {
+	if text.len() % 2 != 0 {
+		return Err(Error::InvalidBase)
+	}
+	let digit = |c: &u8| {
+		BASE16_ALPHABET.iter().position(|a| a == c).map(|v| v as u8).ok_or(Error::InvalidBase)
+	};
+	text.chunks(2).map(|pair| Ok(digit(&pair[0])? << 4 | digit(&pair[1])?)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const V0: &[u8] = b"QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+	const V1: &[u8] = b"bafybeihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku";
+
+	#[test]
+	fn parses_cid_v0() {
+		let cid = Cid::parse(V0).unwrap();
+		assert_eq!(cid.version, Version::V0);
+		assert_eq!(cid.codec, DAG_PB);
+		assert_eq!(cid.hash.code, SHA2_256);
+		assert_eq!(cid.hash.digest.len(), 32);
+		assert_eq!(cid.to_text(), V0.to_vec());
+	}
+
+	#[test]
+	fn parses_cid_v1() {
+		let cid = Cid::parse(V1).unwrap();
+		assert_eq!(cid.version, Version::V1);
+		assert_eq!(cid.codec, DAG_PB);
+		assert_eq!(cid.to_text(), V1.to_vec());
+		assert_eq!(cid.to_string().as_bytes(), V1);
+
+		// the same content as the CIDv0.
+		assert_eq!(cid.hash, Cid::parse(V0).unwrap().hash);
+	}
+
+	#[test]
+	fn parses_other_bases() {
+		let cid = Cid::parse(V1).unwrap();
+		let bytes = cid.to_bytes();
+
+		let mut z = vec![b'z'];
+		z.extend(base58_encode(&bytes));
+		assert_eq!(cid.to_base58(), z);
+		assert_eq!(Cid::parse(&z), Ok(cid.clone()));
+
+		let mut f = vec![b'f'];
+		f.extend(bytes.iter().flat_map(|b| {
+			vec![BASE16_ALPHABET[(b >> 4) as usize], BASE16_ALPHABET[(b & 0xf) as usize]]
+		}));
+		assert_eq!(Cid::parse(&f), Ok(cid.clone()));
+		assert_eq!(Cid::parse(&f.to_ascii_uppercase()), Ok(cid.clone()));
+		assert_eq!(Cid::parse(&V1.to_ascii_uppercase()), Ok(cid));
+	}
+
+	#[test]
+	fn rejects_invalid_cids() {
+		assert_eq!(Cid::parse(b""), Err(Error::UnknownBase));
+		assert_eq!(Cid::parse(b"xafy"), Err(Error::UnknownBase));
+		assert_eq!(
+			Cid::parse(b"QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR10"),
+			Err(Error::InvalidBase)
+		);
+		assert_eq!(Cid::parse(b"bafy1"), Err(Error::InvalidBase));
+		// truncated digest.
+		assert_eq!(Cid::parse(&V1[..V1.len() - 8]), Err(Error::InvalidMultihash));
+		// CIDv0 must not have a multibase prefix.
+		let mut z = vec![b'z'];
+		z.extend_from_slice(&V0[..]);
+		assert_eq!(Cid::parse(&z), Err(Error::InvalidVersion));
+	}
+
+	#[test]
+	fn rejects_long_strings() {
+		let longest = Cid::new_v1(RAW, Multihash::new(IDENTITY, vec![0xff; 64]).unwrap());
+		let text = longest.to_base58();
+		assert!(text.len() <= MAX_TEXT_LEN);
+		assert_eq!(Cid::parse(&text), Ok(longest));
+
+		let mut long = b"z".to_vec();
+		long.resize(MAX_TEXT_LEN + 1, b'2');
+		assert_eq!(Cid::parse(&long), Err(Error::TooLong));
+	}
+
+	#[test]
+	fn keeps_legacy_hashes() {
+		assert_eq!(Cid::from_legacy(V0), Cid::parse(V0).unwrap());
+
+		let raw = [7u8; 46];
+		let cid = Cid::from_legacy(&raw);
+		assert_eq!(cid.codec, RAW);
+		assert_eq!(cid.hash, Multihash { code: IDENTITY, digest: raw.to_vec() });
+		assert_eq!(Cid::parse(&cid.to_text()), Ok(cid));
+	}
+
+	#[test]
+	fn varint_works() {
+		for n in [0u64, 1, 127, 128, 300, 0xb220, u32::MAX as u64, (1 << 63) - 1].iter() {
+			let mut bytes = Vec::new();
+			write_varint(&mut bytes, *n);
+			let mut input = &bytes[..];
+			assert_eq!(read_varint(&mut input), Ok(*n));
+			assert!(input.is_empty());
+		}
+		let mut truncated = &[0x80u8, 0x80][..];
+		assert_eq!(read_varint(&mut truncated), Err(Error::InvalidVarint));
+	}
+}
@@ -55,8 +55,14 @@ use sp_runtime::{
 use crate::constants::currency;
 use crate::ocw_common::*;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
 const EOS_NODE_URL: &[u8] = b"http://localhost:8421/v1/eosio/tx/";
 
+/// the max number of notary keys.
+pub const MAX_NOTARY_KEYS: u32 = 50;
+
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 // type BalanceOf<T> = <<T as staking::Trait>::StakingCurrency as Currency<<T as
 // frame_system::Trait>::AccountId>>::Balance;
@@ -126,6 +132,36 @@ enum VerifyStatus {
 	Pass,
 }
 
+pub trait WeightInfo {
+	fn set_exchange_deadline() -> Weight;
+	fn set_notary_keys(k: u32) -> Weight;
+	fn del_notary_keys(k: u32) -> Weight;
+	fn exchange() -> Weight;
+	fn record_suc_verify() -> Weight;
+	fn record_fail_verify() -> Weight;
+}
+
+impl WeightInfo for () {
+	fn set_exchange_deadline() -> Weight {
+		1_000_000_000
+	}
+	fn set_notary_keys(_k: u32) -> Weight {
+		1_000_000_000
+	}
+	fn del_notary_keys(_k: u32) -> Weight {
+		1_000_000_000
+	}
+	fn exchange() -> Weight {
+		1_000_000_000
+	}
+	fn record_suc_verify() -> Weight {
+		1_000_000_000
+	}
+	fn record_fail_verify() -> Weight {
+		1_000_000_000
+	}
+}
+
 /// The module's configuration trait.
 pub trait Trait: system::Trait + timestamp::Trait + SendTransactionTypes<Call<Self>> {
 	/// The overarching event type.
@@ -155,6 +191,9 @@ pub trait Trait: system::Trait + timestamp::Trait + SendTransactionTypes<Call<Se
 	type OnUnbalanced: OnUnbalanced<PositiveImbalanceOf<Self>>;
 
 	type Currency: Currency<Self::AccountId>;
+
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
 }
 
 decl_error! {
@@ -254,18 +293,18 @@ decl_module! {
 		0
 	   }
 
-	 #[weight = 0]
+	 #[weight = <T as Trait>::WeightInfo::set_exchange_deadline()]
 	 fn SetExchangeDealine(origin, deadline_time: T::BlockNumber) -> DispatchResult{
 			ensure_root(origin)?;
 			<RootDeadlineTime<T>>::put(deadline_time);
 			Ok(())
 	 }
 
-	 #[weight = 0]
+	 #[weight = <T as Trait>::WeightInfo::set_notary_keys(MAX_NOTARY_KEYS)]
 	 fn set_notary_keys(origin, node: T::AccountId) -> DispatchResult{
 			ensure_root(origin)?;
 			<NotaryKeys<T>>::try_mutate(|keys| {
-			if keys.len()>=50{
+			if keys.len() >= MAX_NOTARY_KEYS as usize {
 				return Err(Error::<T>::OverMaximum)?;
 			}
 			if keys.contains(&node){
@@ -279,7 +318,7 @@ decl_module! {
 			Ok(())
 	 }
 
-	 #[weight = 0]
+	 #[weight = <T as Trait>::WeightInfo::del_notary_keys(MAX_NOTARY_KEYS)]
 	 fn del_notary_keys(origin, node: T::AccountId) -> DispatchResult{
 		ensure_root(origin)?;
 		let keys = NotaryKeys::<T>::get();
@@ -295,7 +334,7 @@ decl_module! {
 	 }
 
 
-	 #[weight = 10_000]
+	 #[weight = <T as Trait>::WeightInfo::exchange()]
 	 fn exchange(origin, tx: Vec<u8>) -> DispatchResult{
 		// let deadline_blocknum = sp_std::cmp::max(<RootDeadlineTime<T>>::get(),T::Deadline::get());
 		let deadline_blocknum = <RootDeadlineTime<T>>::get();
@@ -326,7 +365,7 @@ decl_module! {
 	 }
 
 
-	#[weight = 0]
+	#[weight = <T as Trait>::WeightInfo::record_suc_verify()]
 	fn record_suc_verify(
 	  origin,
 	  block_num: T::BlockNumber,
@@ -381,7 +420,7 @@ decl_module! {
 	  Ok(())
 	}
 
-	#[weight = 0]
+	#[weight = <T as Trait>::WeightInfo::record_fail_verify()]
 	fn record_fail_verify(
 		origin,
 		block: T::BlockNumber,
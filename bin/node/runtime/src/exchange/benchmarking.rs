@@ -0,0 +1,116 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks of the exchange module.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_support::{StorageMap, StorageValue};
+use frame_system::RawOrigin;
+use sp_runtime::traits::{Bounded, TrailingZeroInput};
+
+const SEED: u32 = 0;
+
+/// a transaction id of the EOS chain.
+fn eos_tx(i: u32) -> Vec<u8> {
+	let mut tx = vec![0xee; 32];
+	tx[..4].copy_from_slice(&i.to_le_bytes());
+	tx
+}
+
+fn notary_keys<T: Trait>(k: u32) {
+	let keys = (0..k).map(|i| account("notary", i, SEED)).collect::<Vec<T::AccountId>>();
+	NotaryKeys::<T>::put(keys);
+}
+
+fn signature<T: Trait>() -> <T::AuthorityId as RuntimeAppPublic>::Signature {
+	Decode::decode(&mut TrailingZeroInput::new(&[][..])).expect("zeroes decode a signature")
+}
+
+benchmarks! {
+	_ { }
+
+	set_exchange_deadline {
+		let deadline = T::BlockNumber::max_value();
+	}: SetExchangeDealine(RawOrigin::Root, deadline)
+	verify {
+		assert_eq!(RootDeadlineTime::<T>::get(), deadline);
+	}
+
+	set_notary_keys {
+		let k in 0 .. MAX_NOTARY_KEYS - 1;
+		notary_keys::<T>(k);
+		let node: T::AccountId = account("node", 0, SEED);
+	}: _(RawOrigin::Root, node.clone())
+	verify {
+		assert!(NotaryKeys::<T>::get().contains(&node));
+	}
+
+	del_notary_keys {
+		let k in 1 .. MAX_NOTARY_KEYS;
+		notary_keys::<T>(k);
+		let node: T::AccountId = account("notary", k - 1, SEED);
+	}: _(RawOrigin::Root, node.clone())
+	verify {
+		assert!(!NotaryKeys::<T>::get().contains(&node));
+	}
+
+	exchange {
+		RootDeadlineTime::<T>::put(T::BlockNumber::max_value());
+		let caller: T::AccountId = whitelisted_caller();
+		let tx = eos_tx(0);
+	}: _(RawOrigin::Signed(caller), tx.clone())
+	verify {
+		assert_eq!(TokenStatus::<T>::get(&tx).0, 1000);
+	}
+
+	// the last verification passes, the token is created.
+	record_suc_verify {
+		let account: T::AccountId = account("notary", 0, SEED);
+		let receiver: T::AccountId = account("receiver", 0, SEED);
+		let key = T::AuthorityId::from([0u8; 32]);
+		let tx = eos_tx(0);
+		TokenStatus::<T>::insert(&tx, (1290, receiver));
+		TokenStatusLen::put(1);
+		let block_num = frame_system::Module::<T>::block_number();
+	}: _(RawOrigin::None, block_num, account, key, tx.clone(), 0, 1000, signature::<T>())
+	verify {
+		assert_eq!(SucTxExchange::get(&tx), Some(true));
+	}
+
+	// the last verification fails, the failures of the notary are full.
+	record_fail_verify {
+		let account: T::AccountId = account("notary", 0, SEED);
+		let receiver: T::AccountId = account("receiver", 0, SEED);
+		let key = T::AuthorityId::from([0u8; 32]);
+		let tx = eos_tx(0);
+		TokenStatus::<T>::insert(&tx, (1060, receiver));
+		TokenStatusLen::put(1);
+		let block_num = frame_system::Module::<T>::block_number();
+		let failures = (0..51).map(|i| FetchFailedOf::<T> {
+			block_num,
+			tx: eos_tx(i),
+			err: vec![0; 32],
+		}).collect::<Vec<_>>();
+		FetchFailed::<T>::insert(&account, failures);
+		let err = vec![0; 32];
+	}: _(RawOrigin::None, block_num, account.clone(), key, tx.clone(), err, signature::<T>())
+	verify {
+		assert!(!TokenStatus::<T>::contains_key(&tx));
+		assert_eq!(FetchFailed::<T>::get(&account).len(), 51);
+	}
+}
@@ -18,23 +18,59 @@
 extern crate frame_system as system;
 extern crate pallet_timestamp as timestamp;
 
-use codec::{Decode, Encode};
-use frame_support::traits::{BalanceStatus, Currency, Get, ReservableCurrency};
+use codec::{Codec, Decode, Encode};
+use frame_support::traits::{
+	BalanceStatus, Currency, EnsureOrigin, Get, Imbalance, OnUnbalanced, Randomness,
+	ReservableCurrency,
+};
 use frame_support::{
-	debug, decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure,
-	StorageMap, StorageValue,
+	debug, decl_error, decl_event, decl_module, decl_storage,
+	dispatch::DispatchResult,
+	ensure,
+	storage::{
+		migration::{
+			have_storage_value, put_storage_value, remove_storage_prefix, take_storage_value,
+			StorageIterator, StorageKeyIterator,
+		},
+		unhashed,
+	},
+	weights::Weight,
+	IterableStorageMap, Parameter, ReversibleStorageHasher, StorageMap, StorageValue,
+	Twox64Concat,
 };
 use sp_std::vec;
 
-use sp_runtime::{traits::SaturatedConversion, DispatchError, ModuleId};
+use frame_system::offchain::{SendTransactionTypes, SubmitTransaction};
+use sp_runtime::{
+	helpers_128bit,
+	traits::{
+		CheckedAdd, CheckedMul, IdentifyAccount, SaturatedConversion, Saturating, Verify, Zero,
+	},
+	transaction_validity::{
+		InvalidTransaction, TransactionPriority, TransactionSource, TransactionValidity,
+		ValidTransaction,
+	},
+	DispatchError, ModuleId, Permill,
+};
 
+use crate::bounded_history::{BoundedHistory, BoundedList, Cursor};
+use crate::cid::Cid;
+use crate::ipse_traits::PriceProvider;
 use core::{u128, u64};
+use ipse_io::{porep, NODE_SIZE, SECTOR_SIZE};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 use sp_runtime::traits::AccountIdConversion;
-use sp_std::{convert::TryInto, result, vec::Vec};
-use system::ensure_signed;
+use sp_std::{convert::{TryFrom, TryInto}, result, vec::Vec};
+use system::{ensure_none, ensure_signed};
 // use pallet_staking as staking;
 // use pallet_balances as balances;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+
 pub const KB: u64 = 1024;
 
 pub const MB: u64 = 1024 * 1024;
@@ -44,22 +80,181 @@ pub const BASIC_BALANCE: u64 = 100000000000000;
 // When whose times of violation is more than 3,
 // slash all funds of this miner.
 pub const MAX_VIOLATION_TIMES: u64 = 3;
-// millisecond * sec * min * hour
-// pub const DAY: u64 = 1000 * 60 * 60 * 24;
-pub const DAY: u64 = 1000 * 60 * 60 * 24;
-// max list order len
-pub const NUM_LIST_ORDER_LEN: usize = 500;
-// history len
-pub const NUM_LIST_HISTORY_LEN: usize = 500;
+// the size of a chunk that a miner proves to hold (byte)
+pub const CHUNK_SIZE: u128 = 1024;
+// the max number of pieces in a sector
+pub const MAX_PIECES: u32 = 16;
+// how many bids that the offchain worker matches per block
+pub const NUM_MATCH_PER_BLOCK: usize = 10;
+// the max number of orders that a query returns at once
+pub const MAX_PAGE_LEN: u32 = 100;
+
+/// the order id, it increases monotonically from 0.
+pub type OrderId = u64;
+
+/// the bid id, it increases monotonically from 0.
+pub type BidId = u64;
+
+/// the retrieval deal id, it increases monotonically from 0.
+pub type DealId = u64;
+
+/// the sector id, chosen by the miner, unique among the sectors of the miner.
+pub type SectorId = u64;
 
 pub type BalanceOf<T> =
 	<<T as Trait>::StakingCurrency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
 
+pub type OrderOf<T> = Order<
+	<T as frame_system::Trait>::AccountId,
+	BalanceOf<T>,
+	<T as frame_system::Trait>::BlockNumber,
+>;
+
+pub type SectorOf<T> = Sector<<T as frame_system::Trait>::BlockNumber>;
+
 pub type NegativeImbalanceOf<T> = <<T as Trait>::StakingCurrency as Currency<
 	<T as frame_system::Trait>::AccountId,
 >>::NegativeImbalance;
 
-pub trait Trait: system::Trait + timestamp::Trait {
+type MinerHistoryOf<T> = BoundedHistory<
+	<T as frame_system::Trait>::AccountId,
+	OrderId,
+	MinerHistoryCursors<T>,
+	MinerHistoryItems<T>,
+>;
+
+type HistoryOf<T> = BoundedHistory<
+	<T as frame_system::Trait>::AccountId,
+	(<T as frame_system::Trait>::BlockNumber, BalanceOf<T>),
+	HistoryCursors<T>,
+	HistoryItems<T>,
+>;
+
+type ListOrderOf = BoundedList<OrderId, ListOrderCursor, ListOrderItems>;
+
+pub trait WeightInfo {
+	fn register_miner() -> Weight;
+	fn update_miner() -> Weight;
+	fn add_capacity() -> Weight;
+	fn reduce_capacity() -> Weight;
+	fn update_capabilities() -> Weight;
+	fn set_reference_price() -> Weight;
+	fn unregister_miner() -> Weight;
+	fn withdraw_unbonded() -> Weight;
+	fn create_order(r: u32, c: u32) -> Weight;
+	fn precommit_sector(p: u32) -> Weight;
+	fn prove_commit_sector() -> Weight;
+	fn delete_order(r: u32) -> Weight;
+	fn renew_order(r: u32) -> Weight;
+	fn set_auto_renew() -> Weight;
+	fn open_retrieval() -> Weight;
+	fn claim_retrieval() -> Weight;
+	fn dispute_retrieval() -> Weight;
+	fn close_retrieval() -> Weight;
+	fn verify_storage() -> Weight;
+	fn open_dispute() -> Weight;
+	fn prove_dispute(p: u32) -> Weight;
+	fn arbitrate() -> Weight;
+	fn publish_ask() -> Weight;
+	fn cancel_ask() -> Weight;
+	fn publish_bid(c: u32) -> Weight;
+	fn cancel_bid() -> Weight;
+	fn submit_match(r: u32) -> Weight;
+	fn apply_to_recommended_list() -> Weight;
+	fn drop_out_recommended_list() -> Weight;
+}
+
+impl WeightInfo for () {
+	fn register_miner() -> Weight {
+		1_000_000_000
+	}
+	fn update_miner() -> Weight {
+		1_000_000_000
+	}
+	fn add_capacity() -> Weight {
+		1_000_000_000
+	}
+	fn reduce_capacity() -> Weight {
+		1_000_000_000
+	}
+	fn update_capabilities() -> Weight {
+		1_000_000_000
+	}
+	fn set_reference_price() -> Weight {
+		1_000_000_000
+	}
+	fn unregister_miner() -> Weight {
+		1_000_000_000
+	}
+	fn withdraw_unbonded() -> Weight {
+		1_000_000_000
+	}
+	fn create_order(_r: u32, _c: u32) -> Weight {
+		1_000_000_000
+	}
+	fn precommit_sector(_p: u32) -> Weight {
+		1_000_000_000
+	}
+	fn prove_commit_sector() -> Weight {
+		1_000_000_000
+	}
+	fn delete_order(_r: u32) -> Weight {
+		1_000_000_000
+	}
+	fn renew_order(_r: u32) -> Weight {
+		1_000_000_000
+	}
+	fn set_auto_renew() -> Weight {
+		1_000_000_000
+	}
+	fn open_retrieval() -> Weight {
+		1_000_000_000
+	}
+	fn claim_retrieval() -> Weight {
+		1_000_000_000
+	}
+	fn dispute_retrieval() -> Weight {
+		1_000_000_000
+	}
+	fn close_retrieval() -> Weight {
+		1_000_000_000
+	}
+	fn verify_storage() -> Weight {
+		1_000_000_000
+	}
+	fn open_dispute() -> Weight {
+		1_000_000_000
+	}
+	fn prove_dispute(_p: u32) -> Weight {
+		1_000_000_000
+	}
+	fn arbitrate() -> Weight {
+		1_000_000_000
+	}
+	fn publish_ask() -> Weight {
+		1_000_000_000
+	}
+	fn cancel_ask() -> Weight {
+		1_000_000_000
+	}
+	fn publish_bid(_c: u32) -> Weight {
+		1_000_000_000
+	}
+	fn cancel_bid() -> Weight {
+		1_000_000_000
+	}
+	fn submit_match(_r: u32) -> Weight {
+		1_000_000_000
+	}
+	fn apply_to_recommended_list() -> Weight {
+		1_000_000_000
+	}
+	fn drop_out_recommended_list() -> Weight {
+		1_000_000_000
+	}
+}
+
+pub trait Trait: system::Trait + timestamp::Trait + SendTransactionTypes<Call<Self>> {
 	/// default event
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 	/// currency
@@ -69,8 +264,83 @@ pub trait Trait: system::Trait + timestamp::Trait {
 
 	/// The treasury module account id to recycle assets.for default miner register
 	type TreasuryModuleId: Get<ModuleId>;
+
+	/// Handler for the slashed staking of miners.
+	type StakingSlash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// How many blocks that the staking is still reserved after the miner unregisters.
+	type UnbondingDuration: Get<Self::BlockNumber>;
+
+	/// Something that provides randomness in the runtime, used to pick the challenged chunk.
+	type Randomness: Randomness<Self::Hash>;
+
+	/// How many blocks that the miner has to answer a challenge.
+	type ChallengeDuration: Get<Self::BlockNumber>;
+
+	/// The max number of replicas of an order.
+	type MaxReplicas: Get<u32>;
+
+	/// The priority of the match submitted by the offchain worker.
+	type UnsignedPriority: Get<TransactionPriority>;
+
+	/// How many blocks a settlement period lasts, the miners are paid once per period.
+	type SettlementPeriod: Get<Self::BlockNumber>;
+
+	/// How many blocks that the expired or deleted orders are kept before being removed.
+	type OrderRetention: Get<Self::BlockNumber>;
+
+	/// How many blocks a retrieval deal lasts before the user can take the funds back.
+	type RetrievalTimeout: Get<Self::BlockNumber>;
+
+	/// The signature of the users on the retrieval vouchers.
+	type Signature: Verify<Signer = Self::Signer> + Parameter;
+
+	/// The signer of the retrieval vouchers.
+	type Signer: IdentifyAccount<AccountId = Self::AccountId>;
+
+	/// How much the reputation counts when the recommend list is ranked, zero ranks the
+	/// miners purely by the reserved amount.
+	type ReputationWeight: Get<Permill>;
+
+	/// The price of the native token, which converts the prices of the miners who quote in the
+	/// reference unit.
+	type PriceFeed: PriceProvider;
+
+	/// How many latest orders are kept for every miner.
+	type MinerHistoryLen: Get<u32>;
+
+	/// How many latest rewards are kept for every miner.
+	type HistoryLen: Get<u32>;
+
+	/// How many latest orders are kept.
+	type ListOrderLen: Get<u32>;
+
+	/// The origin which rules the disputes.
+	type ArbitrationOrigin: EnsureOrigin<Self::Origin>;
+
+	/// How many blocks that the miner has to prove the disputed chunks, and the arbitration
+	/// has to rule the dispute.
+	type DisputeWindow: Get<Self::BlockNumber>;
+
+	/// The deposit reserved from who raises a dispute, it goes to the other side if the
+	/// dispute is rejected.
+	type DisputeDeposit: Get<BalanceOf<Self>>;
+
+	/// The max number of chunks that the miner proves in a dispute.
+	type DisputeChunks: Get<u32>;
+
+	/// How many blocks after the pre-commit of a sector that the seed of its seal proof is
+	/// drawn.
+	type SealDelay: Get<Self::BlockNumber>;
+
+	/// How many blocks that the miner has to prove the seal after the seed is drawn.
+	type SealDuration: Get<Self::BlockNumber>;
+
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
 }
 
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
 pub struct Miner<AccountId, Balance> {
 	// account id
@@ -87,7 +357,7 @@ pub struct Miner<AccountId, Balance> {
 	pub stash_address: AccountId,
 	// capacity of data miner can store
 	pub capacity: u128,
-	// price per KB every day
+	// price per byte every settlement period
 	pub unit_price: Balance,
 	// times of violations
 	pub violation_times: u64,
@@ -97,16 +367,45 @@ pub struct Miner<AccountId, Balance> {
 	pub create_ts: u64,
 	// update timestamp
 	pub update_ts: u64,
+	// whether the miner stores hot data
+	pub hot: bool,
+	// the network of the miner's machine
+	pub bandwidth: BandwidthTier,
+}
+
+impl<AccountId, Balance> Miner<AccountId, Balance> {
+	/// whether the miner is able to store the data of the class and serve its retrievals.
+	pub fn can_store(&self, class: DataClass, frequency: RetrievalFrequency) -> bool {
+		(class == DataClass::Cold || self.hot) && self.bandwidth >= frequency.min_bandwidth()
+	}
+}
+
+/// The miner before it declares its capabilities.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+struct MinerV1<AccountId, Balance> {
+	account_id: AccountId,
+	nickname: Vec<u8>,
+	region: Vec<u8>,
+	url: Vec<u8>,
+	public_key: Vec<u8>,
+	stash_address: AccountId,
+	capacity: u128,
+	unit_price: Balance,
+	violation_times: u64,
+	total_staking: Balance,
+	create_ts: u64,
+	update_ts: u64,
 }
 
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
-pub struct Order<AccountId, Balance> {
+pub struct Order<AccountId, Balance, BlockNumber> {
 	// miner account id
 	pub miner: AccountId,
 	// the label of this data
 	pub label: Vec<u8>,
-	// the hash of data
-	pub hash: [u8; 46],
+	// the content id of data
+	pub cid: Cid,
 	// the size of storing data(byte)
 	pub size: u128,
 	pub user: AccountId,
@@ -116,16 +415,88 @@ pub struct Order<AccountId, Balance> {
 	pub create_ts: u64,
 	// last update-status timestamp
 	pub update_ts: u64,
-	// how long this data keep
-	pub duration: u64,
+	// the block when the order is created, the settlement periods start from it
+	pub start: BlockNumber,
+	// how many settlement periods this data keep
+	pub periods: u32,
+	// how many settlement periods are settled
+	pub settled: u32,
+	pub class: DataClass,
+	pub frequency: RetrievalFrequency,
+}
+
+impl<AccountId, Balance, BlockNumber> Order<AccountId, Balance, BlockNumber>
+where
+	BlockNumber: Copy + Saturating + From<u32>,
+{
+	/// the block when the settled periods end, i.e. the current period starts.
+	pub fn settled_until(&self, period: BlockNumber) -> BlockNumber {
+		self.start.saturating_add(period.saturating_mul(self.settled.into()))
+	}
+
+	/// the block when the order ends.
+	pub fn end(&self, period: BlockNumber) -> BlockNumber {
+		self.start.saturating_add(period.saturating_mul(self.periods.into()))
+	}
+}
+
+/// The order before the terms are expressed in blocks.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+struct OrderV2<AccountId, Balance> {
+	miner: AccountId,
+	label: Vec<u8>,
+	hash: [u8; 46],
+	size: u128,
+	user: AccountId,
+	orders: Vec<MinerOrder<AccountId, Balance>>,
+	status: OrderStatus,
+	create_ts: u64,
+	update_ts: u64,
+	// milliseconds
+	duration: u64,
+}
+
+/// The order before the data is addressed by the CID.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+struct OrderV3<AccountId, Balance, BlockNumber> {
+	miner: AccountId,
+	label: Vec<u8>,
+	hash: [u8; 46],
+	size: u128,
+	user: AccountId,
+	orders: Vec<MinerOrder<AccountId, Balance>>,
+	status: OrderStatus,
+	create_ts: u64,
+	update_ts: u64,
+	start: BlockNumber,
+	periods: u32,
+	settled: u32,
 }
 
+/// The order before it has the data class and the retrieval frequency.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+struct OrderV4<AccountId, Balance, BlockNumber> {
+	miner: AccountId,
+	label: Vec<u8>,
+	cid: Cid,
+	size: u128,
+	user: AccountId,
+	orders: Vec<MinerOrder<AccountId, Balance>>,
+	status: OrderStatus,
+	create_ts: u64,
+	update_ts: u64,
+	start: BlockNumber,
+	periods: u32,
+	settled: u32,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
 pub struct MinerOrder<AccountId, Balance> {
 	pub miner: AccountId,
-	// one day price = unit_price * data_length
-	pub day_price: Balance,
-	// total_price = day_price * days
+	// period_price = unit_price * data_length
+	pub period_price: Balance,
+	// total_price = period_price * periods
 	pub total_price: Balance,
 	// last verify result
 	pub verify_result: bool,
@@ -145,14 +516,412 @@ pub struct MiningHistory<Balance, BlockNumber> {
 	pub history: Vec<(BlockNumber, Balance)>,
 }
 
+/// hot data should be stored by the miners who have public ip,
+/// cold data can be stored anywhere.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataClass {
+	Hot,
+	Cold,
+}
+
+impl Default for DataClass {
+	fn default() -> Self {
+		Self::Cold
+	}
+}
+
+/// how often the data is retrieved.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetrievalFrequency {
+	Low,
+	Normal,
+	Frequent,
+}
+
+impl Default for RetrievalFrequency {
+	fn default() -> Self {
+		Self::Low
+	}
+}
+
+/// the network of the miner's machine.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BandwidthTier {
+	// no public ip
+	Private,
+	// public ip, at least 1M bandwidth
+	Public1M,
+	// public ip, at least 10M bandwidth
+	Public10M,
+}
+
+impl Default for BandwidthTier {
+	fn default() -> Self {
+		Self::Private
+	}
+}
+
+impl DataClass {
+	/// the price of the class, in percent of the base price.
+	pub fn price_percent(&self) -> u32 {
+		match self {
+			DataClass::Hot => 150,
+			DataClass::Cold => 100,
+		}
+	}
+}
+
+impl RetrievalFrequency {
+	/// the lowest bandwidth tier that can serve the data.
+	pub fn min_bandwidth(&self) -> BandwidthTier {
+		match self {
+			RetrievalFrequency::Low => BandwidthTier::Private,
+			RetrievalFrequency::Normal => BandwidthTier::Public1M,
+			RetrievalFrequency::Frequent => BandwidthTier::Public10M,
+		}
+	}
+
+	/// the price of the frequency, in percent of the base price.
+	pub fn price_percent(&self) -> u32 {
+		match self {
+			RetrievalFrequency::Low => 100,
+			RetrievalFrequency::Normal => 120,
+			RetrievalFrequency::Frequent => 150,
+		}
+	}
+
+	/// how many times the miners prove the storage every settlement period.
+	pub fn challenges_per_period(&self) -> u32 {
+		match self {
+			RetrievalFrequency::Low => 1,
+			RetrievalFrequency::Normal => 2,
+			RetrievalFrequency::Frequent => 4,
+		}
+	}
+}
+
+/// What the miner offers to the storage market.
+#[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ask<Balance> {
+	// price per byte every settlement period
+	pub unit_price: Balance,
+	// the capacity that the miner offers to the market
+	pub capacity: u128,
+	// where miner server locates
+	pub region: Vec<u8>,
+	// whether the miner stores hot data
+	pub hot: bool,
+	pub bandwidth: BandwidthTier,
+}
+
+/// What the user wants from the storage market.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Bid<AccountId, Balance> {
+	pub user: AccountId,
+	// the label of this data
+	pub label: Vec<u8>,
+	// the content id of data
+	pub cid: Cid,
+	// the size of storing data(byte)
+	pub size: u128,
+	// the CommP of the data
+	pub comm_p: [u8; 32],
+	// use to be read data
+	pub url: Option<Vec<u8>>,
+	// how many settlement periods this data keep
+	pub periods: u32,
+	pub replicas: u32,
+	// where the miners should locate, empty means anywhere
+	pub region: Vec<u8>,
+	pub class: DataClass,
+	pub frequency: RetrievalFrequency,
+	// the max price per byte every settlement period
+	pub unit_price: Balance,
+}
+
+impl<Balance: PartialOrd> Ask<Balance> {
+	/// whether the ask can serve the bid.
+	pub fn matches<AccountId>(&self, bid: &Bid<AccountId, Balance>) -> bool {
+		self.unit_price <= bid.unit_price &&
+			self.capacity >= bid.size &&
+			(bid.region.is_empty() || self.region == bid.region) &&
+			(bid.class == DataClass::Cold || self.hot) &&
+			self.bandwidth >= bid.frequency.min_bandwidth()
+	}
+}
+
+/// The user pays the miner for downloading the data of an order.
+///
+/// The user signs cumulative vouchers `(b"ipse/retrieval", deal id, bytes)` while the data is
+/// served, the miner claims the payment of the latest voucher.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct RetrievalDeal<AccountId, Balance, BlockNumber> {
+	pub order_id: OrderId,
+	pub user: AccountId,
+	pub miner: AccountId,
+	// price per GB
+	pub price: Balance,
+	// the max bytes the user pays for
+	pub max_size: u128,
+	// the bytes of the latest claimed voucher
+	pub claimed: u128,
+	// the user can close the deal after this block
+	pub deadline: BlockNumber,
+	// the user disputes the deal, the miner has to claim before the deadline
+	pub disputed: bool,
+}
+
+/// The facts about how well a miner keeps the data, from which its reputation is scored.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Reputation {
+	// the challenges answered with a valid proof
+	pub proofs_passed: u64,
+	// the challenges answered with an invalid proof or not answered in time
+	pub proofs_failed: u64,
+	// the settled periods of confirmed replicas that were proven
+	pub periods_proven: u64,
+	// the settled periods of confirmed replicas that were not proven
+	pub periods_missed: u64,
+	// the orders stored until they expired
+	pub orders_completed: u64,
+	// the orders deleted by the users while being stored
+	pub orders_deleted: u64,
+}
+
+impl Reputation {
+	/// the score of the miner, from zero to one.
+	///
+	/// it weights the proof success rate by 40%, the proven periods by 30%, the completed
+	/// orders by 20% and the violations by 10%, a rate without any fact counts as a half.
+	pub fn score(&self, violation_times: u64) -> Permill {
+		let rate = |good: u64, bad: u64| match good.saturating_add(bad) {
+			0 => Permill::from_percent(50),
+			total => Permill::from_rational_approximation(good, total),
+		};
+		let violations = Permill::from_rational_approximation(
+			violation_times.min(MAX_VIOLATION_TIMES),
+			MAX_VIOLATION_TIMES,
+		);
+		rate(self.proofs_passed, self.proofs_failed)
+			.saturating_mul(Permill::from_percent(40))
+			.saturating_add(
+				rate(self.periods_proven, self.periods_missed)
+					.saturating_mul(Permill::from_percent(30)),
+			)
+			.saturating_add(
+				rate(self.orders_completed, self.orders_deleted)
+					.saturating_mul(Permill::from_percent(20)),
+			)
+			.saturating_add(
+				Permill::one().saturating_sub(violations).saturating_mul(Permill::from_percent(10)),
+			)
+	}
+}
+
+/// The miner and how much of its capacity is used, returned by the queries.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct MinerDetails<AccountId, Balance> {
+	pub info: Miner<AccountId, Balance>,
+	// the capacity that is not used by orders
+	pub free_capacity: u128,
+	// the capacity used by the live orders
+	pub used_capacity: u128,
+	pub reputation: Reputation,
+	pub score: Permill,
+}
+
+/// What a miner charges for storing the data.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Quote<Balance> {
+	// how many settlement periods the data is kept
+	pub periods: u32,
+	// period_price = unit_price * data_length
+	pub period_price: Balance,
+	// total_price = period_price * periods
+	pub total_price: Balance,
+}
+
+/// A storage challenge, the miner must prove that it holds the node `node_index` of the
+/// replica of the sector.
+#[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Challenge<BlockNumber> {
+	// the index of the challenged node
+	pub node_index: u64,
+	// makes every challenge unique
+	pub nonce: u64,
+	// the challenge must be answered before this block
+	pub deadline: BlockNumber,
+}
+
+/// A part of the data of an order sealed in a sector.
+///
+/// the data of an order larger than a sector is cut into parts of `SECTOR_SIZE` bytes, every
+/// part takes a whole sector. a smaller order is a single part, which takes the power of two
+/// nodes above its size. the pieces are packed in a sector from the largest one, so the subtree
+/// of `tree_d` of a piece is its CommP, and CommD is computed from the CommPs.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Piece {
+	pub order_id: OrderId,
+	// the index of the part in the order
+	pub part: u32,
+	// how many bytes of the order are in the part
+	pub size: u128,
+	// the CommP of the part, which is the CommP of the order if it is a single part
+	pub comm_p: [u8; 32],
+}
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectorStatus {
+	// the miner committed the replica, the seal is not proven yet.
+	PreCommitted,
+	// the seal is proven, the sector is challenged instead of its orders.
+	Active,
+}
+
+/// A sector of `SECTOR_SIZE` bytes, in which the miner seals the pieces of its orders.
+///
+/// the replica of an order is confirmed once all its data is in active sectors, the sealing is
+/// described in `ipse_io::seal`.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Sector<BlockNumber> {
+	// the commitment of the replica (CommR)
+	pub comm_r: [u8; 32],
+	// the merkle root of the data (CommD), computed from the pieces
+	pub comm_d: [u8; 32],
+	pub pieces: Vec<Piece>,
+	pub status: SectorStatus,
+	// the seed of the seal proof is drawn at this block
+	pub seal_at: BlockNumber,
+	pub seed: Option<[u8; 32]>,
+	// the sector failed its last challenge
+	pub faulty: bool,
+}
+
+impl<BlockNumber> Sector<BlockNumber> {
+	/// how many bytes of data are sealed in the sector.
+	pub fn size(&self) -> u128 {
+		self.pieces.iter().fold(0, |size, p| size.saturating_add(p.size))
+	}
+}
+
+/// What the dispute of a replica is about.
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisputeKind {
+	// the user claims the miner lost the data, the miner proves the disputed chunks.
+	DataLost,
+	// the miner claims it never received the data, the arbitration rules it.
+	DataNotReceived,
+}
+
+impl DisputeKind {
+	/// the ruling when the window is over and the dispute is still open.
+	pub fn default_verdict(&self) -> Verdict {
+		match self {
+			// the miner did not prove the chunks in time.
+			DisputeKind::DataLost => Verdict::MinerAtFault,
+			DisputeKind::DataNotReceived => Verdict::NoFault,
+		}
+	}
+
+	/// whether the verdict rejects the dispute, the user raises `DataLost` and the miner
+	/// raises `DataNotReceived`.
+	pub fn rejected_by(&self, verdict: Verdict) -> bool {
+		match (self, verdict) {
+			(DisputeKind::DataLost, Verdict::UserAtFault) => true,
+			(DisputeKind::DataNotReceived, Verdict::MinerAtFault) => true,
+			_ => false,
+		}
+	}
+}
+
+/// The ruling of a dispute.
+///
+/// the replica ends unless the miner proved a `DataLost` dispute wrong, the escrow of its
+/// unsettled periods is refunded to the user, except the current period which is paid to the
+/// miner if the user did not deliver the data, and the staking slashed from a miner at fault
+/// goes to the user.
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+	MinerAtFault,
+	UserAtFault,
+	NoFault,
+}
+
+/// A dispute of a replica, it freezes the settlement of the order until it is ruled.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct Dispute<AccountId, Balance, BlockNumber> {
+	pub kind: DisputeKind,
+	// who raised the dispute
+	pub plaintiff: AccountId,
+	pub deposit: Balance,
+	// the indices of the chunks that the miner has not proven yet
+	pub pending_chunks: Vec<u64>,
+	// the dispute is ruled by its kind after this block
+	pub deadline: BlockNumber,
+}
+
+// A value placed in storage that represents the current version of the ipse storage.
+// It is used by `on_runtime_upgrade` to decide whether the storage migration should run.
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Releases {
+	// orders are kept in a single `Vec`, the order id is the index of the vec.
+	V1_0_0,
+	// orders are kept in a map keyed by `OrderId`.
+	V2_0_0,
+	// the terms of orders are expressed in blocks.
+	V3_0_0,
+	// the data is addressed by the CID.
+	V4_0_0,
+	// the orders have the data class and the retrieval frequency, the miners declare their
+	// capabilities.
+	V5_0_0,
+	// the histories are kept in ring buffers.
+	V6_0_0,
+	// the replicas are sealed in sectors, which are challenged instead of the orders.
+	V7_0_0,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1_0_0
+	}
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 #[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum OrderStatus {
 	Created,
-	// Once one miner confirms it,
+	// Once all miners confirm it,
 	// this order becomes confirmed.
 	Confirmed,
 	Expired,
 	Deleted,
+	// Some miners (not all) confirm it.
+	PartiallyConfirmed,
+}
+
+impl OrderStatus {
+	/// whether the order is still in storing.
+	pub fn is_live(&self) -> bool {
+		match self {
+			OrderStatus::Created | OrderStatus::PartiallyConfirmed | OrderStatus::Confirmed => true,
+			_ => false,
+		}
+	}
+
+	/// whether some miners have confirmed the order.
+	pub fn is_confirmed(&self) -> bool {
+		match self {
+			OrderStatus::PartiallyConfirmed | OrderStatus::Confirmed => true,
+			_ => false,
+		}
+	}
 }
 
 decl_storage! {
@@ -160,23 +929,119 @@ decl_storage! {
 		/// the info of miners.
 		pub Miners get(fn miner): map hasher(twox_64_concat) T::AccountId => Option<Miner<T::AccountId,BalanceOf<T>>>;
 
-		/// order id is the index of vec.
-		pub Orders get(fn order): Vec<Order<T::AccountId, BalanceOf<T>>>;
+		/// the orders.
+		pub Orders get(fn order): map hasher(twox_64_concat) OrderId => Option<OrderOf<T>>;
+
+		/// the id of the next order.
+		pub NextOrderId get(fn next_order_id): OrderId;
+
+		/// the order ids of users.
+		pub UserOrders get(fn user_orders): map hasher(twox_64_concat) T::AccountId => Vec<OrderId>;
+
+		/// the order ids of miners.
+		pub MinerOrders get(fn miner_orders): map hasher(twox_64_concat) T::AccountId => Vec<OrderId>;
+
+		/// the order ids that store the same content.
+		pub HashOrders get(fn hash_orders): map hasher(blake2_128_concat) Cid => Vec<OrderId>;
 
 		/// exposed miners
 		pub RecommendList get(fn recommend_list): Vec<(T::AccountId, BalanceOf<T>)>;
 
-		/// the rewad history of miners.
-		pub MinerHistory get(fn miner_history): map hasher(twox_64_concat) T::AccountId => Vec<Order<T::AccountId, BalanceOf<T>>>;
+		/// the latest orders of miners, in ring buffers, see `miner_history`.
+		pub MinerHistoryCursors: map hasher(twox_64_concat) T::AccountId => Cursor;
+		pub MinerHistoryItems: double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) u32 => Option<OrderId>;
+
+		/// how many times miners are rewarded.
+		pub RewardNums get(fn reward_num): map hasher(twox_64_concat) T::AccountId => u64;
 
-		/// the rewad history of users.
-		pub History get(fn history): map hasher(twox_64_concat) T::AccountId => Option<MiningHistory<BalanceOf<T>, T::BlockNumber>>;
+		/// the latest rewards of miners, in ring buffers, see `history`.
+		pub HistoryCursors: map hasher(twox_64_concat) T::AccountId => Cursor;
+		pub HistoryItems: double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) u32 => Option<(T::BlockNumber, BalanceOf<T>)>;
 
 		/// whose url?.
 		pub Url get(fn url): map hasher(twox_64_concat) Vec<u8> => T::AccountId;
 
-		/// orders.
-		pub ListOrder get(fn list_order): Vec<Order<T::AccountId, BalanceOf<T>>>;
+		/// the latest orders, in a ring buffer, see `list_order`.
+		pub ListOrderCursor: Cursor;
+		pub ListOrderItems: map hasher(twox_64_concat) u32 => Option<OrderId>;
+
+		/// the CommP of the data of the order that the user commits to, see `seal::comm_p`.
+		pub DataCommitments get(fn data_commitment): map hasher(twox_64_concat) OrderId => Option<[u8; 32]>;
+
+		/// the CommP of the data sealed by the miner, registered when the replica is confirmed,
+		/// the disputed chunks are proven against it.
+		pub Commitments get(fn commitment): double_map hasher(twox_64_concat) OrderId, hasher(twox_64_concat) T::AccountId => Option<[u8; 32]>;
+
+		/// the sectors of miners.
+		pub Sectors get(fn sector): double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) SectorId => Option<SectorOf<T>>;
+
+		/// the sectors in which the miner seals the replica of the order.
+		pub OrderSectors get(fn order_sectors): double_map hasher(twox_64_concat) OrderId, hasher(twox_64_concat) T::AccountId => Vec<SectorId>;
+
+		/// the pre-committed sectors whose seed is drawn at this block.
+		pub SealSeeds get(fn seal_seeds): map hasher(twox_64_concat) T::BlockNumber => Vec<(T::AccountId, SectorId)>;
+
+		/// the pre-committed sectors which are aborted at this block if the seal is not proven.
+		pub PreCommitDeadlines get(fn precommit_deadlines): map hasher(twox_64_concat) T::BlockNumber => Vec<(T::AccountId, SectorId)>;
+
+		/// the open challenges of sectors.
+		pub Challenges get(fn challenge): double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) SectorId => Option<Challenge<T::BlockNumber>>;
+
+		/// the orders that are settled at this block.
+		pub DueOrders get(fn due_orders): map hasher(twox_64_concat) T::BlockNumber => Vec<OrderId>;
+
+		/// the retrieval deals.
+		pub RetrievalDeals get(fn retrieval_deal): map hasher(twox_64_concat) DealId => Option<RetrievalDeal<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+
+		/// the id of the next retrieval deal.
+		pub NextDealId get(fn next_deal_id): DealId;
+
+		/// how many retrieval deals of miners are open.
+		pub MinerDeals get(fn miner_deals): map hasher(twox_64_concat) T::AccountId => u32;
+
+		/// the orders that are renewed automatically, and by how many periods.
+		pub AutoRenew get(fn auto_renew): map hasher(twox_64_concat) OrderId => Option<u32>;
+
+		/// the finished orders that are removed at this block.
+		pub PrunableOrders get(fn prunable_orders): map hasher(twox_64_concat) T::BlockNumber => Vec<OrderId>;
+
+		/// the orders whose replicas are challenged again at this block, see
+		/// `RetrievalFrequency::challenges_per_period`.
+		pub ChallengeRounds get(fn challenge_rounds): map hasher(twox_64_concat) T::BlockNumber => Vec<OrderId>;
+
+		/// the challenges that expire at this block.
+		pub ChallengeDeadlines get(fn challenge_deadlines): map hasher(twox_64_concat) T::BlockNumber => Vec<(T::AccountId, SectorId)>;
+
+		/// the staking of unregistered miners, (unreserve block, amount).
+		pub Unbonding get(fn unbonding): map hasher(twox_64_concat) T::AccountId => Vec<(T::BlockNumber, BalanceOf<T>)>;
+
+		/// the asks of miners.
+		pub Asks get(fn ask): map hasher(twox_64_concat) T::AccountId => Option<Ask<BalanceOf<T>>>;
+
+		/// the bids of users which wait to be matched.
+		pub Bids get(fn bid): map hasher(twox_64_concat) BidId => Option<Bid<T::AccountId, BalanceOf<T>>>;
+
+		/// the id of the next bid.
+		pub NextBidId get(fn next_bid_id): BidId;
+
+		/// the prices of the miners who quote in the reference unit, per GB every settlement
+		/// period, they replace `unit_price` and are converted at the order time.
+		pub ReferencePrices get(fn reference_price): map hasher(twox_64_concat) T::AccountId => Option<u128>;
+
+		/// the reputation facts of miners.
+		pub Reputations get(fn reputation): map hasher(twox_64_concat) T::AccountId => Reputation;
+
+		/// the open disputes of replicas.
+		pub Disputes get(fn dispute): double_map hasher(twox_64_concat) OrderId, hasher(twox_64_concat) T::AccountId => Option<Dispute<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+
+		/// the disputes whose window is over at this block.
+		pub DisputeDeadlines get(fn dispute_deadlines): map hasher(twox_64_concat) T::BlockNumber => Vec<(OrderId, T::AccountId)>;
+
+		/// the disputed orders whose settlement is frozen, and the block it was due.
+		pub FrozenSettlements get(fn frozen_settlement): map hasher(twox_64_concat) OrderId => Option<T::BlockNumber>;
+
+		/// the version of storage.
+		pub StorageVersion get(fn storage_version) build(|_| Releases::V7_0_0): Releases;
 
 	}
 }
@@ -188,18 +1053,69 @@ decl_module! {
 
 		fn deposit_event() = default;
 
+		/// how many blocks that the miner has to answer a challenge.
+		const ChallengeDuration: T::BlockNumber = T::ChallengeDuration::get();
+
+		/// the max number of replicas of an order.
+		const MaxReplicas: u32 = T::MaxReplicas::get();
+
+		/// how many blocks that the staking is still reserved after the miner unregisters.
+		const UnbondingDuration: T::BlockNumber = T::UnbondingDuration::get();
+
+		/// how many blocks a settlement period lasts.
+		const SettlementPeriod: T::BlockNumber = T::SettlementPeriod::get();
+
+		/// how many blocks that the expired or deleted orders are kept.
+		const OrderRetention: T::BlockNumber = T::OrderRetention::get();
+
+		/// how many blocks a retrieval deal lasts.
+		const RetrievalTimeout: T::BlockNumber = T::RetrievalTimeout::get();
+
+		/// how much the reputation counts when the recommend list is ranked.
+		const ReputationWeight: Permill = T::ReputationWeight::get();
+
+		/// how many latest orders are kept for every miner.
+		const MinerHistoryLen: u32 = T::MinerHistoryLen::get();
+
+		/// how many latest rewards are kept for every miner.
+		const HistoryLen: u32 = T::HistoryLen::get();
+
+		/// how many latest orders are kept.
+		const ListOrderLen: u32 = T::ListOrderLen::get();
+
+		/// how many blocks the window of a dispute lasts.
+		const DisputeWindow: T::BlockNumber = T::DisputeWindow::get();
+
+		/// the deposit of who raises a dispute.
+		const DisputeDeposit: BalanceOf<T> = T::DisputeDeposit::get();
+
+		/// the max number of chunks that the miner proves in a dispute.
+		const DisputeChunks: u32 = T::DisputeChunks::get();
+
+		/// how many blocks after the pre-commit that the seed of the seal proof is drawn.
+		const SealDelay: T::BlockNumber = T::SealDelay::get();
+
+		/// how many blocks that the miner has to prove the seal after the seed is drawn.
+		const SealDuration: T::BlockNumber = T::SealDuration::get();
 
 		/// register
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::register_miner()]
 		fn register_miner(origin,nickname: Vec<u8>, region: Vec<u8>, url: Vec<u8>, public_key: Vec<u8>,stash_address: T::AccountId, capacity: u128, unit_price: BalanceOf<T>) {
 			let who = ensure_signed(origin)?;
 
-			let total_staking = capacity.saturated_into::<BalanceOf<T>>() * unit_price;
-
 			ensure!(capacity > 0, Error::<T>::NoneCapacity);
 
+			let total_staking = capacity
+				.saturated_into::<BalanceOf<T>>()
+				.checked_mul(&unit_price)
+				.ok_or(Error::<T>::Overflow)?;
+
 			ensure!(!<Url<T>>::contains_key(url.clone()), Error::<T>::UrlExists);
 
+			ensure!(!Miners::<T>::contains_key(&who), Error::<T>::AlreadyRegistered);
+
+			T::StakingCurrency::reserve(&who, total_staking).map_err(|_| Error::<T>::CannotStake)?;
+
 			Url::<T>::insert(url.clone(),&who);
 
 			Miners::<T>::insert(&who, Miner {
@@ -215,183 +1131,799 @@ decl_module! {
 				total_staking,
 				create_ts:Self::get_now_ts(),
 				update_ts:Self::get_now_ts(),
+				hot: false,
+				bandwidth: BandwidthTier::default(),
 			});
 			Self::deposit_event(RawEvent::Registered(who));
 		}
 
 
-		/// the user create the order.
-		#[weight = 10_000]
-		fn create_order(origin,miner: T::AccountId, label: Vec<u8>, hash: [u8; 46], size: u128, url: Option<Vec<u8>>, days: u64, unit_price: BalanceOf<T>) {
-			let user = ensure_signed(origin)?;
+		/// the miner updates its info.
+		#[weight = <T as Trait>::WeightInfo::update_miner()]
+		fn update_miner(origin, nickname: Vec<u8>, region: Vec<u8>, url: Vec<u8>, public_key: Vec<u8>, stash_address: T::AccountId, unit_price: BalanceOf<T>) {
+			let who = ensure_signed(origin)?;
 
-			let mut order_list= Vec::new();
+			let mut miner_info = Self::miner(&who).ok_or(Error::<T>::MinerNotFound)?;
 
-			let miner_cp = miner.clone();
+			if url != miner_info.url {
+				ensure!(!<Url<T>>::contains_key(url.clone()), Error::<T>::UrlExists);
+				Url::<T>::remove(miner_info.url.clone());
+				Url::<T>::insert(url.clone(), &who);
+			}
 
-			ensure!(<Miners<T>>::contains_key(&miner), Error::<T>::MinerNotFound);
-			ensure!(days > 0, Error::<T>::NoneDays);
+			// the staking of all the capacity, free and used by orders, follows the new price.
+			let capacity = miner_info
+				.capacity
+				.checked_add(Self::used_capacity(&who))
+				.ok_or(Error::<T>::Overflow)?
+				.saturated_into::<BalanceOf<T>>();
+			let total_staking = if unit_price >= miner_info.unit_price {
+				capacity
+					.checked_mul(&(unit_price - miner_info.unit_price))
+					.and_then(|more| more.checked_add(&miner_info.total_staking))
+					.ok_or(Error::<T>::Overflow)?
+			} else {
+				let less = capacity.saturating_mul(miner_info.unit_price - unit_price);
+				miner_info.total_staking.saturating_sub(less)
+			};
+			Self::update_staking(&who, miner_info.total_staking, total_staking)?;
 
+			miner_info.nickname = nickname;
+			miner_info.region = region;
+			miner_info.url = url;
+			miner_info.public_key = public_key;
+			miner_info.stash_address = stash_address;
+			miner_info.unit_price = unit_price;
+			miner_info.total_staking = total_staking;
+			miner_info.update_ts = Self::get_now_ts();
+			Miners::<T>::insert(&who, miner_info);
+
+			Self::deposit_event(RawEvent::UpdatedMiner(who));
+		}
 
-			if let Some(miner_info) = Miners::<T>::get(&miner).as_mut() {
 
-				ensure!(miner_info.capacity > size, Error::<T>::InsufficientCapacity);
+		/// the miner adds capacity, and stakes more.
+		#[weight = <T as Trait>::WeightInfo::add_capacity()]
+		fn add_capacity(origin, capacity: u128) {
+			let who = ensure_signed(origin)?;
 
-				miner_info.capacity = miner_info.capacity - size;
+			ensure!(capacity > 0, Error::<T>::NoneCapacity);
 
-				let day_price = miner_info.unit_price * size.saturated_into::<BalanceOf<T>>();
-				let total_price = day_price * days.saturated_into::<BalanceOf<T>>();
+			let mut miner_info = Self::miner(&who).ok_or(Error::<T>::MinerNotFound)?;
+			miner_info.capacity = miner_info.capacity.checked_add(capacity).ok_or(Error::<T>::Overflow)?;
 
-				let miner_order = MinerOrder {
-					miner: miner_cp.clone(),
-					day_price,
-					total_price,
-					verify_result: true,
-					verify_ts: Self::get_now_ts(),
-					confirm_ts: Self::get_now_ts(),
-					url: url,
-				};
-				T::StakingCurrency::reserve(&user, miner_order.total_price)?;
-				order_list.push(miner_order);
-
-				Self::append_or_replace_orders(Order {
-						miner: miner_cp.clone(),
-						label: label.clone(),
-						hash: hash.clone(),
-						size: size.clone(),
-						user: user.clone(),
-						orders: order_list.clone(),
-						status: OrderStatus::Confirmed,
-						create_ts: Self::get_now_ts(),
-						update_ts: Self::get_now_ts(),
-						duration: days * DAY,
-					});
+			let total_staking = capacity
+				.saturated_into::<BalanceOf<T>>()
+				.checked_mul(&miner_info.unit_price)
+				.and_then(|staking| staking.checked_add(&miner_info.total_staking))
+				.ok_or(Error::<T>::Overflow)?;
+			Self::update_staking(&who, miner_info.total_staking, total_staking)?;
 
-				Self::update_miner_history(miner_cp.clone(),Order {
-						miner: miner_cp.clone(),
-						label: label.clone(),
-						hash: hash.clone(),
-						size: size.clone(),
-						user: user.clone(),
-						orders: order_list.clone(),
-						status: OrderStatus::Confirmed,
-						create_ts: Self::get_now_ts(),
-						update_ts: Self::get_now_ts(),
-						duration: days * DAY,
-					});
+			miner_info.total_staking = total_staking;
+			miner_info.update_ts = Self::get_now_ts();
+			Miners::<T>::insert(&who, miner_info);
 
-				Orders::<T>::mutate( |o| o.push(
-					Order {
-						miner: miner_cp.clone(),
-						label: label.clone(),
-						hash: hash.clone(),
-						size: size.clone(),
-						user: user.clone(),
-						orders: order_list,
-						status: OrderStatus::Confirmed,
-						create_ts: Self::get_now_ts(),
-						update_ts: Self::get_now_ts(),
-						duration: days * DAY,
-					}
-				));
+			Self::deposit_event(RawEvent::AddedCapacity(who, capacity));
+		}
+
+
+		/// the miner reduces the capacity that is not used by orders, and unstakes.
+		#[weight = <T as Trait>::WeightInfo::reduce_capacity()]
+		fn reduce_capacity(origin, capacity: u128) {
+			let who = ensure_signed(origin)?;
+
+			ensure!(capacity > 0, Error::<T>::NoneCapacity);
+
+			let mut miner_info = Self::miner(&who).ok_or(Error::<T>::MinerNotFound)?;
+			ensure!(miner_info.capacity >= capacity, Error::<T>::InsufficientCapacity);
+			miner_info.capacity -= capacity;
+
+			let staking = capacity
+				.saturated_into::<BalanceOf<T>>()
+				.checked_mul(&miner_info.unit_price)
+				.ok_or(Error::<T>::Overflow)?;
+			let total_staking = miner_info.total_staking.saturating_sub(staking);
+			Self::update_staking(&who, miner_info.total_staking, total_staking)?;
+
+			Asks::<T>::mutate(&who, |ask| if let Some(ask) = ask {
+				ask.capacity = ask.capacity.min(miner_info.capacity);
+			});
+
+			miner_info.total_staking = total_staking;
+			miner_info.update_ts = Self::get_now_ts();
+			Miners::<T>::insert(&who, miner_info);
+
+			Self::deposit_event(RawEvent::ReducedCapacity(who, capacity));
+		}
+
+
+		/// the miner declares whether it stores hot data and the bandwidth of its machine.
+		///
+		/// the ask can't offer more than the miner is able to, it is narrowed down.
+		#[weight = <T as Trait>::WeightInfo::update_capabilities()]
+		fn update_capabilities(origin, hot: bool, bandwidth: BandwidthTier) {
+			let who = ensure_signed(origin)?;
+
+			let mut miner_info = Self::miner(&who).ok_or(Error::<T>::MinerNotFound)?;
+			miner_info.hot = hot;
+			miner_info.bandwidth = bandwidth;
+			miner_info.update_ts = Self::get_now_ts();
+			Miners::<T>::insert(&who, miner_info);
 
-				Miners::<T>::insert(&miner_cp.clone(), miner_info);
+			Asks::<T>::mutate(&who, |ask| if let Some(ask) = ask {
+				ask.hot &= hot;
+				ask.bandwidth = ask.bandwidth.min(bandwidth);
+			});
+
+			Self::deposit_event(RawEvent::UpdatedMiner(who));
+		}
+
+
+		/// the miner quotes in the reference unit (e.g. micro USD) per GB every settlement
+		/// period, `None` goes back to `unit_price`.
+		///
+		/// the price is converted into the native token at the order time, no order can be
+		/// made with the miner while the price feed is stale.
+		#[weight = <T as Trait>::WeightInfo::set_reference_price()]
+		fn set_reference_price(origin, price: Option<u128>) {
+			let who = ensure_signed(origin)?;
+
+			ensure!(Miners::<T>::contains_key(&who), Error::<T>::MinerNotFound);
+
+			match price {
+				Some(price) => ReferencePrices::<T>::insert(&who, price),
+				None => ReferencePrices::<T>::remove(&who),
+			}
+			Self::deposit_event(RawEvent::UpdatedReferencePrice(who, price));
+		}
+
+
+		/// the miner leaves, all its orders, sectors, challenges, disputes and retrieval deals
+		/// should be settled.
+		#[weight = <T as Trait>::WeightInfo::unregister_miner()]
+		fn unregister_miner(origin) {
+			let who = ensure_signed(origin)?;
+
+			let miner_info = Self::miner(&who).ok_or(Error::<T>::MinerNotFound)?;
+
+			for order_id in Self::miner_orders(&who) {
+				if let Some(order) = Self::order(order_id) {
+					ensure!(
+						!order.status.is_live() || order.orders.iter().all(|mo| mo.miner != who),
+						Error::<T>::HasActiveOrders
+					);
+				}
+				ensure!(!Disputes::<T>::contains_key(order_id, &who), Error::<T>::HasOpenDisputes);
+			}
+			ensure!(Sectors::<T>::iter_prefix_values(&who).next().is_none(), Error::<T>::HasSectors);
+			ensure!(
+				Challenges::<T>::iter_prefix_values(&who).next().is_none(),
+				Error::<T>::HasOpenChallenges
+			);
+			ensure!(Self::miner_deals(&who) == 0, Error::<T>::HasOpenDeals);
 
+			let mut list = <RecommendList<T>>::get();
+			if let Some(pos) = list.iter().position(|h| h.0 == who) {
+				let amount = list.remove(pos).1;
+				T::StakingCurrency::unreserve(&who, amount);
+				<RecommendList<T>>::put(list);
 			}
 
+			// the staking is unreserved after the unbonding duration.
+			let expire = <system::Module<T>>::block_number() + T::UnbondingDuration::get();
+			Unbonding::<T>::mutate(&who, |u| u.push((expire, miner_info.total_staking)));
+
+			Url::<T>::remove(miner_info.url);
+			Asks::<T>::remove(&who);
+			ReferencePrices::<T>::remove(&who);
+			Miners::<T>::remove(&who);
+
+			Self::deposit_event(RawEvent::Unregistered(who));
+		}
+
+
+		/// unreserve the staking whose unbonding duration is over.
+		#[weight = <T as Trait>::WeightInfo::withdraw_unbonded()]
+		fn withdraw_unbonded(origin) {
+			let who = ensure_signed(origin)?;
+
+			let now = <system::Module<T>>::block_number();
+			let mut amount = 0.saturated_into::<BalanceOf<T>>();
+			Unbonding::<T>::mutate_exists(&who, |unbonding| {
+				if let Some(u) = unbonding {
+					u.retain(|(expire, value)| if *expire <= now {
+						amount = amount.saturating_add(*value);
+						false
+					} else {
+						true
+					});
+					if u.is_empty() {
+						*unbonding = None;
+					}
+				}
+			});
+			ensure!(amount > 0.saturated_into::<BalanceOf<T>>(), Error::<T>::NoneUnbonded);
+
+			T::StakingCurrency::unreserve(&who, amount);
 
-			Self::deposit_event(RawEvent::CreatedOrder(user));
+			Self::deposit_event(RawEvent::Withdrawn(who, amount));
+		}
+
+
+		/// the user create the order.
+		///
+		/// the data, whose CID string is `cid`, is stored by `replicas` miners for `periods`
+		/// settlement periods, they are `miners` and the miners selected from the recommend
+		/// list whose price per byte is not more than `unit_price`. the miners seal the data
+		/// whose CommP is `comm_p`.
+		///
+		/// all the miners must be able to store the data of `class` retrieved at `frequency`,
+		/// which also raise the price and how often the storage is proven.
+		#[weight = <T as Trait>::WeightInfo::create_order(*replicas, cid.len() as u32)]
+		fn create_order(origin, miners: Vec<T::AccountId>, replicas: u32, label: Vec<u8>, cid: Vec<u8>, size: u128, comm_p: [u8; 32], url: Option<Vec<u8>>, periods: u32, class: DataClass, frequency: RetrievalFrequency, unit_price: BalanceOf<T>) {
+			let user = ensure_signed(origin)?;
+
+			let cid = Cid::parse(&cid).map_err(|_| Error::<T>::InvalidCid)?;
+			ensure!(periods > 0, Error::<T>::NonePeriods);
+			ensure!(replicas > 0 && replicas <= T::MaxReplicas::get(), Error::<T>::InvalidReplicas);
+			ensure!(miners.len() <= replicas as usize, Error::<T>::InvalidReplicas);
+
+			let mut miners = miners;
+			for (i, miner) in miners.iter().enumerate() {
+				ensure!(!miners[..i].contains(miner), Error::<T>::DuplicateMiner);
+				let miner_info = Self::miner(miner).ok_or(Error::<T>::MinerNotFound)?;
+				ensure!(miner_info.can_store(class, frequency), Error::<T>::MissingCapability);
+			}
+			Self::select_miners(&mut miners, replicas as usize, size, class, frequency, unit_price);
+			ensure!(miners.len() == replicas as usize, Error::<T>::NotEnoughMiners);
 
+			Self::do_create_order(
+				user, miners, label, cid, size, comm_p, url, periods, class, frequency,
+			)?;
 		}
 
-		/// the miner confirm the order.
-		#[weight = 10_000]
-		fn confirm_order(origin, order_id: u64, url: Vec<u8>) {
+		/// the miner pre-commits a sector, in which it seals the `pieces` of its orders.
+		///
+		/// `comm_r` is the commitment of the replica sealed with `replica_id`, the seed of the
+		/// seal proof is drawn `SealDelay` blocks later. `proofs` are the merkle paths of the
+		/// pieces in the trees of their orders, they are empty for the single parts.
+		#[weight = <T as Trait>::WeightInfo::precommit_sector(pieces.len() as u32)]
+		fn precommit_sector(origin, sector_id: SectorId, comm_r: [u8; 32], pieces: Vec<Piece>, proofs: Vec<Vec<[u8; 32]>>) {
 			let miner = ensure_signed(origin)?;
-			let miner_cp = miner.clone();
 
-			// must check total staking, if is zero, cannot confirm order.
 			let miner_info = Self::miner(&miner).ok_or(Error::<T>::MinerNotFound)?;
 			ensure!(miner_info.total_staking > 0.saturated_into::<BalanceOf<T>>(), Error::<T>::NoneStaking);
+			ensure!(!Sectors::<T>::contains_key(&miner, sector_id), Error::<T>::SectorExists);
+			ensure!(
+				!pieces.is_empty() && pieces.len() <= MAX_PIECES as usize,
+				Error::<T>::InvalidPieces
+			);
+			ensure!(proofs.len() == pieces.len(), Error::<T>::InvalidPieces);
 
-			let now = Self::get_now_ts();
-			Orders::<T>::mutate( |os| -> DispatchResult {
-
-				let mut order = os.get_mut(order_id as usize).ok_or(Error::<T>::OrderNotFound)?;
+			let mut ids = pieces.iter().map(|p| p.order_id).collect::<Vec<_>>();
+			ids.sort();
+			ids.dedup();
+			ensure!(ids.len() == pieces.len(), Error::<T>::InvalidPieces);
 
+			let mut layout = Vec::with_capacity(pieces.len());
+			for (piece, proof) in pieces.iter().zip(&proofs) {
+				let order = Self::order(piece.order_id).ok_or(Error::<T>::OrderNotFound)?;
 				ensure!(order.status != OrderStatus::Deleted, Error::<T>::OrderDeleted);
 				ensure!(order.status != OrderStatus::Expired, Error::<T>::OrderExpired);
+				ensure!(order.orders.iter().any(|mo| mo.miner == miner), Error::<T>::MinerOrderNotFound);
+				let comm_p = Self::data_commitment(piece.order_id)
+					.ok_or(Error::<T>::DataCommitmentNotFound)?;
 
-				let mut miner_order = Self::find_miner_order(miner, &mut order.orders).ok_or(Error::<T>::MinerOrderNotFound)?;
-				miner_order.confirm_ts = now;
-				miner_order.url = Some(url);
-				// update order's status and update_ts
-				if order.status == OrderStatus::Created {
-					order.status = OrderStatus::Confirmed;
-					order.update_ts = now;
-				}
+				// the part is in the order and not sealed yet.
+				ensure!(
+					piece.size > 0 && piece.size == part_size(order.size, piece.part),
+					Error::<T>::InvalidPieces
+				);
+				let sealed = Self::pieces_of(piece.order_id, &miner);
+				ensure!(sealed.iter().all(|(_, p)| p.part != piece.part), Error::<T>::InvalidPieces);
+				ensure!(
+					verify_part(order.size, piece.part, piece.comm_p, proof, comm_p),
+					Error::<T>::MismatchedPiece
+				);
 
-				// reserve some user's funds for the order
-				T::StakingCurrency::reserve(&order.user, miner_order.total_price)?;
-				Ok(())
-			})?;
-			Self::deposit_event(RawEvent::ConfirmedOrder(miner_cp, order_id));
+				layout.push((piece_nodes(order.size), piece.comm_p));
+			}
+			// every piece is aligned to its size only if the larger ones are packed first.
+			ensure!(layout.windows(2).all(|w| w[0].0 >= w[1].0), Error::<T>::InvalidPieces);
+			let nodes = layout.iter().fold(0u64, |nodes, (n, _)| nodes.saturating_add(*n));
+			ensure!(nodes <= SECTOR_SIZE / NODE_SIZE, Error::<T>::SectorOverflow);
+			let comm_d = comm_d_of(&layout, SECTOR_SIZE / NODE_SIZE);
+
+			let seal_at = <system::Module<T>>::block_number() + T::SealDelay::get();
+			for piece in &pieces {
+				OrderSectors::<T>::mutate(piece.order_id, &miner, |ids| ids.push(sector_id));
+			}
+			Sectors::<T>::insert(&miner, sector_id, Sector {
+				comm_r,
+				comm_d,
+				pieces,
+				status: SectorStatus::PreCommitted,
+				seal_at,
+				seed: None,
+				faulty: false,
+			});
+			SealSeeds::<T>::mutate(seal_at, |s| s.push((miner.clone(), sector_id)));
+
+			Self::deposit_event(RawEvent::SectorPreCommitted(miner, sector_id, comm_r));
+		}
+
+
+		/// the miner proves the seal of the pre-committed sector with the seed drawn by the
+		/// chain, the replicas whose data is all sealed are confirmed.
+		#[weight = <T as Trait>::WeightInfo::prove_commit_sector()]
+		fn prove_commit_sector(origin, sector_id: SectorId, proof: Vec<u8>) {
+			let miner = ensure_signed(origin)?;
+
+			let mut sector = Self::sector(&miner, sector_id).ok_or(Error::<T>::SectorNotFound)?;
+			ensure!(sector.status == SectorStatus::PreCommitted, Error::<T>::SectorActive);
+			let seed = sector.seed.ok_or(Error::<T>::SeedNotDrawn)?;
+
+			let replica_id = Self::replica_id(&miner, sector_id, &sector.comm_d);
+			ensure!(
+				porep::verify_seal(sector.comm_r, sector.comm_d, replica_id, seed, &proof),
+				Error::<T>::InvalidSealProof
+			);
+
+			sector.status = SectorStatus::Active;
+			let pieces = sector.pieces.clone();
+			Sectors::<T>::insert(&miner, sector_id, sector);
+			Self::deposit_event(RawEvent::SectorProveCommitted(miner.clone(), sector_id));
+
+			for piece in pieces {
+				Self::confirm_replica(piece.order_id, &miner);
+			}
+			Self::open_challenge(&miner, sector_id);
 		}
 
 
 		/// users delete their order.
-		#[weight = 10_000]
-		fn delete_order(origin, order_id: u64) {
+		///
+		/// the miners who proved the storage are paid for the blocks of the current period,
+		/// the rest of the unsettled funds is refunded.
+		#[weight = <T as Trait>::WeightInfo::delete_order(T::MaxReplicas::get())]
+		fn delete_order(origin, order_id: OrderId) {
 			let user = ensure_signed(origin)?;
 			let user_cp = user.clone();
-			Orders::<T>::mutate( |os| -> DispatchResult {
-				let mut order = os.get_mut(order_id as usize).ok_or(Error::<T>::OrderNotFound)?;
+			Orders::<T>::try_mutate(order_id, |o| -> DispatchResult {
+				let order = o.as_mut().ok_or(Error::<T>::OrderNotFound)?;
 				ensure!(user == order.user , Error::<T>::PermissionDenyed);
 				ensure!(order.status != OrderStatus::Deleted, Error::<T>::OrderDeleted);
 				ensure!(order.status != OrderStatus::Expired, Error::<T>::OrderExpired);
+				ensure!(!Self::is_disputed(order_id), Error::<T>::OrderDisputed);
 
-				let now = Self::get_now_ts();
-				order.status = OrderStatus::Deleted;
-				order.update_ts = now;
-				// unreserve some user's funds
-				let days_to_deadline: u64 = (order.duration + order.update_ts - now)/DAY;
-				let mut refund = 0.saturated_into::<BalanceOf<T>>();
+				let period = T::SettlementPeriod::get();
+				let elapsed = <system::Module<T>>::block_number()
+					.saturating_sub(order.settled_until(period));
+				let unsettled = order.periods.saturating_sub(order.settled);
+
+				let mut refunded = BalanceOf::<T>::zero();
 				for mo in &order.orders {
-					refund += mo.day_price;
+					let proven = mo.verify_result && Commitments::<T>::contains_key(order_id, &mo.miner);
+					let (paid, refund) = split_unsettled(
+						mo.period_price.saturated_into::<u128>(),
+						unsettled,
+						elapsed.saturated_into::<u128>(),
+						period.saturated_into::<u128>(),
+						proven,
+					);
+					let paid = paid.saturated_into::<BalanceOf<T>>();
+					let refund = refund.saturated_into::<BalanceOf<T>>();
+					Self::pay_miner(&order.user, &mo.miner, paid);
+					T::StakingCurrency::unreserve(&order.user, refund);
+					refunded += refund;
+					let miner = mo.miner.clone();
+					Self::deposit_event(RawEvent::ReplicaSettled(order_id, miner, unsettled, paid, refund));
+					if Commitments::<T>::contains_key(order_id, &mo.miner) {
+						Reputations::<T>::mutate(&mo.miner, |r| {
+							r.orders_deleted = r.orders_deleted.saturating_add(1)
+						});
+					}
 				}
-				refund = refund * days_to_deadline.saturated_into::<BalanceOf<T>>();
-				T::StakingCurrency::unreserve(&order.user, refund);
+				if !refunded.is_zero() {
+					Self::deposit_event(RawEvent::OrderRefunded(order_id, user.clone(), refunded));
+				}
+				order.settled = order.periods;
+				order.status = OrderStatus::Deleted;
+				order.update_ts = Self::get_now_ts();
+				Self::release_order(order_id, order);
 				Ok(())
 			})?;
-			Self::deposit_event(RawEvent::DeletedOrder(user_cp, order_id));
+			Self::deposit_event(RawEvent::OrderDeleted(order_id, user_cp));
 		}
 
 
+		/// the user extends the order by `extra_periods` settlement periods.
+		///
+		/// the extension keeps the price of the order, the funds of the extra periods are reserved.
+		#[weight = <T as Trait>::WeightInfo::renew_order(T::MaxReplicas::get())]
+		fn renew_order(origin, order_id: OrderId, extra_periods: u32) {
+			let user = ensure_signed(origin)?;
+
+			ensure!(extra_periods > 0, Error::<T>::NonePeriods);
+
+			let mut order = Self::order(order_id).ok_or(Error::<T>::OrderNotFound)?;
+			ensure!(user == order.user, Error::<T>::PermissionDenyed);
+			ensure!(order.status != OrderStatus::Deleted, Error::<T>::OrderDeleted);
+			ensure!(order.status != OrderStatus::Expired, Error::<T>::OrderExpired);
+			ensure!(DataCommitments::<T>::contains_key(order_id), Error::<T>::DataCommitmentNotFound);
+
+			Self::do_renew_order(order_id, &mut order, extra_periods)?;
+			Orders::<T>::insert(order_id, order);
+		}
+
+
+		/// the user renews the order by `periods` settlement periods whenever it is going to
+		/// expire, as long as the user has enough free balance, `0` turns it off.
+		#[weight = <T as Trait>::WeightInfo::set_auto_renew()]
+		fn set_auto_renew(origin, order_id: OrderId, periods: u32) {
+			let user = ensure_signed(origin)?;
+
+			let order = Self::order(order_id).ok_or(Error::<T>::OrderNotFound)?;
+			ensure!(user == order.user, Error::<T>::PermissionDenyed);
+			ensure!(order.status != OrderStatus::Deleted, Error::<T>::OrderDeleted);
+			ensure!(order.status != OrderStatus::Expired, Error::<T>::OrderExpired);
+
+			if periods > 0 {
+				ensure!(
+					DataCommitments::<T>::contains_key(order_id),
+					Error::<T>::DataCommitmentNotFound
+				);
+				AutoRenew::insert(order_id, periods);
+			} else {
+				AutoRenew::remove(order_id);
+			}
+
+			Self::deposit_event(RawEvent::AutoRenewUpdated(order_id, user, periods));
+		}
+
+
+		/// the user asks the miner of the order for the data, and reserves the payment of
+		/// `max_size` bytes at `price` per GB.
+		#[weight = <T as Trait>::WeightInfo::open_retrieval()]
+		fn open_retrieval(origin, order_id: OrderId, miner: T::AccountId, price: BalanceOf<T>, max_size: u128) {
+			let user = ensure_signed(origin)?;
+
+			ensure!(max_size > 0, Error::<T>::NoneCapacity);
+
+			let order = Self::order(order_id).ok_or(Error::<T>::OrderNotFound)?;
+			ensure!(order.status.is_confirmed(), Error::<T>::OrderUnconfirmed);
+			ensure!(Commitments::<T>::contains_key(order_id, &miner), Error::<T>::CommitmentNotFound);
+
+			let escrow = retrieval_price(price.saturated_into::<u128>(), max_size)
+				.ok_or(Error::<T>::Overflow)?;
+			T::StakingCurrency::reserve(&user, escrow.saturated_into::<BalanceOf<T>>())
+				.map_err(|_| Error::<T>::NotEnoughMoney)?;
+
+			let deal_id = Self::next_deal_id();
+			MinerDeals::<T>::mutate(&miner, |n| *n = n.saturating_add(1));
+			RetrievalDeals::<T>::insert(deal_id, RetrievalDeal {
+				order_id,
+				user: user.clone(),
+				miner,
+				price,
+				max_size,
+				claimed: 0,
+				deadline: <system::Module<T>>::block_number() + T::RetrievalTimeout::get(),
+				disputed: false,
+			});
+			NextDealId::put(deal_id + 1);
+
+			Self::deposit_event(RawEvent::OpenedRetrieval(user, deal_id));
+		}
 
-		/// verify
-		#[weight = 10_000]
-		fn verify_storage(origin, order_id: u64) {
+
+		/// the miner claims the payment of the voucher that the user signed for `bytes`
+		/// served bytes in total.
+		#[weight = <T as Trait>::WeightInfo::claim_retrieval()]
+		fn claim_retrieval(origin, deal_id: DealId, bytes: u128, signature: T::Signature) {
 			let miner = ensure_signed(origin)?;
-			let mut orders = Self::order();
-			let order = orders.get_mut(order_id as usize).ok_or(Error::<T>::OrderNotFound)?;
 
-			ensure!(order.status == OrderStatus::Confirmed, Error::<T>::OrderUnconfirmed);
+			let mut deal = Self::retrieval_deal(deal_id).ok_or(Error::<T>::DealNotFound)?;
+			ensure!(deal.miner == miner, Error::<T>::PermissionDenyed);
+			ensure!(<system::Module<T>>::block_number() <= deal.deadline, Error::<T>::DealExpired);
+			ensure!(bytes > deal.claimed && bytes <= deal.max_size, Error::<T>::InvalidVoucher);
 
-			let now = Self::get_now_ts();
+			let voucher = (b"ipse/retrieval", deal_id, bytes).encode();
+			ensure!(signature.verify(&voucher[..], &deal.user), Error::<T>::InvalidVoucher);
 
-			for mut mo in &mut order.orders {
-				if mo.miner ==  miner {
-					mo.verify_ts = now;
-					mo.verify_result = true;
-				}
+			let price = deal.price.saturated_into::<u128>();
+			let amount = retrieval_price(price, bytes)
+				.zip(retrieval_price(price, deal.claimed))
+				.and_then(|(total, claimed)| total.checked_sub(claimed))
+				.ok_or(Error::<T>::Overflow)?
+				.saturated_into::<BalanceOf<T>>();
+			Self::pay_miner(&deal.user, &miner, amount);
+
+			deal.claimed = bytes;
+			RetrievalDeals::<T>::insert(deal_id, deal);
+
+			Self::deposit_event(RawEvent::ClaimedRetrieval(miner, deal_id, amount));
+		}
+
+
+		/// the user disputes the deal, the miner has `ChallengeDuration` blocks to claim
+		/// the vouchers, the rest of the funds are refunded after that.
+		#[weight = <T as Trait>::WeightInfo::dispute_retrieval()]
+		fn dispute_retrieval(origin, deal_id: DealId) {
+			let user = ensure_signed(origin)?;
+
+			let mut deal = Self::retrieval_deal(deal_id).ok_or(Error::<T>::DealNotFound)?;
+			ensure!(deal.user == user, Error::<T>::PermissionDenyed);
+			ensure!(!deal.disputed, Error::<T>::DealDisputed);
+
+			let deadline = <system::Module<T>>::block_number() + T::ChallengeDuration::get();
+			deal.deadline = deal.deadline.min(deadline);
+			deal.disputed = true;
+			RetrievalDeals::<T>::insert(deal_id, deal);
+
+			Self::deposit_event(RawEvent::DisputedRetrieval(user, deal_id));
+		}
+
+
+		/// close the deal and refund the funds that are not claimed, the miner can close it
+		/// at any time, the user after the deadline.
+		#[weight = <T as Trait>::WeightInfo::close_retrieval()]
+		fn close_retrieval(origin, deal_id: DealId) {
+			let who = ensure_signed(origin)?;
+
+			let deal = Self::retrieval_deal(deal_id).ok_or(Error::<T>::DealNotFound)?;
+			if who != deal.miner {
+				ensure!(who == deal.user, Error::<T>::PermissionDenyed);
+				ensure!(<system::Module<T>>::block_number() > deal.deadline, Error::<T>::DealNotExpired);
+			}
+
+			let price = deal.price.saturated_into::<u128>();
+			let refund = retrieval_price(price, deal.max_size)
+				.zip(retrieval_price(price, deal.claimed))
+				.and_then(|(escrow, claimed)| escrow.checked_sub(claimed))
+				.ok_or(Error::<T>::Overflow)?
+				.saturated_into::<BalanceOf<T>>();
+			T::StakingCurrency::unreserve(&deal.user, refund);
+			RetrievalDeals::<T>::remove(deal_id);
+			MinerDeals::<T>::mutate_exists(&deal.miner, |n| {
+				*n = n.and_then(|n| n.checked_sub(1)).filter(|n| *n > 0);
+			});
+
+			Self::deposit_event(RawEvent::ClosedRetrieval(deal.user, deal_id, refund));
+		}
+
+
+
+		/// the miner answers the challenge of the sector.
+		///
+		/// `proof` opens the challenged node of the replica against the CommR of the sector, a
+		/// failed sector fails the current period of all its orders.
+		#[weight = <T as Trait>::WeightInfo::verify_storage()]
+		fn verify_storage(origin, sector_id: SectorId, nonce: u64, proof: Vec<u8>) {
+			let miner = ensure_signed(origin)?;
+			let sector = Self::sector(&miner, sector_id).ok_or(Error::<T>::SectorNotFound)?;
+
+			let challenge = Self::challenge(&miner, sector_id).ok_or(Error::<T>::ChallengeNotFound)?;
+			ensure!(challenge.nonce == nonce, Error::<T>::ChallengeMismatch);
+			ensure!(<system::Module<T>>::block_number() <= challenge.deadline, Error::<T>::ChallengeExpired);
+
+			let verify_result = porep::verify_replica(sector.comm_r, challenge.node_index, &proof);
+
+			Challenges::<T>::remove(&miner, sector_id);
+			Reputations::<T>::mutate(&miner, |r| if verify_result {
+				r.proofs_passed = r.proofs_passed.saturating_add(1)
+			} else {
+				r.proofs_failed = r.proofs_failed.saturating_add(1)
+			});
+			Self::sector_proven(&miner, sector_id, sector, verify_result);
+
+			Self::deposit_event(RawEvent::StorageProven(miner, sector_id, verify_result));
+		}
+
+
+		/// the user disputes that the miner lost the data, or the miner disputes that it
+		/// never received the data, the settlement of the order is frozen until it is ruled.
+		///
+		/// the miner has `DisputeWindow` blocks to prove `chunks` of the data (random chunks
+		/// if empty) against its commitment, a dispute of a miner is ruled by the arbitration.
+		#[weight = <T as Trait>::WeightInfo::open_dispute()]
+		fn open_dispute(origin, order_id: OrderId, miner: T::AccountId, chunks: Vec<u64>) {
+			let who = ensure_signed(origin)?;
+
+			let order = Self::order(order_id).ok_or(Error::<T>::OrderNotFound)?;
+			ensure!(order.status != OrderStatus::Deleted, Error::<T>::OrderDeleted);
+			ensure!(order.status != OrderStatus::Expired, Error::<T>::OrderExpired);
+			ensure!(order.orders.iter().any(|mo| mo.miner == miner), Error::<T>::MinerOrderNotFound);
+			ensure!(!Disputes::<T>::contains_key(order_id, &miner), Error::<T>::AlreadyDisputed);
+			ensure!(chunks.len() <= T::DisputeChunks::get() as usize, Error::<T>::InvalidChunks);
+
+			let confirmed = Commitments::<T>::contains_key(order_id, &miner);
+			let (kind, pending_chunks) = if who == order.user {
+				ensure!(confirmed, Error::<T>::CommitmentNotFound);
+				let chunks = Self::disputed_chunks(order_id, &miner, order.size, chunks)?;
+				(DisputeKind::DataLost, chunks)
+			} else if who == miner {
+				// the miner who confirmed the order has received the data.
+				ensure!(!confirmed, Error::<T>::AlreadyConfirmed);
+				(DisputeKind::DataNotReceived, Vec::new())
+			} else {
+				Err(Error::<T>::PermissionDenyed)?
+			};
+
+			let deposit = T::DisputeDeposit::get();
+			T::StakingCurrency::reserve(&who, deposit).map_err(|_| Error::<T>::NotEnoughMoney)?;
+
+			let deadline = <system::Module<T>>::block_number() + T::DisputeWindow::get();
+			Disputes::<T>::insert(order_id, &miner, Dispute {
+				kind,
+				plaintiff: who.clone(),
+				deposit,
+				pending_chunks,
+				deadline,
+			});
+			DisputeDeadlines::<T>::mutate(deadline, |d| d.push((order_id, miner.clone())));
+
+			Self::deposit_event(RawEvent::DisputeOpened(order_id, miner, who, kind));
+		}
+
+
+		/// the miner proves a disputed chunk against the CommP of the data it sealed, see
+		/// `verify_chunk`, the dispute is rejected once all the chunks are proven.
+		#[weight = <T as Trait>::WeightInfo::prove_dispute(proof.len() as u32)]
+		fn prove_dispute(origin, order_id: OrderId, chunk_index: u64, chunk: Vec<u8>, proof: Vec<[u8; 32]>) {
+			let miner = ensure_signed(origin)?;
+
+			let mut dispute = Self::dispute(order_id, &miner).ok_or(Error::<T>::DisputeNotFound)?;
+			ensure!(dispute.kind == DisputeKind::DataLost, Error::<T>::NotProvable);
+			ensure!(<system::Module<T>>::block_number() <= dispute.deadline, Error::<T>::DisputeExpired);
+
+			let index = dispute.pending_chunks.iter().position(|c| *c == chunk_index)
+				.ok_or(Error::<T>::InvalidChunks)?;
+
+			let order = Self::order(order_id).ok_or(Error::<T>::OrderNotFound)?;
+			let comm_p = Self::commitment(order_id, &miner).ok_or(Error::<T>::CommitmentNotFound)?;
+			ensure!(
+				verify_chunk(order.size, chunk_index, &chunk, &proof, comm_p),
+				Error::<T>::InvalidProof
+			);
+
+			dispute.pending_chunks.swap_remove(index);
+			let proven = dispute.pending_chunks.is_empty();
+			Disputes::<T>::insert(order_id, &miner, dispute);
+
+			Self::deposit_event(RawEvent::DisputeChunkProven(order_id, miner.clone(), chunk_index));
+
+			if proven {
+				Self::resolve_dispute(order_id, miner, Verdict::UserAtFault);
 			}
-			Self::deposit_event(RawEvent::VerifyStorage(miner, true));
 		}
 
 
+		/// the arbitration rules the dispute of the replica.
+		#[weight = <T as Trait>::WeightInfo::arbitrate()]
+		fn arbitrate(origin, order_id: OrderId, miner: T::AccountId, verdict: Verdict) {
+			T::ArbitrationOrigin::ensure_origin(origin)?;
+
+			ensure!(Disputes::<T>::contains_key(order_id, &miner), Error::<T>::DisputeNotFound);
+			Self::resolve_dispute(order_id, miner, verdict);
+		}
+
+
+
+		/// the miner publishes what it offers to the storage market.
+		#[weight = <T as Trait>::WeightInfo::publish_ask()]
+		fn publish_ask(origin, unit_price: BalanceOf<T>, capacity: u128, region: Vec<u8>, hot: bool, bandwidth: BandwidthTier) {
+			let miner = ensure_signed(origin)?;
+
+			let miner_info = Self::miner(&miner).ok_or(Error::<T>::MinerNotFound)?;
+			ensure!(capacity > 0, Error::<T>::NoneCapacity);
+			ensure!(capacity <= miner_info.capacity, Error::<T>::InsufficientCapacity);
+			ensure!(
+				(!hot || miner_info.hot) && bandwidth <= miner_info.bandwidth,
+				Error::<T>::MissingCapability
+			);
+
+			Asks::<T>::insert(&miner, Ask {
+				unit_price,
+				capacity,
+				region,
+				hot,
+				bandwidth,
+			});
+
+			Self::deposit_event(RawEvent::PublishedAsk(miner));
+		}
+
+
+		/// the miner withdraws its ask.
+		#[weight = <T as Trait>::WeightInfo::cancel_ask()]
+		fn cancel_ask(origin) {
+			let miner = ensure_signed(origin)?;
+
+			ensure!(Asks::<T>::contains_key(&miner), Error::<T>::AskNotFound);
+			Asks::<T>::remove(&miner);
+
+			Self::deposit_event(RawEvent::CanceledAsk(miner));
+		}
+
+
+		/// the user publishes a bid, the offchain worker matches miners for it.
+		#[weight = <T as Trait>::WeightInfo::publish_bid(cid.len() as u32)]
+		fn publish_bid(origin, label: Vec<u8>, cid: Vec<u8>, size: u128, comm_p: [u8; 32], url: Option<Vec<u8>>, periods: u32, replicas: u32, region: Vec<u8>, class: DataClass, frequency: RetrievalFrequency, unit_price: BalanceOf<T>) {
+			let user = ensure_signed(origin)?;
+
+			let cid = Cid::parse(&cid).map_err(|_| Error::<T>::InvalidCid)?;
+			ensure!(periods > 0, Error::<T>::NonePeriods);
+			ensure!(replicas > 0 && replicas <= T::MaxReplicas::get(), Error::<T>::InvalidReplicas);
+
+			let bid_id = Self::next_bid_id();
+			Bids::<T>::insert(bid_id, Bid {
+				user: user.clone(),
+				label,
+				cid,
+				size,
+				comm_p,
+				url,
+				periods,
+				replicas,
+				region,
+				class,
+				frequency,
+				unit_price,
+			});
+			NextBidId::put(bid_id + 1);
+
+			Self::deposit_event(RawEvent::PublishedBid(user, bid_id));
+		}
+
+
+		/// the user withdraws the bid.
+		#[weight = <T as Trait>::WeightInfo::cancel_bid()]
+		fn cancel_bid(origin, bid_id: BidId) {
+			let user = ensure_signed(origin)?;
+
+			let bid = Self::bid(bid_id).ok_or(Error::<T>::BidNotFound)?;
+			ensure!(bid.user == user, Error::<T>::PermissionDenyed);
+			Bids::<T>::remove(bid_id);
+
+			Self::deposit_event(RawEvent::CanceledBid(user, bid_id));
+		}
+
+
+		/// the offchain worker submits the miners matched for the bid.
+		#[weight = <T as Trait>::WeightInfo::submit_match(miners.len() as u32)]
+		fn submit_match(origin, bid_id: BidId, miners: Vec<T::AccountId>) {
+			ensure_none(origin)?;
+
+			let bid = Self::bid(bid_id).ok_or(Error::<T>::BidNotFound)?;
+			Self::validate_match(&bid, &miners)?;
+
+			let order_id = Self::do_create_order(
+				bid.user.clone(),
+				miners.clone(),
+				bid.label,
+				bid.cid,
+				bid.size,
+				bid.comm_p,
+				bid.url,
+				bid.periods,
+				bid.class,
+				bid.frequency,
+			)?;
+
+			for miner in miners.iter() {
+				Asks::<T>::mutate(miner, |ask| if let Some(ask) = ask {
+					ask.capacity = ask.capacity.saturating_sub(bid.size);
+				});
+			}
+			Bids::<T>::remove(bid_id);
+
+			Self::deposit_event(RawEvent::MatchedBid(bid.user, bid_id, order_id));
+		}
+
 
 		/// the miner apply to recommended list.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::apply_to_recommended_list()]
 		fn apply_to_recommended_list(origin, amount: BalanceOf<T>) {
 
 			let miner = ensure_signed(origin)?;
@@ -407,7 +1939,7 @@ decl_module! {
 
 
 		/// the miner drop out recommended list.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::drop_out_recommended_list()]
 		fn drop_out_recommended_list(origin) {
 			let miner = ensure_signed(origin)?;
 
@@ -427,48 +1959,94 @@ decl_module! {
 
 		}
 
+		fn offchain_worker(_block: T::BlockNumber) {
+			if sp_io::offchain::is_validator() {
+				Self::match_bids();
+			}
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			let version = Self::storage_version();
+			if version == Releases::V1_0_0 {
+				Self::migrate_to_keyed_orders();
+				StorageVersion::put(Releases::V2_0_0);
+			}
+			if Self::storage_version() == Releases::V2_0_0 {
+				Self::migrate_to_block_terms();
+				StorageVersion::put(Releases::V3_0_0);
+			}
+			if Self::storage_version() == Releases::V3_0_0 {
+				Self::migrate_to_cids();
+				StorageVersion::put(Releases::V4_0_0);
+			}
+			if Self::storage_version() == Releases::V4_0_0 {
+				Self::migrate_to_tiers();
+				StorageVersion::put(Releases::V5_0_0);
+			}
+			if Self::storage_version() == Releases::V5_0_0 {
+				Self::migrate_to_ring_buffers();
+				StorageVersion::put(Releases::V6_0_0);
+			}
+			if Self::storage_version() == Releases::V6_0_0 {
+				Self::migrate_to_sectors();
+				StorageVersion::put(Releases::V7_0_0);
+			}
+			if version != Self::storage_version() {
+				T::MaximumBlockWeight::get()
+			} else {
+				0
+			}
+		}
+
 		fn on_finalize(n: T::BlockNumber) {
 			let current_block = n;
-			let n = n.saturated_into::<u64>();
-			// Check verifying result per 20 blocks,
-			// 20 blocks just 1 minute.
-			if n%20 != 0 { return }
-			let now = Self::get_now_ts();
-			Orders::<T>::mutate( |orders| {
-				for mut order in orders {
-					if order.status == OrderStatus::Confirmed {
-						let create_ts = order.create_ts;
-						let confirm_ts = order.update_ts;
-						for mo in &order.orders {
-							if now > order.duration + create_ts + DAY{
-								order.status = OrderStatus::Expired;
-							} else {
-								if now - order.update_ts >= DAY && mo.verify_result {
-									// verify result is ok, transfer one day's funds to miner
-									//  transfer to income address
-								   if let Some(miner) = Miners::<T>::get(&mo.miner){
-										T::StakingCurrency::repatriate_reserved(&order.user, &miner.stash_address, mo.day_price, BalanceStatus::Free);
-
-										debug::info!("miner: {:?}",&miner);
-
-										order.update_ts = now;
-
-										Self::update_history(current_block, mo.miner.clone(), mo.day_price);
-
-										Self::deposit_event(RawEvent::VerifyStorage(mo.miner.clone(), true));
-									}
-
-								} else {
-									// verify result expired or no verifying, punish miner
-									// Self::punish(&mo.miner, order.size);
-									Self::deposit_event(RawEvent::VerifyStorage(mo.miner.clone(), false));
-								}
-							}
-						}
 
-					}
+			for order_id in ChallengeRounds::<T>::take(current_block) {
+				Self::challenge_round(order_id);
+			}
+
+			// the disputes which are not ruled in their window.
+			for (order_id, miner) in DisputeDeadlines::<T>::take(current_block) {
+				match Self::dispute(order_id, &miner) {
+					Some(d) if d.deadline == current_block => {
+						Self::resolve_dispute(order_id, miner, d.kind.default_verdict())
+					},
+					_ => {},
 				}
-			});
+			}
+
+			for (miner, sector_id) in SealSeeds::<T>::take(current_block) {
+				Self::draw_seed(miner, sector_id, current_block);
+			}
+
+			// the pre-committed sectors whose seal is not proven in time.
+			for (miner, sector_id) in PreCommitDeadlines::<T>::take(current_block) {
+				match Self::sector(&miner, sector_id) {
+					Some(s) if s.status == SectorStatus::PreCommitted &&
+						s.seal_at + T::SealDuration::get() == current_block => {
+						Self::remove_sector(&miner, sector_id, s);
+						Self::deposit_event(RawEvent::SectorAborted(miner, sector_id));
+					},
+					_ => {},
+				}
+			}
+
+			// the miners who did not answer their challenges in time.
+			for (miner, sector_id) in ChallengeDeadlines::<T>::take(current_block) {
+				match Self::challenge(&miner, sector_id) {
+					Some(c) if c.deadline == current_block => Self::challenge_missed(miner, sector_id),
+					_ => {},
+				}
+			}
+
+			// only the orders due at this block are settled.
+			for order_id in DueOrders::<T>::take(current_block) {
+				Self::settle(order_id, current_block);
+			}
+
+			for order_id in PrunableOrders::<T>::take(current_block) {
+				Self::prune_order(order_id);
+			}
 		}
 
 	}
@@ -485,10 +2063,788 @@ impl<T: Trait> Module<T> {
 		<T::Moment as TryInto<u64>>::try_into(now).ok().unwrap()
 	}
 
-	fn find_miner_order(
-		miner: T::AccountId,
-		os: &mut Vec<MinerOrder<T::AccountId, BalanceOf<T>>>,
-	) -> Option<&mut MinerOrder<T::AccountId, BalanceOf<T>>> {
+	/// Create the order stored by `miners`, and reserve the funds of all replicas.
+	fn do_create_order(
+		user: T::AccountId,
+		miners: Vec<T::AccountId>,
+		label: Vec<u8>,
+		cid: Cid,
+		size: u128,
+		comm_p: [u8; 32],
+		url: Option<Vec<u8>>,
+		periods: u32,
+		class: DataClass,
+		frequency: RetrievalFrequency,
+	) -> result::Result<OrderId, DispatchError> {
+		let now = Self::get_now_ts();
+		let mut order_list = Vec::new();
+		let mut miner_infos = Vec::new();
+		let mut total_price = 0.saturated_into::<BalanceOf<T>>();
+
+		for miner in miners.iter() {
+			let mut miner_info = Self::miner(miner).ok_or(Error::<T>::MinerNotFound)?;
+
+			ensure!(miner_info.capacity >= size, Error::<T>::InsufficientCapacity);
+
+			miner_info.capacity = miner_info.capacity - size;
+
+			let period_price = Self::period_price(&miner_info, size, class, frequency)?;
+			let miner_total_price = period_price
+				.checked_mul(&periods.saturated_into::<BalanceOf<T>>())
+				.ok_or(Error::<T>::Overflow)?;
+			total_price = total_price.checked_add(&miner_total_price).ok_or(Error::<T>::Overflow)?;
+
+			order_list.push(MinerOrder {
+				miner: miner.clone(),
+				period_price,
+				total_price: miner_total_price,
+				verify_result: false,
+				verify_ts: now,
+				confirm_ts: 0,
+				url: url.clone(),
+			});
+			miner_infos.push(miner_info);
+		}
+
+		// reserve the funds of all replicas.
+		T::StakingCurrency::reserve(&user, total_price)?;
+
+		let order_id = Self::next_order_id();
+		let current_block = <system::Module<T>>::block_number();
+
+		let order = Order {
+			miner: miners[0].clone(),
+			label,
+			cid: cid.clone(),
+			size,
+			user: user.clone(),
+			orders: order_list,
+			status: OrderStatus::Created,
+			create_ts: now,
+			update_ts: now,
+			start: current_block,
+			periods,
+			settled: 0,
+			class,
+			frequency,
+		};
+		Self::deposit_event(RawEvent::OrderCreated(
+			order_id,
+			user.clone(),
+			size,
+			periods,
+			total_price,
+		));
+		for mo in &order.orders {
+			let miner = mo.miner.clone();
+			Self::deposit_event(RawEvent::ReplicaAssigned(order_id, miner, mo.period_price));
+		}
+		Orders::<T>::insert(order_id, order);
+		DataCommitments::<T>::insert(order_id, comm_p);
+		DueOrders::<T>::mutate(current_block + T::SettlementPeriod::get(), |ids| ids.push(order_id));
+		Self::schedule_rounds(order_id, current_block, frequency);
+
+		NextOrderId::put(order_id + 1);
+
+		UserOrders::<T>::mutate(&user, |ids| ids.push(order_id));
+		HashOrders::mutate(&cid, |ids| ids.push(order_id));
+
+		Self::append_or_replace_orders(order_id);
+
+		for miner_info in miner_infos {
+			MinerOrders::<T>::mutate(&miner_info.account_id, |ids| ids.push(order_id));
+			Self::update_miner_history(miner_info.account_id.clone(), order_id);
+			Miners::<T>::insert(miner_info.account_id.clone(), miner_info);
+		}
+
+		Ok(order_id)
+	}
+
+	/// Reserve or unreserve the funds of the miner when its staking changes.
+	fn update_staking(
+		miner: &T::AccountId,
+		old: BalanceOf<T>,
+		new: BalanceOf<T>,
+	) -> DispatchResult {
+		if new > old {
+			T::StakingCurrency::reserve(miner, new - old)?;
+		} else {
+			T::StakingCurrency::unreserve(miner, old - new);
+		}
+		Ok(())
+	}
+
+	/// Check the miners against the bid, and their current capacity and price.
+	fn validate_match(
+		bid: &Bid<T::AccountId, BalanceOf<T>>,
+		miners: &[T::AccountId],
+	) -> DispatchResult {
+		ensure!(miners.len() == bid.replicas as usize, Error::<T>::InvalidReplicas);
+
+		let max_price = bid
+			.unit_price
+			.checked_mul(&bid.size.saturated_into::<BalanceOf<T>>())
+			.ok_or(Error::<T>::Overflow)?;
+		let mut total_price = 0.saturated_into::<BalanceOf<T>>();
+		for (i, miner) in miners.iter().enumerate() {
+			ensure!(!miners[..i].contains(miner), Error::<T>::DuplicateMiner);
+
+			let ask = Self::ask(miner).ok_or(Error::<T>::AskNotFound)?;
+			ensure!(ask.matches(bid), Error::<T>::MismatchedAsk);
+
+			let miner_info = Self::miner(miner).ok_or(Error::<T>::MinerNotFound)?;
+			ensure!(miner_info.capacity >= bid.size, Error::<T>::InsufficientCapacity);
+			ensure!(
+				miner_info.can_store(bid.class, bid.frequency),
+				Error::<T>::MissingCapability
+			);
+			let period_price =
+				Self::period_price(&miner_info, bid.size, bid.class, bid.frequency)?;
+			ensure!(period_price <= max_price, Error::<T>::MismatchedAsk);
+
+			let miner_total_price = period_price
+				.checked_mul(&bid.periods.saturated_into::<BalanceOf<T>>())
+				.ok_or(Error::<T>::Overflow)?;
+			total_price = total_price.checked_add(&miner_total_price).ok_or(Error::<T>::Overflow)?;
+		}
+		ensure!(T::StakingCurrency::can_reserve(&bid.user, total_price), Error::<T>::NotEnoughMoney);
+
+		Ok(())
+	}
+
+	/// Match the cheapest asks for the bids, and submit the matches as unsigned transactions.
+	fn match_bids() {
+		let asks = Asks::<T>::iter().collect::<Vec<_>>();
+		for (bid_id, bid) in Bids::<T>::iter().take(NUM_MATCH_PER_BLOCK) {
+			let mut candidates = asks
+				.iter()
+				.filter(|(miner, ask)| {
+					ask.matches(&bid) &&
+						Self::miner(miner).map_or(false, |m| {
+							m.capacity >= bid.size &&
+								m.can_store(bid.class, bid.frequency) &&
+								Self::within_price(
+									&m,
+									bid.size,
+									bid.class,
+									bid.frequency,
+									bid.unit_price,
+								)
+						})
+				})
+				.collect::<Vec<_>>();
+			if candidates.len() < bid.replicas as usize {
+				continue
+			}
+			candidates.sort_by(|a, b| a.1.unit_price.cmp(&b.1.unit_price));
+
+			let miners = candidates
+				.into_iter()
+				.take(bid.replicas as usize)
+				.map(|(miner, _)| miner.clone())
+				.collect::<Vec<_>>();
+
+			let call = Call::submit_match(bid_id, miners);
+			if SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into()).is_err() {
+				debug::error!("failed to submit the match of bid {}", bid_id);
+			}
+		}
+	}
+
+	/// A page of the orders of the user, starting from the `start`th one.
+	pub fn orders_of_user(
+		user: &T::AccountId,
+		start: u32,
+		count: u32,
+	) -> Vec<(OrderId, OrderOf<T>)> {
+		Self::page_of_orders(Self::user_orders(user), start, count)
+	}
+
+	/// A page of the orders stored by the miner, starting from the `start`th one.
+	pub fn orders_of_miner(
+		miner: &T::AccountId,
+		start: u32,
+		count: u32,
+	) -> Vec<(OrderId, OrderOf<T>)> {
+		Self::page_of_orders(Self::miner_orders(miner), start, count)
+	}
+
+	/// At most `MAX_PAGE_LEN` orders of `ids`, starting from the `start`th one.
+	fn page_of_orders(ids: Vec<OrderId>, start: u32, count: u32) -> Vec<(OrderId, OrderOf<T>)> {
+		ids.into_iter()
+			.skip(start as usize)
+			.take(count.min(MAX_PAGE_LEN) as usize)
+			.filter_map(|id| Self::order(id).map(|order| (id, order)))
+			.collect()
+	}
+
+	/// The capacity of the miner used by the live orders.
+	fn used_capacity(miner: &T::AccountId) -> u128 {
+		Self::miner_orders(miner)
+			.into_iter()
+			.filter_map(Self::order)
+			.filter(|order| order.status.is_live())
+			.fold(0u128, |used, order| used.saturating_add(order.size))
+	}
+
+	/// The miner with its used capacity and reputation.
+	pub fn miner_details(miner: &T::AccountId) -> Option<MinerDetails<T::AccountId, BalanceOf<T>>> {
+		let info = Self::miner(miner)?;
+		Some(MinerDetails {
+			free_capacity: info.capacity,
+			used_capacity: Self::used_capacity(miner),
+			reputation: Self::reputation(miner),
+			score: Self::reputation_score(miner),
+			info,
+		})
+	}
+
+	/// What the miner charges for storing `size` bytes of `class` retrieved at `frequency` for
+	/// `periods` settlement periods, `None` if the miner is not able to store it.
+	pub fn quote(
+		miner: &T::AccountId,
+		size: u128,
+		periods: u32,
+		class: DataClass,
+		frequency: RetrievalFrequency,
+	) -> Option<Quote<BalanceOf<T>>> {
+		let info = Self::miner(miner)?;
+		if !info.can_store(class, frequency) {
+			return None
+		}
+		let period_price = Self::period_price(&info, size, class, frequency).ok()?;
+		let total_price = period_price.saturating_mul(periods.saturated_into::<BalanceOf<T>>());
+		Some(Quote { periods, period_price, total_price })
+	}
+
+	/// The price of storing `size` bytes of `class` retrieved at `frequency` with the miner for
+	/// a settlement period.
+	///
+	/// the reference price of the miner is converted at the current price of the feed, then
+	/// raised by the tiers of the data.
+	pub fn period_price(
+		miner: &Miner<T::AccountId, BalanceOf<T>>,
+		size: u128,
+		class: DataClass,
+		frequency: RetrievalFrequency,
+	) -> result::Result<BalanceOf<T>, DispatchError> {
+		let base = match Self::reference_price(&miner.account_id) {
+			None => miner
+				.unit_price
+				.saturated_into::<u128>()
+				.checked_mul(size)
+				.ok_or(Error::<T>::Overflow)?,
+			Some(reference) => {
+				let price = T::PriceFeed::price().ok_or(Error::<T>::PriceUnavailable)?;
+				helpers_128bit::multiply_by_rational(
+					reference.checked_mul(size).ok_or(Error::<T>::Overflow)?,
+					price,
+					GB as u128,
+				)
+				.map_err(|_| Error::<T>::Overflow)?
+			},
+		};
+		Ok(tiered_price(base, class, frequency).saturated_into::<BalanceOf<T>>())
+	}
+
+	/// Whether the miner stores `size` bytes of the tiers at no more than `unit_price` per byte.
+	fn within_price(
+		miner: &Miner<T::AccountId, BalanceOf<T>>,
+		size: u128,
+		class: DataClass,
+		frequency: RetrievalFrequency,
+		unit_price: BalanceOf<T>,
+	) -> bool {
+		Self::period_price(miner, size, class, frequency).map_or(false, |price| {
+			price <= unit_price.saturating_mul(size.saturated_into::<BalanceOf<T>>())
+		})
+	}
+
+	/// The score of the miner's reputation.
+	pub fn reputation_score(miner: &T::AccountId) -> Permill {
+		let violation_times = Self::miner(miner).map_or(0, |m| m.violation_times);
+		Self::reputation(miner).score(violation_times)
+	}
+
+	/// The recommend list ranked by the reserved amount blended with the reputation.
+	///
+	/// the amount of a miner counts as `amount * ((1 - weight) + weight * score)`.
+	pub fn recommended_miners() -> Vec<(T::AccountId, BalanceOf<T>)> {
+		let weight = T::ReputationWeight::get();
+		let mut list: Vec<_> = Self::recommend_list()
+			.into_iter()
+			.map(|(miner, amount)| {
+				let factor = Permill::one()
+					.saturating_sub(weight)
+					.saturating_add(weight.saturating_mul(Self::reputation_score(&miner)));
+				(miner, factor * amount)
+			})
+			.collect();
+		list.sort_by(|a, b| b.1.cmp(&a.1));
+		list
+	}
+
+	/// Select the miners able to store the data from the recommend list until there are
+	/// `replicas` miners.
+	fn select_miners(
+		miners: &mut Vec<T::AccountId>,
+		replicas: usize,
+		size: u128,
+		class: DataClass,
+		frequency: RetrievalFrequency,
+		unit_price: BalanceOf<T>,
+	) {
+		for (miner, _) in Self::recommended_miners() {
+			if miners.len() >= replicas {
+				break
+			}
+			if miners.contains(&miner) {
+				continue
+			}
+			if let Some(miner_info) = Self::miner(&miner) {
+				if miner_info.capacity >= size &&
+					miner_info.can_store(class, frequency) &&
+					Self::within_price(&miner_info, size, class, frequency, unit_price)
+				{
+					miners.push(miner);
+				}
+			}
+		}
+	}
+
+	/// Settle the current period of the order.
+	///
+	/// every replica is settled independently, the miner who proved the storage is paid for
+	/// the period, otherwise the funds of the period are refunded to the user.
+	///
+	/// the orders migrated from before the sectors have no commitment of their data, their
+	/// replicas can't be sealed or challenged, so they end at their next settlement.
+	fn settle(order_id: OrderId, n: T::BlockNumber) {
+		let mut order = match Self::order(order_id) {
+			Some(order) if order.status.is_live() => order,
+			// deleted orders leave their due entries behind.
+			_ => return,
+		};
+		if Self::is_disputed(order_id) {
+			// settled when the disputes are ruled.
+			FrozenSettlements::<T>::insert(order_id, n);
+			return
+		}
+		let period = T::SettlementPeriod::get().saturated_into::<u128>();
+		let legacy = !DataCommitments::<T>::contains_key(order_id);
+		if order.settled + 1 >= order.periods && !legacy {
+			if let Some(extra_periods) = Self::auto_renew(order_id) {
+				if Self::do_renew_order(order_id, &mut order, extra_periods).is_err() {
+					debug::info!("failed to auto renew the order {}", order_id);
+				}
+			}
+		}
+		let expired = order.settled + 1 >= order.periods || legacy;
+		// the last settlement releases all the funds left.
+		let unsettled = if expired { order.periods.saturating_sub(order.settled).max(1) } else { 1 };
+		order.settled = order.settled.saturating_add(unsettled);
+
+		let mut refunded = BalanceOf::<T>::zero();
+		for mo in &mut order.orders {
+			let confirmed = Commitments::<T>::contains_key(order_id, &mo.miner);
+			let proven = confirmed && mo.verify_result;
+			// the whole period is elapsed.
+			let (paid, refund) = split_unsettled(
+				mo.period_price.saturated_into::<u128>(),
+				unsettled,
+				period,
+				period,
+				proven,
+			);
+			let paid = paid.saturated_into::<BalanceOf<T>>();
+			let refund = refund.saturated_into::<BalanceOf<T>>();
+			Self::pay_miner(&order.user, &mo.miner, paid);
+			T::StakingCurrency::unreserve(&order.user, refund);
+			refunded += refund;
+			Self::deposit_event(RawEvent::ReplicaSettled(
+				order_id,
+				mo.miner.clone(),
+				unsettled,
+				paid,
+				refund,
+			));
+			if proven {
+				Self::update_history(n, mo.miner.clone(), paid);
+			}
+			if !confirmed {
+				// this replica is not confirmed yet.
+				continue
+			}
+			Reputations::<T>::mutate(&mo.miner, |r| {
+				if proven {
+					r.periods_proven = r.periods_proven.saturating_add(unsettled.into());
+				} else {
+					r.periods_missed = r.periods_missed.saturating_add(unsettled.into());
+				}
+				if expired {
+					r.orders_completed = r.orders_completed.saturating_add(1);
+				}
+			});
+			// the miner has to prove the storage again for the next period.
+			mo.verify_result = false;
+			if !expired {
+				Self::challenge_replica(order_id, &mo.miner);
+			}
+		}
+
+		if !refunded.is_zero() {
+			Self::deposit_event(RawEvent::OrderRefunded(order_id, order.user.clone(), refunded));
+		}
+
+		order.update_ts = Self::get_now_ts();
+		if expired {
+			order.status = OrderStatus::Expired;
+			Self::release_order(order_id, &order);
+			Self::deposit_event(RawEvent::OrderExpired(order_id, order.user.clone()));
+		} else {
+			DueOrders::<T>::mutate(n + T::SettlementPeriod::get(), |ids| ids.push(order_id));
+			Self::schedule_rounds(order_id, n, order.frequency);
+		}
+		Orders::<T>::insert(order_id, order);
+	}
+
+	/// Schedule the extra challenge rounds of the period starting at `from`, the first round is
+	/// opened by the settlement.
+	fn schedule_rounds(order_id: OrderId, from: T::BlockNumber, frequency: RetrievalFrequency) {
+		let rounds = frequency.challenges_per_period();
+		let period = T::SettlementPeriod::get();
+		for round in 1..rounds {
+			let at = from + period * round.into() / rounds.into();
+			ChallengeRounds::<T>::mutate(at, |ids| ids.push(order_id));
+		}
+	}
+
+	/// Challenge the replicas of the order again in the middle of the period.
+	///
+	/// only the replicas proven in this period are challenged, the others have already failed
+	/// the period and have an open challenge.
+	fn challenge_round(order_id: OrderId) {
+		let order = match Self::order(order_id) {
+			Some(order) if order.status.is_live() => order,
+			_ => return,
+		};
+		for mo in &order.orders {
+			if mo.verify_result && Commitments::<T>::contains_key(order_id, &mo.miner) {
+				Self::challenge_replica(order_id, &mo.miner);
+			}
+		}
+	}
+
+	/// Reserve the funds of `extra_periods` more periods at the price of the order.
+	fn do_renew_order(
+		order_id: OrderId,
+		order: &mut OrderOf<T>,
+		extra_periods: u32,
+	) -> DispatchResult {
+		let periods = order.periods.checked_add(extra_periods).ok_or(Error::<T>::Overflow)?;
+
+		// nothing is changed if any price overflows.
+		let mut total_price = 0.saturated_into::<BalanceOf<T>>();
+		let mut replica_prices = Vec::with_capacity(order.orders.len());
+		for mo in &order.orders {
+			let price = mo
+				.period_price
+				.checked_mul(&extra_periods.saturated_into::<BalanceOf<T>>())
+				.ok_or(Error::<T>::Overflow)?;
+			total_price = total_price.checked_add(&price).ok_or(Error::<T>::Overflow)?;
+			replica_prices.push(mo.total_price.checked_add(&price).ok_or(Error::<T>::Overflow)?);
+		}
+		T::StakingCurrency::reserve(&order.user, total_price)
+			.map_err(|_| Error::<T>::NotEnoughMoney)?;
+
+		for (mo, price) in order.orders.iter_mut().zip(replica_prices) {
+			mo.total_price = price;
+		}
+		order.periods = periods;
+		order.update_ts = Self::get_now_ts();
+
+		Self::deposit_event(RawEvent::OrderRenewed(
+			order_id,
+			order.user.clone(),
+			extra_periods,
+			total_price,
+		));
+		Ok(())
+	}
+
+	/// The order is over, give the capacity back to the miners, take its pieces out of the
+	/// sectors and remove the order after the retention window.
+	fn release_order(order_id: OrderId, order: &OrderOf<T>) {
+		for mo in &order.orders {
+			Miners::<T>::mutate(&mo.miner, |m| if let Some(m) = m {
+				m.capacity = m.capacity.saturating_add(order.size);
+			});
+			Self::release_sectors(order_id, &mo.miner);
+		}
+		AutoRenew::remove(order_id);
+
+		let prune_at = <system::Module<T>>::block_number() + T::OrderRetention::get();
+		PrunableOrders::<T>::mutate(prune_at, |ids| ids.push(order_id));
+	}
+
+	/// Remove the finished order and its indices.
+	fn prune_order(order_id: OrderId) {
+		let order = match Self::order(order_id) {
+			Some(order) if !order.status.is_live() => order,
+			_ => return,
+		};
+		Orders::<T>::remove(order_id);
+		DataCommitments::<T>::remove(order_id);
+		Commitments::<T>::remove_prefix(order_id);
+		OrderSectors::<T>::remove_prefix(order_id);
+
+		UserOrders::<T>::mutate_exists(&order.user, |ids| Self::remove_order_id(ids, order_id));
+		HashOrders::mutate_exists(&order.cid, |ids| Self::remove_order_id(ids, order_id));
+		for mo in &order.orders {
+			MinerOrders::<T>::mutate_exists(&mo.miner, |ids| Self::remove_order_id(ids, order_id));
+			MinerHistoryOf::<T>::retain(&mo.miner, |id| *id != order_id);
+		}
+		ListOrderOf::retain(|id| *id != order_id);
+	}
+
+	fn remove_order_id(ids: &mut Option<Vec<OrderId>>, order_id: OrderId) {
+		if let Some(v) = ids {
+			v.retain(|id| *id != order_id);
+			if v.is_empty() {
+				*ids = None;
+			}
+		}
+	}
+
+	/// Pay the reserved funds of the user to the stash of the miner, the funds are refunded
+	/// if the miner is gone.
+	fn pay_miner(user: &T::AccountId, miner: &T::AccountId, amount: BalanceOf<T>) {
+		if amount.is_zero() {
+			return
+		}
+		match Self::miner(miner) {
+			Some(miner_info) => {
+				let _ = T::StakingCurrency::repatriate_reserved(
+					user,
+					&miner_info.stash_address,
+					amount,
+					BalanceStatus::Free,
+				);
+			},
+			None => {
+				T::StakingCurrency::unreserve(user, amount);
+			},
+		}
+	}
+
+	/// Challenge the active sectors in which the replica of the order is sealed, the sectors
+	/// which are already challenged keep their challenge.
+	fn challenge_replica(order_id: OrderId, miner: &T::AccountId) {
+		for sector_id in Self::order_sectors(order_id, miner) {
+			let active = Self::sector(miner, sector_id)
+				.map_or(false, |s| s.status == SectorStatus::Active);
+			if active && !Challenges::<T>::contains_key(miner, sector_id) {
+				Self::open_challenge(miner, sector_id);
+			}
+		}
+	}
+
+	/// Open a new challenge for the sector of the miner, it replaces the old one.
+	fn open_challenge(miner: &T::AccountId, sector_id: SectorId) {
+		let random = T::Randomness::random(&(b"ipse/challenge", miner, sector_id).encode());
+		let mut seed = random.as_ref();
+		let node_index = u64::decode(&mut seed).unwrap_or_default();
+		let nonce = u64::decode(&mut seed).unwrap_or_default();
+
+		let node_index = node_index % (SECTOR_SIZE / NODE_SIZE);
+		let deadline = <system::Module<T>>::block_number() + T::ChallengeDuration::get();
+
+		Challenges::<T>::insert(miner, sector_id, Challenge { node_index, nonce, deadline });
+		ChallengeDeadlines::<T>::mutate(deadline, |c| c.push((miner.clone(), sector_id)));
+
+		Self::deposit_event(RawEvent::ChallengeOpened(
+			miner.clone(),
+			sector_id,
+			node_index,
+			deadline,
+		));
+	}
+
+	/// The miner did not answer the challenge of the sector in time.
+	fn challenge_missed(miner: T::AccountId, sector_id: SectorId) {
+		Challenges::<T>::remove(&miner, sector_id);
+		if let Some(sector) = Self::sector(&miner, sector_id) {
+			Reputations::<T>::mutate(&miner, |r| {
+				r.proofs_failed = r.proofs_failed.saturating_add(1)
+			});
+			Self::sector_proven(&miner, sector_id, sector, false);
+		}
+		Self::deposit_event(RawEvent::StorageProven(miner, sector_id, false));
+	}
+
+	/// Record the result of the challenge on the orders sealed in the sector, a failed sector
+	/// is slashed and fails the replicas until it is proven again.
+	fn sector_proven(
+		miner: &T::AccountId,
+		sector_id: SectorId,
+		mut sector: SectorOf<T>,
+		verify_result: bool,
+	) {
+		sector.faulty = !verify_result;
+		Sectors::<T>::insert(miner, sector_id, &sector);
+
+		let now = Self::get_now_ts();
+		for piece in &sector.pieces {
+			// a replica in several sectors is proven if none of them is faulty.
+			let proven = verify_result &&
+				Self::order_sectors(piece.order_id, miner)
+					.into_iter()
+					.all(|id| Self::sector(miner, id).map_or(true, |s| !s.faulty));
+			Orders::<T>::mutate(piece.order_id, |o| if let Some(order) = o {
+				if !order.status.is_confirmed() {
+					return
+				}
+				if let Some(mo) = Self::find_miner_order(miner.clone(), &mut order.orders) {
+					mo.verify_ts = now;
+					mo.verify_result = proven;
+				}
+			});
+		}
+
+		if !verify_result {
+			let imbalance = Self::fine(miner, sector.size());
+			Self::deposit_event(RawEvent::SectorSlashed(
+				miner.clone(),
+				sector_id,
+				imbalance.peek(),
+			));
+			T::StakingSlash::on_unbalanced(imbalance);
+		}
+	}
+
+	/// Draw the seed of the seal proof of the pre-committed sector.
+	fn draw_seed(miner: T::AccountId, sector_id: SectorId, n: T::BlockNumber) {
+		Sectors::<T>::mutate(&miner, sector_id, |sector| {
+			let sector = match sector {
+				Some(s) if s.status == SectorStatus::PreCommitted && s.seal_at == n => s,
+				_ => return,
+			};
+			let random = T::Randomness::random(&(b"ipse/seal", &miner, sector_id).encode());
+			sector.seed = Some(random.using_encoded(sp_io::hashing::blake2_256));
+			let deadline = n + T::SealDuration::get();
+			PreCommitDeadlines::<T>::mutate(deadline, |d| d.push((miner.clone(), sector_id)));
+		});
+	}
+
+	/// The id with which the miner seals the sector, so that the replicas of the same data in
+	/// two sectors are different.
+	pub fn replica_id(miner: &T::AccountId, sector_id: SectorId, comm_d: &[u8; 32]) -> [u8; 32] {
+		(b"ipse/replica", miner, sector_id, comm_d).using_encoded(sp_io::hashing::blake2_256)
+	}
+
+	/// The pieces of the replica in the sectors of the miner, with the status of the sectors.
+	fn pieces_of(order_id: OrderId, miner: &T::AccountId) -> Vec<(SectorStatus, Piece)> {
+		Self::order_sectors(order_id, miner)
+			.into_iter()
+			.filter_map(|id| Self::sector(miner, id))
+			.flat_map(|s| {
+				let status = s.status;
+				s.pieces.into_iter().filter(|p| p.order_id == order_id).map(move |p| (status, p))
+			})
+			.collect()
+	}
+
+	/// Confirm the replica once all its data is sealed in active sectors, the CommP that the
+	/// sealed pieces are proven against is registered.
+	fn confirm_replica(order_id: OrderId, miner: &T::AccountId) {
+		let comm_p = match Self::data_commitment(order_id) {
+			Some(comm_p) => comm_p,
+			None => return,
+		};
+		let sealed = Self::pieces_of(order_id, miner)
+			.iter()
+			.filter(|(status, _)| *status == SectorStatus::Active)
+			.fold(0u128, |size, (_, p)| size.saturating_add(p.size));
+		let url = Self::miner(miner).map(|m| m.url);
+
+		let confirmed = Orders::<T>::mutate(order_id, |o| {
+			let order = match o {
+				Some(order) if order.status.is_live() && sealed >= order.size => order,
+				_ => return false,
+			};
+			// the replicas confirmed before the sectors are only sealed again.
+			if Commitments::<T>::contains_key(order_id, miner) {
+				return false
+			}
+			let now = Self::get_now_ts();
+			match Self::find_miner_order(miner.clone(), &mut order.orders) {
+				Some(mo) => {
+					mo.confirm_ts = now;
+					mo.url = url;
+				},
+				None => return false,
+			}
+			Commitments::<T>::insert(order_id, miner, comm_p);
+
+			order.update_ts = now;
+			order.status = if order
+				.orders
+				.iter()
+				.all(|mo| Commitments::<T>::contains_key(order_id, &mo.miner))
+			{
+				OrderStatus::Confirmed
+			} else {
+				OrderStatus::PartiallyConfirmed
+			};
+			true
+		});
+		if confirmed {
+			Self::deposit_event(RawEvent::OrderConfirmed(order_id, miner.clone(), comm_p));
+		}
+	}
+
+	/// Take the pieces of the replica out of the sectors, the sectors left empty are removed.
+	fn release_sectors(order_id: OrderId, miner: &T::AccountId) {
+		for sector_id in OrderSectors::<T>::take(order_id, miner) {
+			let mut sector = match Self::sector(miner, sector_id) {
+				Some(sector) => sector,
+				None => continue,
+			};
+			sector.pieces.retain(|p| p.order_id != order_id);
+			if sector.pieces.is_empty() {
+				Self::remove_sector(miner, sector_id, sector);
+				Self::deposit_event(RawEvent::SectorTerminated(miner.clone(), sector_id));
+			} else {
+				Sectors::<T>::insert(miner, sector_id, sector);
+			}
+		}
+	}
+
+	fn remove_sector(miner: &T::AccountId, sector_id: SectorId, sector: SectorOf<T>) {
+		for piece in &sector.pieces {
+			OrderSectors::<T>::mutate_exists(piece.order_id, miner, |ids| {
+				if let Some(v) = ids {
+					v.retain(|id| *id != sector_id);
+					if v.is_empty() {
+						*ids = None;
+					}
+				}
+			});
+		}
+		Sectors::<T>::remove(miner, sector_id);
+		Challenges::<T>::remove(miner, sector_id);
+	}
+
+	/// How many chunks that the data is split into.
+	fn chunks_of(size: u128) -> u64 {
+		let chunks = (size + CHUNK_SIZE - 1) / CHUNK_SIZE;
+		(chunks as u64).max(1)
+	}
+
+	fn find_miner_order(
+		miner: T::AccountId,
+		os: &mut Vec<MinerOrder<T::AccountId, BalanceOf<T>>>,
+	) -> Option<&mut MinerOrder<T::AccountId, BalanceOf<T>>> {
 		for o in os {
 			if o.miner == miner {
 				return Some(o)
@@ -535,63 +2891,36 @@ impl<T: Trait> Module<T> {
 		Ok(())
 	}
 
-	fn append_or_replace_orders(order: Order<T::AccountId, BalanceOf<T>>) {
-		ListOrder::<T>::mutate(|orders| {
-			let len = orders.len();
-			if len == NUM_LIST_ORDER_LEN {
-				let pre = len - NUM_LIST_HISTORY_LEN;
-				let new_vec = orders.split_off(pre);
-				let orders = new_vec;
-			}
-			orders.push(order);
-			debug::info!("orders vector: {:?}", orders);
-		});
+	fn append_or_replace_orders(order_id: OrderId) {
+		ListOrderOf::append(order_id, T::ListOrderLen::get());
 	}
 
-	fn update_miner_history(miner: T::AccountId, order: Order<T::AccountId, BalanceOf<T>>) {
-		let mut miner_history = MinerHistory::<T>::get(&miner);
-		let len = miner_history.len();
-
-		if len >= NUM_LIST_HISTORY_LEN {
-			let pre = len - NUM_LIST_HISTORY_LEN;
-			let new_vec = miner_history.split_off(pre);
-			let miner_history = new_vec;
-		}
-		miner_history.push(order);
-		<MinerHistory<T>>::insert(miner, miner_history);
+	fn update_miner_history(miner: T::AccountId, order_id: OrderId) {
+		MinerHistoryOf::<T>::append(&miner, order_id, T::MinerHistoryLen::get());
 	}
 
 	fn update_history(n: T::BlockNumber, miner: T::AccountId, amount: BalanceOf<T>) {
-		let mut history = <History<T>>::get(miner.clone());
-		let miner_cp = miner.clone();
-		if history.is_some() {
-			let mut vec = history.clone().unwrap().history;
-			let num = history.clone().unwrap().total_num;
-			vec.push((n, amount));
-
-			let len = vec.len();
-			if len >= 100 {
-				let pre = len - 100;
-				let new_vec = vec.split_off(pre);
-				vec = new_vec;
-			}
-
-			history = Some(MiningHistory {
-				// miner: miner_cp,
-				total_num: num + 1u64,
-				history: vec,
-			});
-		} else {
-			let mut vec = vec![];
-			vec.push((n, amount));
-			history = Some(MiningHistory {
-				// miner: miner_cp,
-				total_num: 1u64,
-				history: vec,
-			});
+		RewardNums::<T>::mutate(&miner, |num| *num = num.saturating_add(1));
+		HistoryOf::<T>::append(&miner, (n, amount), T::HistoryLen::get());
+	}
+
+	/// The latest orders of the miner, from the oldest.
+	pub fn miner_history(miner: &T::AccountId) -> Vec<OrderId> {
+		MinerHistoryOf::<T>::items(miner)
+	}
+
+	/// The latest rewards of the miner, `None` if it is never rewarded.
+	pub fn history(miner: &T::AccountId) -> Option<MiningHistory<BalanceOf<T>, T::BlockNumber>> {
+		let total_num = Self::reward_num(miner);
+		if total_num == 0 {
+			return None
 		}
+		Some(MiningHistory { total_num, history: HistoryOf::<T>::items(miner) })
+	}
 
-		<History<T>>::insert(miner, history.unwrap());
+	/// The latest orders, from the oldest.
+	pub fn list_order() -> Vec<OrderId> {
+		ListOrderOf::items()
 	}
 
 	fn sort_after(
@@ -616,22 +2945,510 @@ impl<T: Trait> Module<T> {
 		Ok(())
 	}
 
-	fn punish(miner: &T::AccountId, size: u128) {
+	/// Move the orders from the single `Vec` (where the order id is the index of the vec)
+	/// into the `Orders` map, and rebuild the indices, `ListOrder` and `MinerHistory`.
+	fn migrate_to_keyed_orders() {
+		// the chains which never stored orders in the `Vec` have nothing to migrate.
+		if !have_storage_value(b"Ipse", b"Orders", &[]) {
+			return
+		}
+		let orders = take_storage_value::<Vec<OrderV2<T::AccountId, BalanceOf<T>>>>(
+			b"Ipse",
+			b"Orders",
+			&[],
+		)
+		.unwrap_or_default();
+
+		let mut next_order_id = 0;
+		for (index, order) in orders.iter().enumerate() {
+			let order_id = index as OrderId;
+			UserOrders::<T>::mutate(&order.user, |ids| ids.push(order_id));
+			MinerOrders::<T>::mutate(&order.miner, |ids| ids.push(order_id));
+			// still in the old layout, `migrate_to_block_terms` translates it,
+			// `HashOrders` is built by `migrate_to_cids`.
+			unhashed::put(&Orders::<T>::hashed_key_for(order_id), order);
+			next_order_id = next_order_id.max(order_id + 1);
+		}
+		NextOrderId::mutate(|id| *id = (*id).max(next_order_id));
+
+		// `ListOrder` and `MinerHistory` kept copies of the orders, find their ids.
+		let list = take_storage_value::<Vec<OrderV2<T::AccountId, BalanceOf<T>>>>(
+			b"Ipse",
+			b"ListOrder",
+			&[],
+		)
+		.unwrap_or_default();
+		// still in the old layout, `migrate_to_ring_buffers` moves them into the ring buffers.
+		put_storage_value(
+			b"Ipse",
+			b"ListOrder",
+			&[],
+			list.iter().filter_map(|o| Self::find_order_id(&orders, o)).collect::<Vec<OrderId>>(),
+		);
+
+		let histories = StorageIterator::<Vec<OrderV2<T::AccountId, BalanceOf<T>>>>::new(
+			b"Ipse",
+			b"MinerHistory",
+		);
+		for (key, history) in histories {
+			let ids = history
+				.iter()
+				.filter_map(|o| Self::find_order_id(&orders, o))
+				.collect::<Vec<OrderId>>();
+			put_storage_value(b"Ipse", b"MinerHistory", &key, ids);
+		}
+
+		debug::info!("migrated {} orders to keyed storage", orders.len());
+	}
+
+	fn find_order_id(
+		orders: &[OrderV2<T::AccountId, BalanceOf<T>>],
+		order: &OrderV2<T::AccountId, BalanceOf<T>>,
+	) -> Option<OrderId> {
+		orders
+			.iter()
+			.position(|o| {
+				o.user == order.user &&
+					o.miner == order.miner &&
+					o.hash == order.hash &&
+					o.create_ts == order.create_ts
+			})
+			.map(|index| index as OrderId)
+	}
+
+	/// Express the terms of the orders in blocks and schedule the live orders.
+	///
+	/// The timestamps are converted with the expected block time, the old orders were
+	/// settled once a day, so the settlement period is expected to be a day.
+	///
+	/// The confirmed replicas of the live orders are committed to the hash of the old content
+	/// hash, the old orders have no merkle root, so that they are still paid when proven.
+	fn migrate_to_block_terms() {
+		let current_block = <system::Module<T>>::block_number();
+		let now = Self::get_now_ts();
+		let period = T::SettlementPeriod::get();
+
+		let block_time = <T::Moment as TryInto<u64>>::try_into(T::MinimumPeriod::get())
+			.ok()
+			.unwrap_or_default()
+			.saturating_mul(2)
+			.max(1);
+		let period_time = block_time.saturating_mul(period.saturated_into::<u64>()).max(1);
+
+		let orders = Self::legacy_items::<OrderV2<T::AccountId, BalanceOf<T>>>(b"Orders");
+		for (key, old) in orders {
+			let order_id = match OrderId::decode(&mut Twox64Concat::reverse(&key)) {
+				Ok(order_id) => order_id,
+				Err(_) => continue,
+			};
+			let periods = (old.duration / period_time).max(1).saturated_into::<u32>();
+			let settled = (old.update_ts.saturating_sub(old.create_ts) / period_time)
+				.saturated_into::<u32>()
+				.min(periods);
+			let elapsed = now.saturating_sub(old.create_ts) / block_time;
+			let start = current_block.saturating_sub(elapsed.saturated_into());
+
+			if old.status.is_live() {
+				let settled_until = start.saturating_add(period.saturating_mul(settled.into()));
+				let due = settled_until.saturating_add(period).max(current_block + 1u32.into());
+				DueOrders::<T>::mutate(due, |ids| ids.push(order_id));
+
+				let root = sp_io::hashing::blake2_256(&old.hash[..]);
+				for mo in old.orders.iter().filter(|mo| mo.confirm_ts > 0) {
+					Commitments::<T>::insert(order_id, &mo.miner, root);
+				}
+			} else {
+				let prune_at = current_block + T::OrderRetention::get();
+				PrunableOrders::<T>::mutate(prune_at, |ids| ids.push(order_id));
+			}
+
+			// still not the latest layout, `migrate_to_cids` translates it.
+			let order = OrderV3 {
+				miner: old.miner,
+				label: old.label,
+				hash: old.hash,
+				size: old.size,
+				user: old.user,
+				orders: old.orders,
+				status: old.status,
+				create_ts: old.create_ts,
+				update_ts: old.update_ts,
+				start,
+				periods,
+				settled,
+			};
+			put_storage_value(b"Ipse", b"Orders", &key, order);
+		}
+	}
+
+	/// Address the data of the orders by the CID, and index the orders by the CID.
+	fn migrate_to_cids() {
+		// the bids hold no funds, the users publish them again.
+		for (key, value) in Self::raw_items(b"Bids") {
+			if Self::decode_exact::<Bid<T::AccountId, BalanceOf<T>>>(&value).is_none() {
+				unhashed::kill(&key);
+			}
+		}
+
+		let orders =
+			Self::legacy_items::<OrderV3<T::AccountId, BalanceOf<T>, T::BlockNumber>>(b"Orders");
+		if orders.is_empty() {
+			// `HashOrders` is already indexed by the CID.
+			return
+		}
+		remove_storage_prefix(b"Ipse", b"HashOrders", &[]);
+
+		for (key, old) in orders {
+			let order_id = match OrderId::decode(&mut Twox64Concat::reverse(&key)) {
+				Ok(order_id) => order_id,
+				Err(_) => continue,
+			};
+			let cid = Cid::from_legacy(&old.hash);
+			HashOrders::mutate(&cid, |ids| ids.push(order_id));
+
+			// still not the latest layout, `migrate_to_tiers` translates it.
+			let order = OrderV4 {
+				miner: old.miner,
+				label: old.label,
+				cid,
+				size: old.size,
+				user: old.user,
+				orders: old.orders,
+				status: old.status,
+				create_ts: old.create_ts,
+				update_ts: old.update_ts,
+				start: old.start,
+				periods: old.periods,
+				settled: old.settled,
+			};
+			put_storage_value(b"Ipse", b"Orders", &key, order);
+		}
+	}
+
+	/// The old orders are cold data retrieved rarely, and the old miners serve them from a
+	/// private network until they declare their capabilities.
+	fn migrate_to_tiers() {
+		let orders =
+			Self::legacy_items::<OrderV4<T::AccountId, BalanceOf<T>, T::BlockNumber>>(b"Orders");
+		for (key, old) in orders {
+			let order = Order {
+				miner: old.miner,
+				label: old.label,
+				cid: old.cid,
+				size: old.size,
+				user: old.user,
+				orders: old.orders,
+				status: old.status,
+				create_ts: old.create_ts,
+				update_ts: old.update_ts,
+				start: old.start,
+				periods: old.periods,
+				settled: old.settled,
+				class: DataClass::Cold,
+				frequency: RetrievalFrequency::Low,
+			};
+			put_storage_value(b"Ipse", b"Orders", &key, order);
+		}
+
+		let miners = Self::legacy_items::<MinerV1<T::AccountId, BalanceOf<T>>>(b"Miners");
+		for (key, old) in miners {
+			let miner = Miner {
+				account_id: old.account_id,
+				nickname: old.nickname,
+				region: old.region,
+				url: old.url,
+				public_key: old.public_key,
+				stash_address: old.stash_address,
+				capacity: old.capacity,
+				unit_price: old.unit_price,
+				violation_times: old.violation_times,
+				total_staking: old.total_staking,
+				create_ts: old.create_ts,
+				update_ts: old.update_ts,
+				hot: false,
+				bandwidth: BandwidthTier::Private,
+			};
+			put_storage_value(b"Ipse", b"Miners", &key, miner);
+		}
+	}
+
+	/// Move `ListOrder`, `MinerHistory` and `History` into the ring buffers, the items over the
+	/// caps are dropped.
+	fn migrate_to_ring_buffers() {
+		let list =
+			take_storage_value::<Vec<OrderId>>(b"Ipse", b"ListOrder", &[]).unwrap_or_default();
+		for order_id in list {
+			ListOrderOf::append(order_id, T::ListOrderLen::get());
+		}
+
+		let histories = StorageKeyIterator::<T::AccountId, Vec<OrderId>, Twox64Concat>::new(
+			b"Ipse",
+			b"MinerHistory",
+		);
+		for (miner, ids) in histories.drain() {
+			for order_id in ids {
+				MinerHistoryOf::<T>::append(&miner, order_id, T::MinerHistoryLen::get());
+			}
+		}
+
+		let histories = StorageKeyIterator::<
+			T::AccountId,
+			MiningHistory<BalanceOf<T>, T::BlockNumber>,
+			Twox64Concat,
+		>::new(b"Ipse", b"History");
+		for (miner, history) in histories.drain() {
+			RewardNums::<T>::insert(&miner, history.total_num);
+			for reward in history.history {
+				HistoryOf::<T>::append(&miner, reward, T::HistoryLen::get());
+			}
+		}
+	}
+
+	/// The challenges are opened on the sectors, the challenges of the orders are dropped.
+	///
+	/// the replicas confirmed before keep their commitment, and are challenged again once
+	/// they are sealed in sectors.
+	fn migrate_to_sectors() {
+		// the old challenges are keyed by the order id first, whose `Twox64Concat` hash is
+		// followed by the 8 bytes of the id.
+		let mut challenges = 0;
+		for (key, _) in Self::raw_items(b"Challenges") {
+			let suffix = &key[32..];
+			if suffix.len() >= 16 && sp_io::hashing::twox_64(&suffix[8..16]) == suffix[..8] {
+				unhashed::kill(&key);
+				challenges += 1;
+			}
+		}
+		// the deadlines of the sectors have the same size, they are only the old ones if the
+		// old challenges are found.
+		if challenges > 0 {
+			remove_storage_prefix(b"Ipse", b"ChallengeDeadlines", &[]);
+		}
+	}
+
+	/// The raw keys and values of the map `item`.
+	fn raw_items(item: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+		let mut prefix = sp_io::hashing::twox_128(b"Ipse").to_vec();
+		prefix.extend_from_slice(&sp_io::hashing::twox_128(item));
+
+		let mut items = Vec::new();
+		let mut previous_key = prefix.clone();
+		while let Some(key) =
+			sp_io::storage::next_key(&previous_key).filter(|k| k.starts_with(&prefix))
+		{
+			if let Some(value) = unhashed::get_raw(&key) {
+				items.push((key.clone(), value));
+			}
+			previous_key = key;
+		}
+		items
+	}
+
+	/// The items of the map `item` in the old layout `V`, with the keys after the prefix.
+	fn legacy_items<V: Decode>(item: &[u8]) -> Vec<(Vec<u8>, V)> {
+		Self::raw_items(item)
+			.into_iter()
+			.filter_map(|(key, value)| {
+				Self::decode_exact::<V>(&value).map(|v| (key[32..].to_vec(), v))
+			})
+			.collect()
+	}
+
+	/// Decode the value only if it is all consumed, a value of the new layout starts with the
+	/// fields of the old one.
+	fn decode_exact<V: Decode>(value: &[u8]) -> Option<V> {
+		let mut input = value;
+		V::decode(&mut input).ok().filter(|_| input.is_empty())
+	}
+
+	/// Slash the staking of the miner for a violation on the `size` bytes of the order.
+	fn slash(miner: &T::AccountId, order_id: OrderId, size: u128) -> NegativeImbalanceOf<T> {
+		let imbalance = Self::fine(miner, size);
+		Self::deposit_event(RawEvent::MinerSlashed(order_id, miner.clone(), imbalance.peek()));
+		imbalance
+	}
+
+	/// Take the fine of a violation on `size` bytes from the staking of the miner.
+	fn fine(miner: &T::AccountId, size: u128) -> NegativeImbalanceOf<T> {
 		Miners::<T>::mutate(miner, |mi| {
-			let mut m = mi.as_mut().unwrap();
+			let m = match mi.as_mut() {
+				Some(m) => m,
+				None => return NegativeImbalanceOf::<T>::zero(),
+			};
 			let fine = if m.violation_times < MAX_VIOLATION_TIMES {
-				m.unit_price * size.saturated_into::<BalanceOf<T>>()
+				m.unit_price.saturating_mul(size.saturated_into::<BalanceOf<T>>())
 			} else {
 				u128::MAX.saturated_into::<BalanceOf<T>>()
 			};
-			T::StakingCurrency::slash_reserved(miner, fine);
-			m.violation_times += 1;
-			if m.total_staking >= fine {
-				m.total_staking -= fine;
-			} else {
-				m.total_staking = 0.saturated_into::<BalanceOf<T>>();
+			// only the staking of the miner can be slashed.
+			let fine = fine.min(m.total_staking);
+			let (imbalance, _) = T::StakingCurrency::slash_reserved(miner, fine);
+			m.violation_times = m.violation_times.saturating_add(1);
+			m.total_staking -= fine;
+			imbalance
+		})
+	}
+
+	/// Whether the order has open disputes.
+	fn is_disputed(order_id: OrderId) -> bool {
+		Disputes::<T>::iter_prefix_values(order_id).next().is_some()
+	}
+
+	/// The chunks the miner proves in the dispute, random chunks if `chunks` is empty.
+	fn disputed_chunks(
+		order_id: OrderId,
+		miner: &T::AccountId,
+		size: u128,
+		mut chunks: Vec<u64>,
+	) -> result::Result<Vec<u64>, DispatchError> {
+		let total = Self::chunks_of(size);
+		if chunks.is_empty() {
+			for i in 0..T::DisputeChunks::get() {
+				let random = T::Randomness::random(&(b"ipse/dispute", order_id, miner, i).encode());
+				let index = u64::decode(&mut random.as_ref()).unwrap_or_default();
+				chunks.push(index % total);
+			}
+		}
+		ensure!(chunks.iter().all(|c| *c < total), Error::<T>::InvalidChunks);
+		chunks.sort();
+		chunks.dedup();
+		Ok(chunks)
+	}
+
+	/// Rule the dispute of the replica, and resume the settlement of the order once it has no
+	/// more disputes.
+	fn resolve_dispute(order_id: OrderId, miner: T::AccountId, verdict: Verdict) {
+		let dispute = match Disputes::<T>::take(order_id, &miner) {
+			Some(dispute) => dispute,
+			None => return,
+		};
+		let mut order = match Self::order(order_id) {
+			Some(order) => order,
+			None => return,
+		};
+
+		match verdict {
+			Verdict::MinerAtFault => {
+				let imbalance = Self::slash(&miner, order_id, order.size);
+				T::StakingCurrency::resolve_creating(&order.user, imbalance);
+				Reputations::<T>::mutate(&miner, |r| {
+					r.orders_deleted = r.orders_deleted.saturating_add(1)
+				});
+				Self::drop_replica(order_id, &mut order, &miner, false);
+			},
+			Verdict::UserAtFault => {
+				if dispute.kind == DisputeKind::DataNotReceived {
+					Self::drop_replica(order_id, &mut order, &miner, true);
+				}
+			},
+			Verdict::NoFault => Self::drop_replica(order_id, &mut order, &miner, false),
+		}
+
+		// the deposit of a rejected dispute goes to the other side.
+		if dispute.kind.rejected_by(verdict) {
+			let other = if dispute.plaintiff == order.user { &miner } else { &order.user };
+			let _ = T::StakingCurrency::repatriate_reserved(
+				&dispute.plaintiff,
+				other,
+				dispute.deposit,
+				BalanceStatus::Free,
+			);
+		} else {
+			T::StakingCurrency::unreserve(&dispute.plaintiff, dispute.deposit);
+		}
+		Orders::<T>::insert(order_id, order);
+
+		Self::deposit_event(RawEvent::DisputeResolved(order_id, miner, verdict));
+
+		if !Self::is_disputed(order_id) {
+			if let Some(due) = FrozenSettlements::<T>::take(order_id) {
+				// the frozen blocks don't count, the order is shifted.
+				let now = <system::Module<T>>::block_number();
+				Orders::<T>::mutate(order_id, |o| if let Some(o) = o {
+					o.start = o.start.saturating_add(now.saturating_sub(due));
+				});
+				Self::settle(order_id, now);
 			}
+		}
+	}
+
+	/// End the replica of the miner before the order ends, the escrow of its unsettled
+	/// periods is refunded to the user, except the current period if `pay_period`.
+	fn drop_replica(
+		order_id: OrderId,
+		order: &mut OrderOf<T>,
+		miner: &T::AccountId,
+		pay_period: bool,
+	) {
+		let index = match order.orders.iter().position(|mo| &mo.miner == miner) {
+			Some(index) => index,
+			None => return,
+		};
+		let mo = order.orders.remove(index);
+		let unsettled = order.periods.saturating_sub(order.settled);
+		let escrow = mo.period_price.saturating_mul(unsettled.saturated_into::<BalanceOf<T>>());
+		let paid = if pay_period && unsettled > 0 { mo.period_price } else { Zero::zero() };
+		let refund = escrow.saturating_sub(paid);
+		Self::pay_miner(&order.user, miner, paid);
+		T::StakingCurrency::unreserve(&order.user, refund);
+		Self::deposit_event(RawEvent::ReplicaSettled(
+			order_id,
+			miner.clone(),
+			unsettled,
+			paid,
+			refund,
+		));
+		if !refund.is_zero() {
+			Self::deposit_event(RawEvent::OrderRefunded(order_id, order.user.clone(), refund));
+		}
+
+		Commitments::<T>::remove(order_id, miner);
+		Self::release_sectors(order_id, miner);
+		Miners::<T>::mutate(miner, |m| if let Some(m) = m {
+			m.capacity = m.capacity.saturating_add(order.size);
 		});
+		MinerOrders::<T>::mutate_exists(miner, |ids| Self::remove_order_id(ids, order_id));
+
+		order.update_ts = Self::get_now_ts();
+		if order.orders.is_empty() {
+			order.settled = order.periods;
+			order.status = OrderStatus::Deleted;
+			Self::release_order(order_id, order);
+			Self::deposit_event(RawEvent::OrderDeleted(order_id, order.user.clone()));
+		} else if order
+			.orders
+			.iter()
+			.all(|mo| Commitments::<T>::contains_key(order_id, &mo.miner))
+		{
+			order.status = OrderStatus::Confirmed;
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+		match call {
+			Call::submit_match(bid_id, miners) => {
+				let bid = match Self::bid(bid_id) {
+					Some(bid) => bid,
+					None => return InvalidTransaction::Stale.into(),
+				};
+				if Self::validate_match(&bid, miners).is_err() {
+					return InvalidTransaction::Call.into()
+				}
+				Ok(ValidTransaction {
+					priority: T::UnsignedPriority::get(),
+					requires: vec![],
+					provides: vec![(b"ipse/match", bid_id).encode()],
+					longevity: 5,
+					propagate: true,
+				})
+			},
+			_ => InvalidTransaction::Call.into(),
+		}
 	}
 }
 
@@ -640,17 +3457,73 @@ decl_event! {
 		where
 		AccountId = <T as system::Trait>::AccountId,
 		Balance = <<T as Trait>::StakingCurrency as Currency<<T as frame_system::Trait>::AccountId>>::Balance,
+		BlockNumber = <T as system::Trait>::BlockNumber,
 		{
 			Registered(AccountId),
 			UpdatedMiner(AccountId),
-			VerifyStorage(AccountId, bool),
-			VerifyBlock(u64),
-			CreatedOrder(AccountId),
-			ListOrder(AccountId),
-			ConfirmedOrder(AccountId, u64),
-			DeletedOrder(AccountId, u64),
+			AddedCapacity(AccountId, u128),
+			ReducedCapacity(AccountId, u128),
+			Unregistered(AccountId),
+			Withdrawn(AccountId, Balance),
+			/// (order id, user, size, periods, funds reserved for all the replicas).
+			OrderCreated(OrderId, AccountId, u128, u32, Balance),
+			/// the miner stores a replica of the order, (order id, miner, period price).
+			ReplicaAssigned(OrderId, AccountId, Balance),
+			/// all the data of the replica is sealed, (order id, miner, CommP of the data).
+			OrderConfirmed(OrderId, AccountId, [u8; 32]),
+			/// (miner, sector id, comm_r).
+			SectorPreCommitted(AccountId, SectorId, [u8; 32]),
+			/// the seal of the sector is proven, (miner, sector id).
+			SectorProveCommitted(AccountId, SectorId),
+			/// the seal is not proven in time, (miner, sector id).
+			SectorAborted(AccountId, SectorId),
+			/// all the orders in the sector are over, (miner, sector id).
+			SectorTerminated(AccountId, SectorId),
+			/// (miner, sector id, node index, deadline).
+			ChallengeOpened(AccountId, SectorId, u64, BlockNumber),
+			/// the miner answers the challenge or misses it, (miner, sector id, result).
+			StorageProven(AccountId, SectorId, bool),
+			/// the staking of the miner is slashed for the sector, (miner, sector id, amount).
+			SectorSlashed(AccountId, SectorId, Balance),
+			/// the periods of the replica are settled,
+			/// (order id, miner, periods, paid to the miner, refunded to the user).
+			ReplicaSettled(OrderId, AccountId, u32, Balance, Balance),
+			/// the funds settled back to the user, (order id, user, amount).
+			OrderRefunded(OrderId, AccountId, Balance),
+			/// the staking of the miner is slashed, (order id, miner, amount).
+			MinerSlashed(OrderId, AccountId, Balance),
+			/// the order is expired and its funds are released, (order id, user).
+			OrderExpired(OrderId, AccountId),
+			/// the order is deleted before it expires, (order id, user).
+			OrderDeleted(OrderId, AccountId),
+			/// the order is extended, (order id, user, extra periods, funds reserved).
+			OrderRenewed(OrderId, AccountId, u32, Balance),
+			/// the auto renewal of the order is updated, (order id, user, periods).
+			AutoRenewUpdated(OrderId, AccountId, u32),
+			/// the miner quotes in the reference unit, or goes back to `unit_price`.
+			UpdatedReferencePrice(AccountId, Option<u128>),
+			/// (user, deal id).
+			OpenedRetrieval(AccountId, u64),
+			/// (miner, deal id, amount).
+			ClaimedRetrieval(AccountId, u64, Balance),
+			/// (user, deal id).
+			DisputedRetrieval(AccountId, u64),
+			/// the deal is closed, (user, deal id, refund).
+			ClosedRetrieval(AccountId, u64, Balance),
 			RequestUpToList(AccountId, Balance),
 			RequestDownFromList(AccountId),
+			PublishedAsk(AccountId),
+			CanceledAsk(AccountId),
+			PublishedBid(AccountId, u64),
+			CanceledBid(AccountId, u64),
+			/// the bid is matched, (user, bid id, order id).
+			MatchedBid(AccountId, u64, u64),
+			/// the replica is disputed, (order id, miner, plaintiff, kind).
+			DisputeOpened(OrderId, AccountId, AccountId, DisputeKind),
+			/// the miner proves a disputed chunk, (order id, miner, chunk index).
+			DisputeChunkProven(OrderId, AccountId, u64),
+			/// (order id, miner, verdict).
+			DisputeResolved(OrderId, AccountId, Verdict),
 		}
 }
 
@@ -681,9 +3554,998 @@ decl_error! {
 		PermissionDenyed,
 		AmountNotEnough,
 		NotInList,
+		/// The replicas number is zero or too large.
+		InvalidReplicas,
+		/// The miner appears more than once.
+		DuplicateMiner,
+		/// Not enough miners to store the replicas.
+		NotEnoughMiners,
+		/// The miner already confirmed the order.
+		AlreadyConfirmed,
+		/// The miner already registered.
+		AlreadyRegistered,
+		/// No staking can be unreserved.
+		NoneUnbonded,
+		/// The miner still has orders in storing.
+		HasActiveOrders,
+		/// The miner still has sectors, pre-committed or active.
+		HasSectors,
+		/// The miner still has challenges to answer.
+		HasOpenChallenges,
+		/// The miner still has disputes to resolve.
+		HasOpenDisputes,
+		/// The miner still has retrieval deals to close.
+		HasOpenDeals,
+		/// over flow.
+		Overflow,
+		/// The price feed has no fresh price to convert the reference price.
+		PriceUnavailable,
+		/// The miner is not able to store the data of the class or serve its retrievals.
+		MissingCapability,
+		/// The ask not found.
+		AskNotFound,
+		/// The bid not found.
+		BidNotFound,
+		/// The ask of the miner does not match the bid.
+		MismatchedAsk,
+		/// The challenge not found.
+		ChallengeNotFound,
+		/// The answer is not for the current challenge.
+		ChallengeMismatch,
+		/// The challenge is already expired.
+		ChallengeExpired,
+		/// The miner did not register the CommP of the data.
+		CommitmentNotFound,
+		/// The CID is malformed.
+		InvalidCid,
+		/// The retrieval deal not found.
+		DealNotFound,
+		/// The retrieval deal is over.
+		DealExpired,
+		/// The user can not close the retrieval deal before the deadline.
+		DealNotExpired,
+		/// The retrieval deal is already disputed.
+		DealDisputed,
+		/// The voucher is not signed by the user or not for more bytes.
+		InvalidVoucher,
+		/// The dispute not found.
+		DisputeNotFound,
+		/// The replica is already disputed.
+		AlreadyDisputed,
+		/// The order has open disputes.
+		OrderDisputed,
+		/// The window of the dispute is over.
+		DisputeExpired,
+		/// The dispute is not ruled by the proofs of the miner.
+		NotProvable,
+		/// The chunks are too many, out of the data or not disputed.
+		InvalidChunks,
+		/// The merkle proof of the chunk is invalid.
+		InvalidProof,
+		/// The miner already has the sector.
+		SectorExists,
+		/// The sector not found.
+		SectorNotFound,
+		/// The seal of the sector is already proven.
+		SectorActive,
+		/// The pieces are empty, duplicated, or not of the orders of the miner.
+		InvalidPieces,
+		/// The piece is not a part of the data that the user commits to.
+		MismatchedPiece,
+		/// The order has no commitment of its data, it is migrated from the old storage.
+		DataCommitmentNotFound,
+		/// The pieces are larger than a sector.
+		SectorOverflow,
+		/// The seed of the seal proof is not drawn yet.
+		SeedNotDrawn,
+		/// The seal proof is invalid.
+		InvalidSealProof,
 		/// Miners provide insufficient storage capacity
 		InsufficientCapacity,
 		NoneCapacity,
-		NoneDays,
+		NonePeriods,
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// The api to query the ipse storage market.
+	pub trait IpseApi<AccountId, Balance, BlockNumber> where
+		AccountId: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// A page of the orders of the user, at most `MAX_PAGE_LEN` orders.
+		fn user_orders(
+			user: AccountId,
+			start: u32,
+			count: u32,
+		) -> Vec<(OrderId, Order<AccountId, Balance, BlockNumber>)>;
+
+		/// A page of the orders stored by the miner, at most `MAX_PAGE_LEN` orders.
+		fn miner_orders(
+			miner: AccountId,
+			start: u32,
+			count: u32,
+		) -> Vec<(OrderId, Order<AccountId, Balance, BlockNumber>)>;
+
+		/// The order.
+		fn order(order_id: OrderId) -> Option<Order<AccountId, Balance, BlockNumber>>;
+
+		/// The miner with its free capacity.
+		fn miner(miner: AccountId) -> Option<MinerDetails<AccountId, Balance>>;
+
+		/// What the miner charges for storing `size` bytes of the tiers for `days` days.
+		fn quote(
+			miner: AccountId,
+			size: u128,
+			days: u32,
+			class: DataClass,
+			frequency: RetrievalFrequency,
+		) -> Option<Quote<Balance>>;
+
+		/// The recommend list ranked by the reserved amount blended with the reputation.
+		fn recommend_list() -> Vec<(AccountId, Balance)>;
+
+		/// The reputation facts of the miner and its score.
+		fn miner_reputation(miner: AccountId) -> (Reputation, Permill);
+	}
+}
+
+/// The part of `amount` for `part` blocks of a period of `whole` blocks, rounded down.
+pub fn pro_rata(amount: u128, part: u128, whole: u128) -> u128 {
+	if whole == 0 || part >= whole {
+		return amount
+	}
+	// `part < whole`, the result is less than `amount` and never overflows.
+	helpers_128bit::multiply_by_rational(amount, part, whole).unwrap_or(amount)
+}
+
+/// The price of `size` bytes at `price` per GB, rounded down, `None` if it overflows.
+pub fn retrieval_price(price: u128, size: u128) -> Option<u128> {
+	helpers_128bit::multiply_by_rational(price, size, GB as u128).ok()
+}
+
+/// Split the unsettled funds of a replica when its order stops, (paid to the miner, refunded).
+///
+/// The miner who proved the storage is paid for the `elapsed` blocks of the current period,
+/// the rest of the current period and the later periods are refunded.
+pub fn split_unsettled(
+	period_price: u128,
+	unsettled: u32,
+	elapsed: u128,
+	period: u128,
+	proven: bool,
+) -> (u128, u128) {
+	if unsettled == 0 {
+		return (0, 0)
+	}
+	let total = period_price.saturating_mul(unsettled.into());
+	let paid = if proven { pro_rata(period_price, elapsed, period) } else { 0 };
+	(paid, total - paid)
+}
+
+/// Raise the base price by the tiers of the data.
+pub fn tiered_price(base: u128, class: DataClass, frequency: RetrievalFrequency) -> u128 {
+	let percent = u128::from(class.price_percent()) * u128::from(frequency.price_percent());
+	helpers_128bit::multiply_by_rational(base, percent, 10_000).unwrap_or(u128::max_value())
+}
+
+/// The parent of two nodes of the trees of the data, as `ipse_io::seal` hashes them.
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+	sp_io::hashing::sha2_256(&[&left[..], &right[..]].concat())
+}
+
+/// How many bytes of the data of `size` bytes are in its part `part`.
+pub fn part_size(size: u128, part: u32) -> u128 {
+	let start = u128::from(part).saturating_mul(SECTOR_SIZE as u128);
+	size.saturating_sub(start).min(SECTOR_SIZE as u128)
+}
+
+/// How many nodes a part of the data of `size` bytes takes in a sector, a power of two.
+pub fn piece_nodes(size: u128) -> u64 {
+	if size >= SECTOR_SIZE as u128 {
+		return SECTOR_SIZE / NODE_SIZE
+	}
+	let nodes = (size as u64 + NODE_SIZE - 1) / NODE_SIZE;
+	nodes.max(1).next_power_of_two()
+}
+
+/// Whether `comm_p` is the CommP of the part `part` of the data of `size` bytes whose CommP is
+/// `root`, `proof` is the merkle path of the part in the tree of the data.
+///
+/// the parts are the subtrees of `SECTOR_SIZE` bytes, the path of a single part is empty.
+pub fn verify_part(
+	size: u128,
+	part: u32,
+	comm_p: [u8; 32],
+	proof: &[[u8; 32]],
+	root: [u8; 32],
+) -> bool {
+	let sector_size = SECTOR_SIZE as u128;
+	let parts = size / sector_size + if size % sector_size == 0 { 0 } else { 1 };
+	let depth = match parts.max(1).checked_next_power_of_two() {
+		Some(leaves) => leaves.trailing_zeros() as usize,
+		None => return false,
+	};
+	if u128::from(part) >= parts || proof.len() != depth {
+		return false
+	}
+
+	let mut node = comm_p;
+	let mut index = part;
+	for sibling in proof {
+		node = if index % 2 == 0 { node_hash(&node, sibling) } else { node_hash(sibling, &node) };
+		index /= 2;
+	}
+	node == root
+}
+
+/// The root of the tree of the nodes of `data`, which is padded with zero nodes to `nodes`
+/// nodes, a power of two.
+fn nodes_root(data: &[u8], nodes: usize) -> [u8; 32] {
+	let mut level = vec![[0u8; 32]; nodes];
+	for (node, chunk) in level.iter_mut().zip(data.chunks(NODE_SIZE as usize)) {
+		node[..chunk.len()].copy_from_slice(chunk);
+	}
+	while level.len() > 1 {
+		level = level.chunks(2).map(|pair| node_hash(&pair[0], &pair[1])).collect();
+	}
+	level[0]
+}
+
+/// Whether `chunk` is the chunk `index` of the data of `size` bytes whose CommP is `comm_p`.
+///
+/// the chunks are the subtrees of `CHUNK_SIZE` bytes of the trees of the parts, or the whole
+/// tree of a smaller part. `proof` is the merkle path of the chunk in the tree of its part,
+/// followed by the path of the part in the tree of the data, see `verify_part`.
+pub fn verify_chunk(
+	size: u128,
+	index: u64,
+	chunk: &[u8],
+	proof: &[[u8; 32]],
+	comm_p: [u8; 32],
+) -> bool {
+	let start = u128::from(index).saturating_mul(CHUNK_SIZE);
+	if start >= size || chunk.len() as u128 != (size - start).min(CHUNK_SIZE) {
+		return false
+	}
+	let sector_size = SECTOR_SIZE as u128;
+	let part = match u32::try_from(start / sector_size) {
+		Ok(part) => part,
+		Err(_) => return false,
+	};
+	let part_nodes = piece_nodes(part_size(size, part));
+	let chunk_nodes = part_nodes.min(CHUNK_SIZE as u64 / NODE_SIZE);
+	let depth = (part_nodes / chunk_nodes).trailing_zeros() as usize;
+	if proof.len() < depth {
+		return false
+	}
+
+	let mut node = nodes_root(chunk, chunk_nodes as usize);
+	let mut index = (start % sector_size / CHUNK_SIZE) as u64;
+	for sibling in &proof[..depth] {
+		node = if index % 2 == 0 { node_hash(&node, sibling) } else { node_hash(sibling, &node) };
+		index /= 2;
+	}
+	verify_part(size, part, node, &proof[depth..], comm_p)
+}
+
+/// The CommD of a sector of `sector_nodes` nodes, in which the pieces `(nodes, comm_p)` are
+/// packed from the first node in that order, the rest of the sector is zero nodes.
+///
+/// the nodes of the pieces are powers of two which don't increase, so the pieces are aligned
+/// to their sizes, and their CommPs are the roots of subtrees of `tree_d`.
+pub fn comm_d_of(pieces: &[(u64, [u8; 32])], sector_nodes: u64) -> [u8; 32] {
+	// the roots of the subtrees of zero nodes, `zeros[i]` has `2^i` nodes.
+	let mut zeros = vec![[0u8; 32]];
+	for i in 0..sector_nodes.trailing_zeros() as usize {
+		zeros.push(node_hash(&zeros[i], &zeros[i]));
+	}
+
+	// the complete subtrees so far, `(nodes, root)`, the earlier ones are larger.
+	let mut subtrees = Vec::new();
+	let mut offset = 0u64;
+	for (nodes, comm_p) in pieces {
+		push_subtree(&mut subtrees, *nodes, *comm_p);
+		offset += nodes;
+	}
+	// the zero nodes are filled by the largest aligned subtrees.
+	while offset < sector_nodes {
+		let nodes = if offset == 0 { sector_nodes } else { offset & offset.wrapping_neg() };
+		push_subtree(&mut subtrees, nodes, zeros[nodes.trailing_zeros() as usize]);
+		offset += nodes;
+	}
+	subtrees.first().map_or(zeros[zeros.len() - 1], |(_, root)| *root)
+}
+
+/// Append the subtree, and merge the last two subtrees while they are siblings.
+fn push_subtree(subtrees: &mut Vec<(u64, [u8; 32])>, nodes: u64, root: [u8; 32]) {
+	subtrees.push((nodes, root));
+	while subtrees.len() > 1 && subtrees[subtrees.len() - 2].0 == subtrees[subtrees.len() - 1].0 {
+		let (nodes, right) = subtrees.pop().expect("there are two subtrees; qed");
+		let (_, left) = subtrees.pop().expect("there are two subtrees; qed");
+		subtrees.push((nodes * 2, node_hash(&left, &right)));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::mock::{new_test_ext, run_to_block, Balances, Ipse, Origin, Test};
+	use super::*;
+	use frame_support::{assert_noop, assert_ok, traits::OnRuntimeUpgrade};
+	use ipse_io::seal;
+	use sp_io::hashing::blake2_256;
+
+	const PERIOD: u128 = 14_400;
+
+	const CID: &[u8] = b"QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+
+	/// the CommP of the data of the orders.
+	const COMM_P: [u8; 32] = [7; 32];
+
+	/// register the miner with `capacity` bytes at 1 per byte.
+	fn register(miner: u64, capacity: u128) {
+		assert_ok!(Ipse::register_miner(
+			Origin::signed(miner),
+			b"miner".to_vec(),
+			b"region".to_vec(),
+			miner.encode(),
+			vec![],
+			miner,
+			capacity,
+			1,
+		));
+	}
+
+	/// the user orders a replica of `size` bytes of cold data from each miner for a period.
+	fn create_replicas(user: u64, miners: Vec<u64>, size: u128) -> DispatchResult {
+		let replicas = miners.len() as u32;
+		Ipse::create_order(
+			Origin::signed(user),
+			miners,
+			replicas,
+			b"label".to_vec(),
+			CID.to_vec(),
+			size,
+			COMM_P,
+			None,
+			1,
+			DataClass::Cold,
+			RetrievalFrequency::Low,
+			1,
+		)
+	}
+
+	/// the user orders `size` bytes of cold data from the miner for a period.
+	fn create_order(user: u64, miner: u64, size: u128) -> DispatchResult {
+		create_order_of(user, miner, size, 1)
+	}
+
+	/// the user orders `size` bytes of cold data from the miner for `periods` periods.
+	fn create_order_of(user: u64, miner: u64, size: u128, periods: u32) -> DispatchResult {
+		Ipse::create_order(
+			Origin::signed(user),
+			vec![miner],
+			1,
+			b"label".to_vec(),
+			CID.to_vec(),
+			size,
+			COMM_P,
+			None,
+			periods,
+			DataClass::Cold,
+			RetrievalFrequency::Low,
+			1,
+		)
+	}
+
+	/// the replica of the miner is confirmed and proven for the current period.
+	fn prove(order_id: OrderId, miner: u64) {
+		Commitments::<Test>::insert(order_id, miner, COMM_P);
+		Orders::<Test>::mutate(order_id, |o| {
+			let order = o.as_mut().unwrap();
+			order.status = OrderStatus::Confirmed;
+			order.orders.iter_mut().for_each(|mo| mo.verify_result = mo.miner == miner);
+		});
+	}
+
+	/// the miner pre-commits the sector `0` with the pieces of single part orders.
+	fn precommit(miner: u64, pieces: Vec<Piece>) -> DispatchResult {
+		let proofs = vec![vec![]; pieces.len()];
+		Ipse::precommit_sector(Origin::signed(miner), 0, [0; 32], pieces, proofs)
+	}
+
+	#[test]
+	fn pro_rata_works() {
+		assert_eq!(pro_rata(1_000, 0, PERIOD), 0);
+		assert_eq!(pro_rata(1_000, PERIOD / 2, PERIOD), 500);
+		assert_eq!(pro_rata(1_000, PERIOD / 3, PERIOD), 333);
+		assert_eq!(pro_rata(1_000, PERIOD, PERIOD), 1_000);
+		// no more than the whole period is paid.
+		assert_eq!(pro_rata(1_000, PERIOD * 2, PERIOD), 1_000);
+		assert_eq!(pro_rata(1_000, 1, 0), 1_000);
+		// no overflow for large amounts.
+		assert_eq!(pro_rata(u128::MAX, PERIOD / 2, PERIOD), u128::MAX / 2);
+	}
+
+	#[test]
+	fn deletion_refunds_unsettled_periods() {
+		// deleted in the block it is created.
+		assert_eq!(split_unsettled(1_000, 30, 0, PERIOD, false), (0, 30_000));
+		assert_eq!(split_unsettled(1_000, 30, 0, PERIOD, true), (0, 30_000));
+		// deleted in the middle of a period.
+		assert_eq!(split_unsettled(1_000, 30, PERIOD / 4, PERIOD, true), (250, 29_750));
+		assert_eq!(split_unsettled(1_000, 1, PERIOD / 4, PERIOD, true), (250, 750));
+		// the miner who did not prove the storage is not paid.
+		assert_eq!(split_unsettled(1_000, 30, PERIOD / 4, PERIOD, false), (0, 30_000));
+		// deleted at the end of a period, before it is settled.
+		assert_eq!(split_unsettled(1_000, 2, PERIOD, PERIOD, true), (1_000, 1_000));
+		// nothing left to split.
+		assert_eq!(split_unsettled(1_000, 0, PERIOD / 2, PERIOD, true), (0, 0));
+	}
+
+	#[test]
+	fn deletion_splits_exactly() {
+		for elapsed in [0, 1, 7, PERIOD / 3, PERIOD - 1, PERIOD].iter() {
+			for unsettled in 1..5u32 {
+				let (paid, refund) = split_unsettled(997, unsettled, *elapsed, PERIOD, true);
+				assert_eq!(paid + refund, 997 * unsettled as u128);
+			}
+		}
+		let (paid, refund) = split_unsettled(u128::MAX / 2, 3, PERIOD / 2, PERIOD, true);
+		assert_eq!(paid, u128::MAX / 4);
+		assert_eq!(refund, u128::MAX - u128::MAX / 4);
+	}
+
+	#[test]
+	fn retrieval_price_works() {
+		assert_eq!(retrieval_price(1_000, 0), Some(0));
+		assert_eq!(retrieval_price(1_000, GB as u128), Some(1_000));
+		assert_eq!(retrieval_price(1_000, GB as u128 / 2), Some(500));
+		assert_eq!(retrieval_price(1_000, 3 * GB as u128 + GB as u128 / 4), Some(3_250));
+		assert_eq!(retrieval_price(1_000, MB as u128), Some(0));
+		assert_eq!(retrieval_price(u128::MAX, GB as u128), Some(u128::MAX));
+		assert_eq!(retrieval_price(u128::MAX, 2 * GB as u128), None);
+
+		// the claims of the cumulative vouchers add up to the price of the last one.
+		let vouchers = [MB as u128, 300 * MB as u128, GB as u128, 5 * GB as u128 / 2];
+		let mut claimed = 0;
+		let mut paid = 0;
+		for bytes in vouchers.iter() {
+			paid += retrieval_price(1_000, *bytes).unwrap() - retrieval_price(1_000, claimed).unwrap();
+			claimed = *bytes;
+		}
+		assert_eq!(Some(paid), retrieval_price(1_000, claimed));
+	}
+
+	#[test]
+	fn expiry_leaves_nothing_reserved() {
+		let period_price = 1_000u128;
+		let proofs = [true, false, true, true, false];
+		let total_price = period_price * proofs.len() as u128;
+
+		// every period is settled as its last unsettled period, after the whole period.
+		let (mut paid, mut refunded) = (0, 0);
+		for proven in proofs.iter() {
+			let (p, r) = split_unsettled(period_price, 1, PERIOD, PERIOD, *proven);
+			paid += p;
+			refunded += r;
+		}
+		assert_eq!(paid, 3_000);
+		assert_eq!(refunded, 2_000);
+		assert_eq!(paid + refunded, total_price);
+		// and nothing is left when the order expires.
+		assert_eq!(split_unsettled(period_price, 0, 0, PERIOD, true), (0, 0));
+	}
+
+	#[test]
+	fn reputation_score_works() {
+		// a new miner without any fact.
+		assert_eq!(Reputation::default().score(0), Permill::from_percent(55));
+
+		let perfect = Reputation {
+			proofs_passed: 10,
+			proofs_failed: 0,
+			periods_proven: 30,
+			periods_missed: 0,
+			orders_completed: 2,
+			orders_deleted: 0,
+		};
+		assert_eq!(perfect.score(0), Permill::one());
+		// the violations only take the last 10%.
+		assert_eq!(perfect.score(MAX_VIOLATION_TIMES), Permill::from_percent(90));
+		assert_eq!(perfect.score(MAX_VIOLATION_TIMES + 5), Permill::from_percent(90));
+
+		let failing = Reputation {
+			proofs_passed: 0,
+			proofs_failed: 4,
+			periods_proven: 0,
+			periods_missed: 4,
+			orders_completed: 0,
+			orders_deleted: 1,
+		};
+		assert_eq!(failing.score(MAX_VIOLATION_TIMES), Permill::zero());
+
+		// half of the proofs and periods, all the orders completed.
+		let mixed = Reputation {
+			proofs_passed: 5,
+			proofs_failed: 5,
+			periods_proven: 3,
+			periods_missed: 3,
+			orders_completed: 1,
+			orders_deleted: 0,
+		};
+		assert_eq!(mixed.score(0), Permill::from_percent(65));
+	}
+
+	#[test]
+	fn tiered_price_works() {
+		use DataClass::*;
+		use RetrievalFrequency::*;
+
+		assert_eq!(tiered_price(1_000, Cold, Low), 1_000);
+		assert_eq!(tiered_price(1_000, Cold, Normal), 1_200);
+		assert_eq!(tiered_price(1_000, Hot, Low), 1_500);
+		assert_eq!(tiered_price(1_000, Hot, Frequent), 2_250);
+		assert_eq!(tiered_price(0, Hot, Frequent), 0);
+		// no overflow for large amounts.
+		assert_eq!(tiered_price(u128::MAX, Cold, Low), u128::MAX);
+		assert_eq!(tiered_price(u128::MAX, Hot, Normal), u128::MAX);
+	}
+
+	#[test]
+	fn dispute_verdicts_work() {
+		use DisputeKind::*;
+		use Verdict::*;
+
+		// the miner who did not prove the chunks lost the data.
+		assert_eq!(DataLost.default_verdict(), MinerAtFault);
+		// the arbitration did not rule it.
+		assert_eq!(DataNotReceived.default_verdict(), NoFault);
+
+		assert!(DataLost.rejected_by(UserAtFault));
+		assert!(!DataLost.rejected_by(MinerAtFault));
+		assert!(!DataLost.rejected_by(NoFault));
+		assert!(DataNotReceived.rejected_by(MinerAtFault));
+		assert!(!DataNotReceived.rejected_by(UserAtFault));
+		assert!(!DataNotReceived.rejected_by(NoFault));
+	}
+
+	#[test]
+	fn orders_fill_the_whole_capacity() {
+		new_test_ext().execute_with(|| {
+			register(1, 100);
+
+			assert_noop!(create_order(2, 1, 101), Error::<Test>::InsufficientCapacity);
+			// the order of exactly the free capacity fits.
+			assert_ok!(create_order(2, 1, 100));
+			assert_eq!(Ipse::miner(1u64).unwrap().capacity, 0);
+			assert_noop!(create_order(3, 1, 1), Error::<Test>::InsufficientCapacity);
+		});
+	}
+
+	#[test]
+	fn staking_overflow_is_rejected() {
+		new_test_ext().execute_with(|| {
+			let register = |capacity, unit_price| {
+				Ipse::register_miner(
+					Origin::signed(1),
+					b"miner".to_vec(),
+					b"region".to_vec(),
+					b"url".to_vec(),
+					vec![],
+					1,
+					capacity,
+					unit_price,
+				)
+			};
+			assert_noop!(register(u128::MAX, 2), Error::<Test>::Overflow);
+
+			assert_ok!(register(100, 2));
+			assert_noop!(
+				Ipse::update_miner(
+					Origin::signed(1),
+					b"miner".to_vec(),
+					b"region".to_vec(),
+					b"url".to_vec(),
+					vec![],
+					1,
+					u128::MAX,
+				),
+				Error::<Test>::Overflow
+			);
+			assert_noop!(
+				Ipse::add_capacity(Origin::signed(1), u128::MAX / 2 + 1),
+				Error::<Test>::Overflow
+			);
+		});
+	}
+
+	#[test]
+	fn updating_the_miner_keeps_the_staking_of_the_used_capacity() {
+		new_test_ext().execute_with(|| {
+			register(1, 1_000);
+			assert_ok!(create_order(2, 1, 100));
+			assert_eq!(Balances::reserved_balance(1), 1_000);
+			let update = |unit_price| {
+				Ipse::update_miner(
+					Origin::signed(1),
+					b"miner".to_vec(),
+					b"region".to_vec(),
+					1u64.encode(),
+					vec![],
+					1,
+					unit_price,
+				)
+			};
+
+			assert_ok!(update(1));
+			assert_eq!(Balances::reserved_balance(1), 1_000);
+			assert_ok!(update(2));
+			assert_eq!(Balances::reserved_balance(1), 2_000);
+			assert_eq!(Ipse::miner(1u64).unwrap().total_staking, 2_000);
+			assert_ok!(update(1));
+			assert_eq!(Balances::reserved_balance(1), 1_000);
+		});
+	}
+
+	#[test]
+	fn miners_with_obligations_cannot_unregister() {
+		new_test_ext().execute_with(|| {
+			register(1, 1_000);
+			let sector = Sector {
+				comm_r: [0; 32],
+				comm_d: [0; 32],
+				pieces: vec![],
+				status: SectorStatus::Active,
+				seal_at: 0,
+				seed: None,
+				faulty: false,
+			};
+			Sectors::<Test>::insert(1u64, 0 as SectorId, sector);
+			assert_noop!(Ipse::unregister_miner(Origin::signed(1)), Error::<Test>::HasSectors);
+			Sectors::<Test>::remove(1u64, 0 as SectorId);
+
+			MinerDeals::<Test>::insert(1u64, 1);
+			assert_noop!(Ipse::unregister_miner(Origin::signed(1)), Error::<Test>::HasOpenDeals);
+			MinerDeals::<Test>::remove(1u64);
+
+			assert_ok!(Ipse::unregister_miner(Origin::signed(1)));
+		});
+	}
+
+	#[test]
+	fn migrated_confirmed_orders_are_settled() {
+		new_test_ext().execute_with(|| {
+			register(1, 100);
+			let mut hash = [0u8; 46];
+			hash.copy_from_slice(CID);
+			let legacy = OrderV2 {
+				miner: 1u64,
+				label: b"label".to_vec(),
+				hash,
+				size: 100,
+				user: 2,
+				orders: vec![MinerOrder {
+					miner: 1,
+					period_price: 100u128,
+					total_price: 500,
+					verify_result: true,
+					verify_ts: 0,
+					confirm_ts: 1,
+					url: None,
+				}],
+				status: OrderStatus::Confirmed,
+				create_ts: 0,
+				update_ts: 0,
+				// 5 settlement periods of 10 blocks, 2 milliseconds each.
+				duration: 100,
+			};
+			assert_ok!(Balances::reserve(&2, 500));
+			put_storage_value(b"Ipse", b"Orders", &[], vec![legacy]);
+			StorageVersion::put(Releases::V1_0_0);
+
+			Ipse::on_runtime_upgrade();
+			assert_eq!(Ipse::storage_version(), Releases::V7_0_0);
+			assert_eq!(Ipse::next_order_id(), 1);
+			assert_eq!(Ipse::order(0u64).unwrap().periods, 5);
+			assert_eq!(Ipse::commitment(0u64, 1u64), Some(blake2_256(CID)));
+			assert_eq!(Ipse::due_orders(11u64), vec![0]);
+
+			// the replica can't be sealed, it can't be renewed either.
+			assert_noop!(
+				Ipse::renew_order(Origin::signed(2), 0, 1),
+				Error::<Test>::DataCommitmentNotFound
+			);
+
+			// the proven replica is paid for the first period, the order ends and the rest is
+			// refunded.
+			let staked = Balances::free_balance(1);
+			let capacity = Ipse::miner(1u64).unwrap().capacity;
+			run_to_block(12);
+			assert_eq!(Balances::free_balance(1), staked + 100);
+			assert_eq!(Balances::reserved_balance(2), 0);
+			let order = Ipse::order(0u64).unwrap();
+			assert_eq!((order.status, order.settled), (OrderStatus::Expired, 5));
+			assert_eq!(Ipse::miner(1u64).unwrap().capacity, capacity + 100);
+
+			// the miner is neither paid nor slashed in the later periods, the order is pruned.
+			let reserved = Balances::reserved_balance(1);
+			run_to_block(32);
+			assert_eq!(Balances::free_balance(1), staked + 100);
+			assert_eq!(Balances::reserved_balance(1), reserved);
+			assert!(Ipse::order(0u64).is_none());
+			assert!(Ipse::commitment(0u64, 1u64).is_none());
+		});
+	}
+
+	#[test]
+	fn renewal_overflow_is_rejected() {
+		new_test_ext().execute_with(|| {
+			register(1, 100);
+			assert_ok!(create_order(2, 1, 100));
+			Orders::<Test>::mutate(0u64, |o| {
+				o.as_mut().unwrap().orders[0].period_price = u128::MAX / 2;
+			});
+
+			assert_noop!(
+				Ipse::renew_order(Origin::signed(2), 0, 3),
+				Error::<Test>::Overflow
+			);
+
+			// the auto renewal fails, and the order expires.
+			assert_ok!(Ipse::set_auto_renew(Origin::signed(2), 0, 3));
+			run_to_block(12);
+			let order = Ipse::order(0u64).unwrap();
+			assert_eq!(order.status, OrderStatus::Expired);
+			assert_eq!(order.periods, 1);
+		});
+	}
+
+	#[test]
+	fn match_overflow_is_rejected() {
+		new_test_ext().execute_with(|| {
+			register(1, 100);
+			assert_ok!(Ipse::publish_ask(
+				Origin::signed(1),
+				1,
+				100,
+				vec![],
+				false,
+				BandwidthTier::Private
+			));
+			assert_ok!(Ipse::publish_bid(
+				Origin::signed(2),
+				b"label".to_vec(),
+				CID.to_vec(),
+				100,
+				COMM_P,
+				None,
+				1,
+				1,
+				vec![],
+				DataClass::Cold,
+				RetrievalFrequency::Low,
+				u128::MAX,
+			));
+
+			assert_noop!(
+				Ipse::submit_match(Origin::none(), 0, vec![1]),
+				Error::<Test>::Overflow
+			);
+		});
+	}
+
+	#[test]
+	fn sector_pieces_must_be_the_committed_data() {
+		new_test_ext().execute_with(|| {
+			register(1, 1_000);
+			assert_ok!(create_order(2, 1, 100));
+			assert_ok!(create_order(2, 1, 600));
+			let piece = |order_id, size, comm_p| Piece { order_id, part: 0, size, comm_p };
+
+			assert_noop!(
+				precommit(1, vec![piece(0, 100, [8; 32])]),
+				Error::<Test>::MismatchedPiece
+			);
+			assert_noop!(
+				Ipse::precommit_sector(
+					Origin::signed(1),
+					0,
+					[0; 32],
+					vec![piece(0, 100, COMM_P)],
+					vec![vec![COMM_P]]
+				),
+				Error::<Test>::MismatchedPiece
+			);
+			// the piece holds the whole data of the order.
+			assert_noop!(precommit(1, vec![piece(0, 99, COMM_P)]), Error::<Test>::InvalidPieces);
+			// the larger piece is packed first.
+			assert_noop!(
+				precommit(1, vec![piece(0, 100, COMM_P), piece(1, 600, COMM_P)]),
+				Error::<Test>::InvalidPieces
+			);
+
+			assert_ok!(precommit(1, vec![piece(1, 600, COMM_P), piece(0, 100, COMM_P)]));
+			let sector = Ipse::sector(1u64, 0u64).unwrap();
+			let layout = [(32, COMM_P), (4, COMM_P)];
+			assert_eq!(sector.comm_d, comm_d_of(&layout, SECTOR_SIZE / NODE_SIZE));
+		});
+	}
+
+	#[test]
+	fn comm_d_is_the_root_of_the_packed_pieces() {
+		let params = seal::SectorParams { nodes: 64, window_nodes: 16 };
+		let a = (0..600).map(|i| i as u8).collect::<Vec<_>>();
+		let b = vec![9u8; 100];
+		assert_eq!((piece_nodes(600), piece_nodes(100)), (32, 4));
+
+		let mut data = a.clone();
+		data.resize(32 * NODE_SIZE as usize, 0);
+		data.extend(&b);
+		let replica = seal::seal(params, [1; 32], &data);
+		let layout = [(32, seal::comm_p(&a)), (4, seal::comm_p(&b))];
+		assert_eq!(comm_d_of(&layout, params.nodes), replica.comm_d());
+		assert_eq!(comm_d_of(&[], params.nodes), seal::seal(params, [1; 32], &[]).comm_d());
+	}
+
+	#[test]
+	fn chunks_are_verified_against_the_comm_p() {
+		// three chunks, the last one is not full, the tree is padded to four.
+		let data = (0..3_000).map(|i| i as u8).collect::<Vec<_>>();
+		let chunk = |i: usize| &data[i * 1024..data.len().min((i + 1) * 1024)];
+		let comm_p = seal::comm_p(&data);
+		let roots = [
+			seal::comm_p(chunk(0)),
+			seal::comm_p(chunk(1)),
+			seal::comm_p(chunk(2)),
+			seal::comm_p(&[0; 1024]),
+		];
+		let left = node_hash(&roots[0], &roots[1]);
+		let right = node_hash(&roots[2], &roots[3]);
+
+		assert!(verify_chunk(3_000, 1, chunk(1), &[roots[0], right], comm_p));
+		assert!(verify_chunk(3_000, 2, chunk(2), &[roots[3], left], comm_p));
+		assert!(!verify_chunk(3_000, 2, &chunk(2)[..900], &[roots[3], left], comm_p));
+		assert!(!verify_chunk(3_000, 1, chunk(0), &[roots[0], right], comm_p));
+		assert!(!verify_chunk(3_000, 3, &[0; 1024], &[roots[2], left], comm_p));
+
+		// the data smaller than a chunk is a single one.
+		assert!(verify_chunk(100, 0, &data[..100], &[], seal::comm_p(&data[..100])));
+	}
+
+	#[test]
+	fn parts_are_verified_against_the_data() {
+		let parts = [[1u8; 32], [2; 32], [3; 32], [4; 32]];
+		let left = node_hash(&parts[0], &parts[1]);
+		let right = node_hash(&parts[2], &parts[3]);
+		let root = node_hash(&left, &right);
+		// three parts, the tree is padded to four.
+		let size = 3 * SECTOR_SIZE as u128 - 1;
+		assert_eq!(part_size(size, 2), SECTOR_SIZE as u128 - 1);
+		assert_eq!(part_size(size, 3), 0);
+
+		assert!(verify_part(size, 2, parts[2], &[parts[3], left], root));
+		assert!(verify_part(size, 1, parts[1], &[parts[0], right], root));
+		assert!(!verify_part(size, 1, parts[2], &[parts[3], left], root));
+		assert!(!verify_part(size, 3, parts[3], &[parts[2], left], root));
+		assert!(!verify_part(size, 2, parts[2], &[parts[3]], root));
+
+		// a single part is the whole data.
+		assert!(verify_part(100, 0, parts[0], &[], parts[0]));
+		assert!(!verify_part(100, 0, parts[0], &[parts[1]], node_hash(&parts[0], &parts[1])));
+	}
+
+	#[test]
+	fn retrieval_prices_are_checked_for_overflow() {
+		new_test_ext().execute_with(|| {
+			register(1, 1_000);
+			assert_ok!(create_order(2, 1, 100));
+			Orders::<Test>::mutate(0u64, |o| o.as_mut().unwrap().status = OrderStatus::Confirmed);
+			Commitments::<Test>::insert(0u64, 1u64, COMM_P);
+			let reserved = Balances::reserved_balance(2);
+
+			// the escrow of 2 GB at the max price per GB doesn't fit in a balance.
+			assert_noop!(
+				Ipse::open_retrieval(Origin::signed(2), 0, 1, u128::MAX, 2 * GB as u128),
+				Error::<Test>::Overflow
+			);
+
+			assert_ok!(Ipse::open_retrieval(Origin::signed(2), 0, 1, 1_000, GB as u128));
+			assert_eq!(Balances::reserved_balance(2), reserved + 1_000);
+			assert_ok!(Ipse::close_retrieval(Origin::signed(1), 0));
+			assert_eq!(Balances::reserved_balance(2), reserved);
+			assert_eq!(Ipse::miner_deals(1u64), 0);
+		});
+	}
+
+	#[test]
+	fn orders_are_released_and_pruned_after_expiry() {
+		new_test_ext().execute_with(|| {
+			register(1, 1_000);
+			let reserved = Balances::reserved_balance(2);
+			let stash = Balances::free_balance(1);
+			assert_ok!(create_order_of(2, 1, 100, 2));
+			assert_eq!(Ipse::miner(1u64).unwrap().capacity, 900);
+			assert_eq!(Balances::reserved_balance(2), reserved + 200);
+			assert_eq!(Ipse::due_orders(11u64), vec![0]);
+
+			// the first period is proven and paid.
+			prove(0, 1);
+			run_to_block(12);
+			assert_eq!(Balances::free_balance(1), stash + 100);
+			assert_eq!(Balances::reserved_balance(2), reserved + 100);
+			assert_eq!(Ipse::order(0u64).unwrap().status, OrderStatus::Confirmed);
+
+			// the second one is not proven, it is refunded and the order expires.
+			run_to_block(22);
+			assert_eq!(Balances::free_balance(1), stash + 100);
+			assert_eq!(Balances::reserved_balance(2), reserved);
+			let order = Ipse::order(0u64).unwrap();
+			assert_eq!((order.status, order.settled), (OrderStatus::Expired, 2));
+			assert_eq!(Ipse::miner(1u64).unwrap().capacity, 1_000);
+			assert_eq!(Ipse::prunable_orders(41u64), vec![0]);
+
+			// the order is pruned after the retention window.
+			run_to_block(41);
+			assert!(Ipse::order(0u64).is_some());
+			run_to_block(42);
+			assert!(Ipse::order(0u64).is_none());
+			assert!(Ipse::commitment(0u64, 1u64).is_none());
+			assert!(Ipse::user_orders(2u64).is_empty());
+			assert!(Ipse::hash_orders(Cid::parse(CID).unwrap()).is_empty());
+		});
+	}
+
+	#[test]
+	fn deleted_orders_are_refunded_pro_rata() {
+		new_test_ext().execute_with(|| {
+			register(1, 1_000);
+			let reserved = Balances::reserved_balance(2);
+			let stash = Balances::free_balance(1);
+			assert_ok!(create_order_of(2, 1, 100, 3));
+			prove(0, 1);
+
+			// the first period is paid at its settlement.
+			run_to_block(12);
+			assert_eq!(Balances::free_balance(1), stash + 100);
+
+			// the miner proves the second period, and is paid for the 4 blocks of it before
+			// the deletion, the rest of it and the third period are refunded.
+			prove(0, 1);
+			run_to_block(15);
+			assert_ok!(Ipse::delete_order(Origin::signed(2), 0));
+			assert_eq!(Balances::free_balance(1), stash + 140);
+			assert_eq!(Balances::reserved_balance(2), reserved);
+			let order = Ipse::order(0u64).unwrap();
+			assert_eq!((order.status, order.settled), (OrderStatus::Deleted, 3));
+			assert_eq!(Ipse::miner(1u64).unwrap().capacity, 1_000);
+
+			// the due settlement of the deleted order does nothing, it is pruned after the
+			// retention window.
+			run_to_block(36);
+			assert!(Ipse::order(0u64).is_none());
+			assert_eq!(Balances::free_balance(1), stash + 140);
+		});
+	}
+
+	#[test]
+	fn every_replica_is_stored_and_paid_by_its_own_miner() {
+		new_test_ext().execute_with(|| {
+			register(1, 100);
+			register(2, 100);
+
+			// a miner stores one replica of the order at most.
+			assert_noop!(create_replicas(3, vec![1, 1], 10), Error::<Test>::DuplicateMiner);
+			assert_ok!(create_replicas(3, vec![1, 2], 10));
+
+			let order = Ipse::order(0).unwrap();
+			assert_eq!(order.status, OrderStatus::Created);
+			assert_eq!(order.orders.iter().map(|mo| mo.miner).collect::<Vec<_>>(), vec![1, 2]);
+			assert_eq!(Ipse::miner(1).unwrap().capacity, 90);
+			assert_eq!(Ipse::miner(2).unwrap().capacity, 90);
+			// the user pays for every replica.
+			assert_eq!(Balances::reserved_balance(3), 20);
+		});
 	}
 }
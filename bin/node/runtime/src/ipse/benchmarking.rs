@@ -0,0 +1,618 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks of the ipse module.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use crate::cid::{Multihash, IDENTITY, MAX_DIGEST_LEN, MAX_TEXT_LEN, RAW};
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_support::traits::UnfilteredDispatchable;
+use frame_system::RawOrigin;
+use sp_runtime::{traits::Bounded, KeyTypeId};
+
+const SEED: u32 = 0;
+
+const CID: &[u8] = b"QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+
+/// the size of the data of the orders.
+const SIZE: u128 = MB as u128;
+
+/// the CommP of the data of the orders.
+const COMM_P: [u8; 32] = [0; 32];
+
+const PERIODS: u32 = 10;
+
+/// the max depth of the merkle tree of the data, the chunk index is a `u64`.
+const MAX_DEPTH: u32 = 64;
+
+const KEY_TYPE: KeyTypeId = KeyTypeId(*b"ipse");
+
+/// the length of the shortest CID that `cid_of_len` makes.
+const MIN_CID_LEN: u32 = 7;
+
+/// the longest CIDv1 in base58btc of at most `len` characters, the base58 decoding is the
+/// slowest one.
+fn cid_of_len(len: u32) -> Vec<u8> {
+	(0..=MAX_DIGEST_LEN)
+		.map(|d| Cid::new_v1(RAW, Multihash { code: IDENTITY, digest: vec![0xff; d] }).to_base58())
+		.take_while(|text| text.len() <= len as usize)
+		.last()
+		.unwrap_or_default()
+}
+
+fn fund<T: Trait>(who: &T::AccountId) {
+	T::StakingCurrency::make_free_balance_be(who, BalanceOf::<T>::max_value() / 2u32.into());
+}
+
+fn funded_account<T: Trait>(name: &'static str, index: u32) -> T::AccountId {
+	let who = account(name, index, SEED);
+	fund::<T>(&who);
+	who
+}
+
+/// register the miner with `GB` of capacity at the price of 1 per byte.
+fn register<T: Trait>(miner: &T::AccountId) -> Result<(), &'static str> {
+	fund::<T>(miner);
+	Module::<T>::register_miner(
+		RawOrigin::Signed(miner.clone()).into(),
+		b"miner".to_vec(),
+		b"region".to_vec(),
+		miner.encode(),
+		vec![0; 32],
+		miner.clone(),
+		GB as u128,
+		1u32.into(),
+	)?;
+	Ok(())
+}
+
+fn miners<T: Trait>(n: u32) -> Result<Vec<T::AccountId>, &'static str> {
+	let mut miners = vec![];
+	for i in 0..n {
+		let miner = account("miner", i, SEED);
+		register::<T>(&miner)?;
+		miners.push(miner);
+	}
+	Ok(miners)
+}
+
+/// `n` miners are in the recommend list, the former reserve more.
+fn recommended_miners<T: Trait>(n: u32) -> Result<Vec<T::AccountId>, &'static str> {
+	let miners = miners::<T>(n)?;
+	for (i, miner) in miners.iter().enumerate() {
+		let amount = (n - i as u32).into();
+		Module::<T>::apply_to_recommended_list(RawOrigin::Signed(miner.clone()).into(), amount)?;
+	}
+	Ok(miners)
+}
+
+/// the miner offers all its capacity at the price of 1 per byte.
+fn publish_ask<T: Trait>(miner: &T::AccountId, region: Vec<u8>) -> Result<(), &'static str> {
+	let origin = RawOrigin::Signed(miner.clone()).into();
+	Module::<T>::publish_ask(origin, 1u32.into(), GB as u128, region, false, Default::default())?;
+	Ok(())
+}
+
+/// the user creates an order of `SIZE` bytes stored by the miners.
+fn create_order<T: Trait>(
+	user: &T::AccountId,
+	miners: Vec<T::AccountId>,
+) -> Result<OrderId, &'static str> {
+	let order_id = Module::<T>::next_order_id();
+	let replicas = miners.len() as u32;
+	Module::<T>::create_order(
+		RawOrigin::Signed(user.clone()).into(),
+		miners,
+		replicas,
+		b"label".to_vec(),
+		CID.to_vec(),
+		SIZE,
+		COMM_P,
+		None,
+		PERIODS,
+		DataClass::Cold,
+		RetrievalFrequency::Low,
+		1u32.into(),
+	)?;
+	Ok(order_id)
+}
+
+/// all the replicas of the order are confirmed and proven, the data has the CommP `comm_p`.
+fn confirm<T: Trait>(order_id: OrderId, comm_p: [u8; 32]) {
+	Orders::<T>::mutate(order_id, |o| if let Some(order) = o {
+		for mo in order.orders.iter_mut() {
+			Commitments::<T>::insert(order_id, &mo.miner, comm_p);
+			mo.verify_result = true;
+		}
+		order.status = OrderStatus::Confirmed;
+	});
+}
+
+/// the data of the orders is sealed in the sector of the miner, its seed is drawn.
+fn insert_sector<T: Trait>(
+	miner: &T::AccountId,
+	sector_id: SectorId,
+	order_ids: &[OrderId],
+	status: SectorStatus,
+) {
+	let pieces = order_ids
+		.iter()
+		.map(|order_id| Piece { order_id: *order_id, part: 0, size: SIZE, comm_p: [0; 32] })
+		.collect();
+	for order_id in order_ids {
+		OrderSectors::<T>::mutate(order_id, miner, |ids| ids.push(sector_id));
+	}
+	Sectors::<T>::insert(miner, sector_id, Sector {
+		comm_r: [0; 32],
+		comm_d: [0; 32],
+		pieces,
+		status,
+		seal_at: Zero::zero(),
+		seed: Some([0; 32]),
+		faulty: false,
+	});
+}
+
+/// the user has a confirmed order stored by a miner.
+fn confirmed_order<T: Trait>() -> Result<(T::AccountId, T::AccountId, OrderId), &'static str> {
+	let user = funded_account::<T>("user", 0);
+	let miner = miners::<T>(1)?.remove(0);
+	let order_id = create_order::<T>(&user, vec![miner.clone()])?;
+	confirm::<T>(order_id, COMM_P);
+	Ok((user, miner, order_id))
+}
+
+benchmarks! {
+	where_clause {
+		where
+			T::Signature: From<sp_core::sr25519::Signature>,
+			T::Signer: From<sp_core::sr25519::Public>,
+	}
+
+	_ { }
+
+	register_miner {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+		let url = caller.encode();
+	}: _(
+		RawOrigin::Signed(caller.clone()),
+		b"miner".to_vec(),
+		b"region".to_vec(),
+		url,
+		vec![0; 32],
+		caller.clone(),
+		GB as u128,
+		1u32.into()
+	)
+	verify {
+		assert!(Miners::<T>::contains_key(&caller));
+	}
+
+	// the url and the staking change.
+	update_miner {
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+		let url = b"url".to_vec();
+	}: _(
+		RawOrigin::Signed(caller.clone()),
+		b"miner".to_vec(),
+		b"region".to_vec(),
+		url.clone(),
+		vec![0; 32],
+		caller.clone(),
+		2u32.into()
+	)
+	verify {
+		assert_eq!(Module::<T>::miner(&caller).map(|m| m.url), Some(url));
+	}
+
+	add_capacity {
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+	}: _(RawOrigin::Signed(caller.clone()), GB as u128)
+	verify {
+		assert_eq!(Module::<T>::miner(&caller).map(|m| m.capacity), Some(2 * GB as u128));
+	}
+
+	// the ask of the miner is narrowed down.
+	reduce_capacity {
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+		publish_ask::<T>(&caller, vec![])?;
+	}: _(RawOrigin::Signed(caller.clone()), GB as u128 / 2)
+	verify {
+		assert_eq!(Module::<T>::ask(&caller).map(|ask| ask.capacity), Some(GB as u128 / 2));
+	}
+
+	// the ask of the miner is narrowed down.
+	update_capabilities {
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+		publish_ask::<T>(&caller, vec![])?;
+	}: _(RawOrigin::Signed(caller.clone()), true, BandwidthTier::Public10M)
+	verify {
+		assert_eq!(Module::<T>::miner(&caller).map(|m| m.hot), Some(true));
+	}
+
+	set_reference_price {
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+	}: _(RawOrigin::Signed(caller.clone()), Some(1_000))
+	verify {
+		assert_eq!(Module::<T>::reference_price(&caller), Some(1_000));
+	}
+
+	// the miner is the last one of the full recommend list.
+	unregister_miner {
+		recommended_miners::<T>(19)?;
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+		let origin = RawOrigin::Signed(caller.clone());
+		Module::<T>::apply_to_recommended_list(origin.into(), 1u32.into())?;
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(!Miners::<T>::contains_key(&caller));
+	}
+
+	withdraw_unbonded {
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+		Module::<T>::unregister_miner(RawOrigin::Signed(caller.clone()).into())?;
+		let now = frame_system::Module::<T>::block_number();
+		frame_system::Module::<T>::set_block_number(now + T::UnbondingDuration::get());
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(!Unbonding::<T>::contains_key(&caller));
+	}
+
+	// all the miners are selected from the recommend list.
+	create_order {
+		let r in 1 .. T::MaxReplicas::get();
+		let c in MIN_CID_LEN .. MAX_TEXT_LEN as u32;
+		recommended_miners::<T>(r)?;
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+	}: _(
+		RawOrigin::Signed(caller.clone()),
+		vec![],
+		r,
+		b"label".to_vec(),
+		cid_of_len(c),
+		SIZE,
+		COMM_P,
+		None,
+		PERIODS,
+		DataClass::Cold,
+		RetrievalFrequency::Low,
+		1u32.into()
+	)
+	verify {
+		assert_eq!(Module::<T>::order(0 as OrderId).map(|o| o.orders.len()), Some(r as usize));
+	}
+
+	// every piece is a whole order.
+	precommit_sector {
+		let p in 1 .. MAX_PIECES;
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+		let user = funded_account::<T>("user", 0);
+		let mut pieces = vec![];
+		for _ in 0..p {
+			let order_id = create_order::<T>(&user, vec![caller.clone()])?;
+			pieces.push(Piece { order_id, part: 0, size: SIZE, comm_p: [0; 32] });
+		}
+	}: _(RawOrigin::Signed(caller.clone()), 0, [0; 32], pieces, vec![vec![]; p as usize])
+	verify {
+		assert!(Sectors::<T>::contains_key(&caller, 0 as SectorId));
+	}
+
+	// no valid seal proof can be made in the runtime, the proof is rejected after it is
+	// checked, the storage written by the accepted proof is weighed in the weight file.
+	prove_commit_sector {
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+		let user = funded_account::<T>("user", 0);
+		let mut order_ids = vec![];
+		for _ in 0..MAX_PIECES {
+			order_ids.push(create_order::<T>(&user, vec![caller.clone()])?);
+		}
+		insert_sector::<T>(&caller, 0, &order_ids, SectorStatus::PreCommitted);
+		let origin = RawOrigin::Signed(caller.clone());
+	}: {
+		let result = Module::<T>::prove_commit_sector(origin.into(), 0, vec![0; 1024]);
+		assert_eq!(result, Err(Error::<T>::InvalidSealProof.into()));
+	}
+
+	// the replicas are confirmed, the miner is paid for the blocks of the current period.
+	delete_order {
+		let r in 1 .. T::MaxReplicas::get();
+		let miners = miners::<T>(r)?;
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+		let order_id = create_order::<T>(&caller, miners.clone())?;
+		confirm::<T>(order_id, COMM_P);
+		for miner in miners.iter() {
+			insert_sector::<T>(miner, 0, &[order_id], SectorStatus::Active);
+		}
+		let now = frame_system::Module::<T>::block_number();
+		let half = T::SettlementPeriod::get() / 2u32.into();
+		frame_system::Module::<T>::set_block_number(now + half);
+	}: _(RawOrigin::Signed(caller.clone()), order_id)
+	verify {
+		let status = Module::<T>::order(order_id).map(|o| o.status);
+		assert_eq!(status, Some(OrderStatus::Deleted));
+	}
+
+	renew_order {
+		let r in 1 .. T::MaxReplicas::get();
+		let miners = miners::<T>(r)?;
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+		let order_id = create_order::<T>(&caller, miners)?;
+	}: _(RawOrigin::Signed(caller.clone()), order_id, PERIODS)
+	verify {
+		assert_eq!(Module::<T>::order(order_id).map(|o| o.periods), Some(2 * PERIODS));
+	}
+
+	set_auto_renew {
+		let (user, _, order_id) = confirmed_order::<T>()?;
+	}: _(RawOrigin::Signed(user), order_id, PERIODS)
+	verify {
+		assert_eq!(Module::<T>::auto_renew(order_id), Some(PERIODS));
+	}
+
+	open_retrieval {
+		let (user, miner, order_id) = confirmed_order::<T>()?;
+	}: _(RawOrigin::Signed(user), order_id, miner, 1_000u32.into(), GB as u128)
+	verify {
+		assert!(RetrievalDeals::<T>::contains_key(0 as DealId));
+	}
+
+	// the voucher is signed by the key of the user.
+	claim_retrieval {
+		let public = sp_io::crypto::sr25519_generate(KEY_TYPE, None);
+		let user = T::Signer::from(public.clone()).into_account();
+		fund::<T>(&user);
+		let miner = miners::<T>(1)?.remove(0);
+		let order_id = create_order::<T>(&user, vec![miner.clone()])?;
+		confirm::<T>(order_id, COMM_P);
+		let origin = RawOrigin::Signed(user.clone());
+		let price = 1_000u32.into();
+		Module::<T>::open_retrieval(origin.into(), order_id, miner.clone(), price, GB as u128)?;
+
+		let bytes = GB as u128;
+		let voucher = (b"ipse/retrieval", 0 as DealId, bytes).encode();
+		let signature = sp_io::crypto::sr25519_sign(KEY_TYPE, &public, &voucher)
+			.ok_or("the key is in the keystore")?;
+	}: _(RawOrigin::Signed(miner), 0, bytes, signature.into())
+	verify {
+		assert_eq!(Module::<T>::retrieval_deal(0 as DealId).map(|d| d.claimed), Some(bytes));
+	}
+
+	dispute_retrieval {
+		let (user, miner, order_id) = confirmed_order::<T>()?;
+		let origin = RawOrigin::Signed(user.clone());
+		let price = 1_000u32.into();
+		Module::<T>::open_retrieval(origin.into(), order_id, miner, price, GB as u128)?;
+	}: _(RawOrigin::Signed(user), 0)
+	verify {
+		assert_eq!(Module::<T>::retrieval_deal(0 as DealId).map(|d| d.disputed), Some(true));
+	}
+
+	// the user closes the deal after the deadline.
+	close_retrieval {
+		let (user, miner, order_id) = confirmed_order::<T>()?;
+		let origin = RawOrigin::Signed(user.clone());
+		let price = 1_000u32.into();
+		Module::<T>::open_retrieval(origin.into(), order_id, miner, price, GB as u128)?;
+		let now = frame_system::Module::<T>::block_number();
+		frame_system::Module::<T>::set_block_number(now + T::RetrievalTimeout::get() + 1u32.into());
+	}: _(RawOrigin::Signed(user), 0)
+	verify {
+		assert!(!RetrievalDeals::<T>::contains_key(0 as DealId));
+	}
+
+	// the proof is rejected, the sector fails all its orders and is slashed.
+	verify_storage {
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+		let user = funded_account::<T>("user", 0);
+		let mut order_ids = vec![];
+		for _ in 0..MAX_PIECES {
+			let order_id = create_order::<T>(&user, vec![caller.clone()])?;
+			confirm::<T>(order_id, COMM_P);
+			order_ids.push(order_id);
+		}
+		insert_sector::<T>(&caller, 0, &order_ids, SectorStatus::Active);
+		Challenges::<T>::insert(&caller, 0, Challenge {
+			node_index: 0,
+			nonce: 0,
+			deadline: T::BlockNumber::max_value(),
+		});
+	}: _(RawOrigin::Signed(caller.clone()), 0, 0, vec![0; 1024])
+	verify {
+		assert_eq!(Module::<T>::sector(&caller, 0 as SectorId).map(|s| s.faulty), Some(true));
+	}
+
+	// the chunks are drawn at random.
+	open_dispute {
+		let (user, miner, order_id) = confirmed_order::<T>()?;
+	}: _(RawOrigin::Signed(user), order_id, miner.clone(), vec![])
+	verify {
+		assert!(Disputes::<T>::contains_key(order_id, &miner));
+	}
+
+	// the last chunk is proven, the dispute is rejected and the frozen settlement goes on, the
+	// data is large enough that the path of its first chunk is `p` nodes long.
+	prove_dispute {
+		let p in 1 .. MAX_DEPTH;
+		let (user, miner, order_id) = confirmed_order::<T>()?;
+
+		let chunk = vec![0u8; CHUNK_SIZE as usize];
+		let proof = vec![[0u8; 32]; p as usize];
+		let leaf = nodes_root(&chunk, (CHUNK_SIZE as u64 / NODE_SIZE) as usize);
+		let comm_p = proof.iter().fold(leaf, |node, sibling| node_hash(&node, sibling));
+		Orders::<T>::mutate(order_id, |o| if let Some(order) = o {
+			order.size = CHUNK_SIZE << p;
+		});
+		confirm::<T>(order_id, comm_p);
+
+		let deposit = T::DisputeDeposit::get();
+		T::StakingCurrency::reserve(&user, deposit)?;
+		Disputes::<T>::insert(order_id, &miner, Dispute {
+			kind: DisputeKind::DataLost,
+			plaintiff: user,
+			deposit,
+			pending_chunks: vec![0],
+			deadline: T::BlockNumber::max_value(),
+		});
+		FrozenSettlements::<T>::insert(order_id, frame_system::Module::<T>::block_number());
+	}: _(RawOrigin::Signed(miner.clone()), order_id, 0, chunk, proof)
+	verify {
+		assert!(!Disputes::<T>::contains_key(order_id, &miner));
+	}
+
+	// the miner is at fault, it is slashed and its replica ends.
+	arbitrate {
+		let (user, miner, order_id) = confirmed_order::<T>()?;
+		let origin = RawOrigin::Signed(user);
+		Module::<T>::open_dispute(origin.into(), order_id, miner.clone(), vec![])?;
+		let origin = T::ArbitrationOrigin::successful_origin();
+		let call = Call::<T>::arbitrate(order_id, miner.clone(), Verdict::MinerAtFault);
+	}: { call.dispatch_bypass_filter(origin)? }
+	verify {
+		assert!(!Disputes::<T>::contains_key(order_id, &miner));
+	}
+
+	publish_ask {
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+	}: _(
+		RawOrigin::Signed(caller.clone()),
+		1u32.into(),
+		GB as u128,
+		b"region".to_vec(),
+		false,
+		BandwidthTier::Private
+	)
+	verify {
+		assert!(Asks::<T>::contains_key(&caller));
+	}
+
+	cancel_ask {
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+		publish_ask::<T>(&caller, vec![])?;
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(!Asks::<T>::contains_key(&caller));
+	}
+
+	publish_bid {
+		let c in MIN_CID_LEN .. MAX_TEXT_LEN as u32;
+		let caller: T::AccountId = whitelisted_caller();
+	}: _(
+		RawOrigin::Signed(caller),
+		b"label".to_vec(),
+		cid_of_len(c),
+		SIZE,
+		COMM_P,
+		None,
+		PERIODS,
+		T::MaxReplicas::get(),
+		b"region".to_vec(),
+		DataClass::Cold,
+		RetrievalFrequency::Low,
+		1u32.into()
+	)
+	verify {
+		assert!(Bids::<T>::contains_key(0 as BidId));
+	}
+
+	cancel_bid {
+		let caller: T::AccountId = whitelisted_caller();
+		Module::<T>::publish_bid(
+			RawOrigin::Signed(caller.clone()).into(),
+			b"label".to_vec(),
+			CID.to_vec(),
+			SIZE,
+			COMM_P,
+			None,
+			PERIODS,
+			T::MaxReplicas::get(),
+			vec![],
+			DataClass::Cold,
+			RetrievalFrequency::Low,
+			1u32.into(),
+		)?;
+	}: _(RawOrigin::Signed(caller), 0)
+	verify {
+		assert!(!Bids::<T>::contains_key(0 as BidId));
+	}
+
+	// every miner matched has an ask in the region of the bid.
+	submit_match {
+		let r in 1 .. T::MaxReplicas::get();
+		let miners = miners::<T>(r)?;
+		for miner in miners.iter() {
+			publish_ask::<T>(miner, b"region".to_vec())?;
+		}
+		let user = funded_account::<T>("user", 0);
+		Module::<T>::publish_bid(
+			RawOrigin::Signed(user).into(),
+			b"label".to_vec(),
+			CID.to_vec(),
+			SIZE,
+			COMM_P,
+			None,
+			PERIODS,
+			r,
+			b"region".to_vec(),
+			DataClass::Cold,
+			RetrievalFrequency::Low,
+			1u32.into(),
+		)?;
+	}: _(RawOrigin::None, 0, miners)
+	verify {
+		assert!(!Bids::<T>::contains_key(0 as BidId));
+		assert_eq!(Module::<T>::order(0 as OrderId).map(|o| o.orders.len()), Some(r as usize));
+	}
+
+	// the miner goes first, the last one drops from the full list.
+	apply_to_recommended_list {
+		recommended_miners::<T>(20)?;
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+		let amount = 21u32.into();
+	}: _(RawOrigin::Signed(caller.clone()), amount)
+	verify {
+		assert_eq!(Module::<T>::recommend_list()[0], (caller, amount));
+	}
+
+	// the miner is the last one of the full list.
+	drop_out_recommended_list {
+		recommended_miners::<T>(19)?;
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller)?;
+		let origin = RawOrigin::Signed(caller.clone());
+		Module::<T>::apply_to_recommended_list(origin.into(), 1u32.into())?;
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(Module::<T>::recommend_list().iter().all(|(miner, _)| miner != &caller));
+	}
+}
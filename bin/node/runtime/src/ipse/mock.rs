@@ -0,0 +1,191 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test utilities of the ipse module.
+
+use super::{Module, Trait};
+use frame_support::{
+	impl_outer_dispatch, impl_outer_origin, parameter_types,
+	traits::{OnFinalize, OnInitialize, TestRandomness},
+	weights::Weight,
+};
+use sp_core::H256;
+use sp_runtime::{
+	testing::{Header, TestSignature, TestXt, UintAuthorityId},
+	traits::{BlakeTwo256, IdentityLookup},
+	ModuleId, Perbill, Permill,
+};
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		ipse::Ipse,
+	}
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Call = Call;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type MaximumBlockLength = MaximumBlockLength;
+	type Version = ();
+	type PalletInfo = ();
+	type AccountData = pallet_balances::AccountData<u128>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Test
+where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = TestXt<Call, ()>;
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u128 = 1;
+}
+
+impl pallet_balances::Trait for Test {
+	type MaxLocks = ();
+	type Balance = u128;
+	type DustRemoval = ();
+	type Event = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const MinimumPeriod: u64 = 1;
+}
+
+impl pallet_timestamp::Trait for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const TreasuryModuleId: ModuleId = ModuleId(*b"py/trsry");
+	pub const UnbondingDuration: u64 = 10;
+	pub const ChallengeDuration: u64 = 5;
+	pub const MaxReplicas: u32 = 3;
+	pub const UnsignedPriority: u64 = u64::max_value() / 2;
+	pub const SettlementPeriod: u64 = 10;
+	pub const OrderRetention: u64 = 20;
+	pub const RetrievalTimeout: u64 = 10;
+	pub const ReputationWeight: Permill = Permill::from_percent(50);
+	pub const MinerHistoryLen: u32 = 10;
+	pub const HistoryLen: u32 = 10;
+	pub const ListOrderLen: u32 = 10;
+	pub const DisputeWindow: u64 = 10;
+	pub const DisputeDeposit: u128 = 10;
+	pub const DisputeChunks: u32 = 4;
+	pub const SealDelay: u64 = 2;
+	pub const SealDuration: u64 = 5;
+}
+
+impl Trait for Test {
+	type Event = ();
+	type Currency = Balances;
+	type StakingCurrency = Balances;
+	type TreasuryModuleId = TreasuryModuleId;
+	type StakingSlash = ();
+	type UnbondingDuration = UnbondingDuration;
+	type Randomness = TestRandomness;
+	type ChallengeDuration = ChallengeDuration;
+	type MaxReplicas = MaxReplicas;
+	type UnsignedPriority = UnsignedPriority;
+	type SettlementPeriod = SettlementPeriod;
+	type OrderRetention = OrderRetention;
+	type RetrievalTimeout = RetrievalTimeout;
+	type Signature = TestSignature;
+	type Signer = UintAuthorityId;
+	type ReputationWeight = ReputationWeight;
+	type PriceFeed = ();
+	type MinerHistoryLen = MinerHistoryLen;
+	type HistoryLen = HistoryLen;
+	type ListOrderLen = ListOrderLen;
+	type ArbitrationOrigin = frame_system::EnsureRoot<u64>;
+	type DisputeWindow = DisputeWindow;
+	type DisputeDeposit = DisputeDeposit;
+	type DisputeChunks = DisputeChunks;
+	type SealDelay = SealDelay;
+	type SealDuration = SealDuration;
+	type WeightInfo = ();
+}
+
+pub type System = frame_system::Module<Test>;
+pub type Balances = pallet_balances::Module<Test>;
+pub type Ipse = Module<Test>;
+
+/// the balance every account has at genesis.
+pub const INITIAL_BALANCE: u128 = 10_000;
+
+/// accounts `1..=5` own `INITIAL_BALANCE`, the chain is at block 1.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: (1..=5).map(|who| (who, INITIAL_BALANCE)).collect(),
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	super::GenesisConfig::default().assimilate_storage::<Test>(&mut t).unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// run the hooks of the ipse module until the block `n`.
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		Ipse::on_finalize(System::block_number());
+		System::set_block_number(System::block_number() + 1);
+		Ipse::on_initialize(System::block_number());
+	}
+}
@@ -1,3 +1,19 @@
 pub trait PocHandler<AccountId> {
 	fn remove_history(miner: AccountId);
 }
+
+impl<AccountId> PocHandler<AccountId> for () {
+	fn remove_history(_miner: AccountId) {}
+}
+
+/// The price of the native token in the reference currency.
+pub trait PriceProvider {
+	/// how many native units one reference unit is worth, `None` if there is no fresh price.
+	fn price() -> Option<u128>;
+}
+
+impl PriceProvider for () {
+	fn price() -> Option<u128> {
+		None
+	}
+}
@@ -90,12 +90,16 @@ use sp_runtime::generic::Era;
 /// Weights for pallets used in the runtime.
 mod weights;
 
+pub mod bounded_history;
+pub mod cid;
 pub mod exchange;
 pub mod ipse;
 pub mod ipse_traits;
 pub mod ocw_common;
 pub mod poc;
 pub mod poc_staking;
+pub mod price_feed;
+pub mod search_index;
 
 // Make the WASM binary available.
 #[cfg(feature = "std")]
@@ -120,10 +124,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 2021100601,
-	impl_version: 1,
+	spec_version: 2021100701,
+	impl_version: 2,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 };
 
 /// Native version.
@@ -292,6 +296,7 @@ impl exchange::Trait for Runtime {
 	type UnsignedPriority = OffchainWorkUnsignedPriority;
 	type OnUnbalanced = ();
 	type Currency = Balances;
+	type WeightInfo = weights::exchange::WeightInfo<Runtime>;
 }
 
 impl pallet_proxy::Trait for Runtime {
@@ -309,12 +314,90 @@ impl pallet_proxy::Trait for Runtime {
 	type AnnouncementDepositFactor = AnnouncementDepositFactor;
 }
 
+parameter_types! {
+	pub const ChallengeDuration: BlockNumber = 6 * HOURS;
+	pub const MaxReplicas: u32 = 5;
+	pub const MinerUnbondingDuration: BlockNumber = 7 * DAYS;
+	pub const SettlementPeriod: BlockNumber = 1 * DAYS;
+	pub const OrderRetention: BlockNumber = 30 * DAYS;
+	pub const RetrievalTimeout: BlockNumber = 1 * DAYS;
+	pub const ReputationWeight: Permill = Permill::from_percent(50);
+	pub const IpseMinerHistoryLen: u32 = 500;
+	pub const IpseHistoryLen: u32 = 100;
+	pub const IpseListOrderLen: u32 = 500;
+	pub const DisputeWindow: BlockNumber = 1 * DAYS;
+	pub const DisputeDeposit: Balance = 10 * DOLLARS;
+	pub const DisputeChunks: u32 = 8;
+	pub const SealDelay: BlockNumber = 1 * HOURS;
+	pub const SealDuration: BlockNumber = 1 * DAYS;
+}
+
 impl ipse::Trait for Runtime {
 	type Event = Event;
 	type Currency = Balances;
 
 	type StakingCurrency = Balances;
 	type TreasuryModuleId = TreasuryModuleId;
+	type StakingSlash = Treasury;
+	type UnbondingDuration = MinerUnbondingDuration;
+	type Randomness = RandomnessCollectiveFlip;
+	type ChallengeDuration = ChallengeDuration;
+	type MaxReplicas = MaxReplicas;
+	type UnsignedPriority = OffchainWorkUnsignedPriority;
+	type SettlementPeriod = SettlementPeriod;
+	type OrderRetention = OrderRetention;
+	type RetrievalTimeout = RetrievalTimeout;
+	type Signature = Signature;
+	type Signer = <Signature as traits::Verify>::Signer;
+	type ReputationWeight = ReputationWeight;
+	type PriceFeed = PriceFeed;
+	type MinerHistoryLen = IpseMinerHistoryLen;
+	type HistoryLen = IpseHistoryLen;
+	type ListOrderLen = IpseListOrderLen;
+	type ArbitrationOrigin = EnsureRootOrHalfCouncil;
+	type DisputeWindow = DisputeWindow;
+	type DisputeDeposit = DisputeDeposit;
+	type DisputeChunks = DisputeChunks;
+	type SealDelay = SealDelay;
+	type SealDuration = SealDuration;
+	type WeightInfo = weights::ipse::WeightInfo<Runtime>;
+}
+
+parameter_types! {
+	pub const MaxOracles: u32 = 16;
+	pub const MaxPriceAge: BlockNumber = 1 * HOURS;
+	pub const MaxPriceDeviation: Permill = Permill::from_percent(20);
+	pub const PriceFetchInterval: BlockNumber = 10 * MINUTES;
+}
+
+impl price_feed::Trait for Runtime {
+	type Event = Event;
+	type AuthorityId = price_feed::crypto::OracleId;
+	type OracleOrigin = EnsureRootOrHalfCouncil;
+	type MaxOracles = MaxOracles;
+	type MaxPriceAge = MaxPriceAge;
+	type MaxDeviation = MaxPriceDeviation;
+	type FetchInterval = PriceFetchInterval;
+	type WeightInfo = weights::price_feed::WeightInfo<Runtime>;
+}
+
+parameter_types! {
+	pub const IndexerStake: Balance = 100 * DOLLARS;
+	pub const MaxKeywords: u32 = 16;
+	pub const MaxSegmentLen: u32 = 1_000;
+	pub const MaxKeywordsPerIndexer: u32 = 100;
+}
+
+impl search_index::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type IndexerStake = IndexerStake;
+	type MaxKeywords = MaxKeywords;
+	type MaxSegmentLen = MaxSegmentLen;
+	type MaxKeywordsPerIndexer = MaxKeywordsPerIndexer;
+	type SlashOrigin = EnsureRootOrHalfCouncil;
+	type Slash = Treasury;
+	type WeightInfo = weights::search_index::WeightInfo<Runtime>;
 }
 
 parameter_types! {
@@ -350,6 +433,8 @@ impl poc_staking::Trait for Runtime {
 	type RecommendMaxNumber = RecommendMaxNumber;
 
 	type PocStakingMinAmount = PocStakingMinAmount;
+
+	type WeightInfo = weights::poc_staking::WeightInfo<Runtime>;
 }
 
 parameter_types! {
@@ -361,6 +446,15 @@ parameter_types! {
 	pub const ProbabilityDeviationValue: Percent = Percent::from_percent(50);
 
 	pub const MaxDeadlineValue: u64 = 12000;
+
+	pub const PocHistoryLen: u32 = 300;
+
+	pub const PocUserRewardHistoryLen: u32 = 300;
+
+	pub const PocDlInfoLen: u32 = 2000;
+
+	/// the deadlines are ranked below the im-online heartbeats.
+	pub const PocUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
 }
 
 impl poc::Trait for Runtime {
@@ -375,6 +469,16 @@ impl poc::Trait for Runtime {
 	type ProbabilityDeviationValue = ProbabilityDeviationValue;
 
 	type MaxDeadlineValue = MaxDeadlineValue;
+
+	type HistoryLen = PocHistoryLen;
+
+	type UserRewardHistoryLen = PocUserRewardHistoryLen;
+
+	type DlInfoLen = PocDlInfoLen;
+
+	type UnsignedPriority = PocUnsignedPriority;
+
+	type WeightInfo = weights::poc::WeightInfo<Runtime>;
 }
 
 parameter_types! {
@@ -1046,8 +1150,10 @@ construct_runtime!(
 		Proxy: pallet_proxy::{Module, Call, Storage, Event<T>},
 		Multisig: pallet_multisig::{Module, Call, Storage, Event<T>},
 		PocStaking: poc_staking::{Module, Call, Storage, Event<T>},
-		PoC: poc::{Module, Call, Storage, Event<T>},
-		Ipse: ipse::{Module, Call, Storage, Event<T>},
+		PoC: poc::{Module, Call, Storage, Config, Event<T>, ValidateUnsigned},
+		Ipse: ipse::{Module, Call, Storage, Config, Event<T>, ValidateUnsigned},
+		SearchIndex: search_index::{Module, Call, Storage, Event<T>},
+		PriceFeed: price_feed::{Module, Call, Storage, Event<T>},
 		Exchange: exchange::{Module, Call, Storage, Event<T>,ValidateUnsigned, Config<T>},
 	}
 );
@@ -1283,6 +1389,73 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl ipse::IpseApi<Block, AccountId, Balance, BlockNumber> for Runtime {
+		fn user_orders(
+			user: AccountId,
+			start: u32,
+			count: u32,
+		) -> Vec<(ipse::OrderId, ipse::Order<AccountId, Balance, BlockNumber>)> {
+			Ipse::orders_of_user(&user, start, count)
+		}
+
+		fn miner_orders(
+			miner: AccountId,
+			start: u32,
+			count: u32,
+		) -> Vec<(ipse::OrderId, ipse::Order<AccountId, Balance, BlockNumber>)> {
+			Ipse::orders_of_miner(&miner, start, count)
+		}
+
+		fn order(order_id: ipse::OrderId) -> Option<ipse::Order<AccountId, Balance, BlockNumber>> {
+			Ipse::order(order_id)
+		}
+
+		fn miner(miner: AccountId) -> Option<ipse::MinerDetails<AccountId, Balance>> {
+			Ipse::miner_details(&miner)
+		}
+
+		fn quote(
+			miner: AccountId,
+			size: u128,
+			days: u32,
+			class: ipse::DataClass,
+			frequency: ipse::RetrievalFrequency,
+		) -> Option<ipse::Quote<Balance>> {
+			// the days are rounded up to whole settlement periods.
+			let period = SettlementPeriod::get() as u64;
+			let periods = (days as u64 * DAYS as u64 + period - 1) / period;
+			Ipse::quote(&miner, size, periods.saturated_into(), class, frequency)
+		}
+
+		fn recommend_list() -> Vec<(AccountId, Balance)> {
+			Ipse::recommended_miners()
+		}
+
+		fn miner_reputation(miner: AccountId) -> (ipse::Reputation, Permill) {
+			(Ipse::reputation(&miner), Ipse::reputation_score(&miner))
+		}
+	}
+
+	impl poc::PocApi<Block> for Runtime {
+		fn mining_info() -> (u64, [u8; 32], u64) {
+			PoC::mining_info()
+		}
+	}
+
+	impl search_index::SearchIndexApi<Block, AccountId, BlockNumber> for Runtime {
+		fn search_tagged(keyword: Vec<u8>) -> Vec<cid::Cid> {
+			SearchIndex::search_tagged(keyword)
+		}
+
+		fn search_segments(keyword: Vec<u8>) -> Vec<(AccountId, Vec<cid::Cid>)> {
+			SearchIndex::search_segments(keyword)
+		}
+
+		fn metadata(cid: cid::Cid) -> Option<search_index::ContentMeta<AccountId, BlockNumber>> {
+			SearchIndex::metadata(cid)
+		}
+	}
+
 	impl sp_session::SessionKeys<Block> for Runtime {
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			SessionKeys::generate(seed)
@@ -1351,6 +1524,12 @@ impl_runtime_apis! {
 			add_benchmark!(params, batches, pallet_treasury, Treasury);
 			add_benchmark!(params, batches, pallet_utility, Utility);
 			add_benchmark!(params, batches, pallet_vesting, Vesting);
+			add_benchmark!(params, batches, exchange, Exchange);
+			add_benchmark!(params, batches, ipse, Ipse);
+			add_benchmark!(params, batches, poc, PoC);
+			add_benchmark!(params, batches, poc_staking, PocStaking);
+			add_benchmark!(params, batches, price_feed, PriceFeed);
+			add_benchmark!(params, batches, search_index, SearchIndex);
 
 			if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
 			Ok(batches)
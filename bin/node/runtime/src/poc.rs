@@ -30,22 +30,36 @@ use frame_support::{
 	debug, decl_error, decl_event, decl_module, decl_storage,
 	dispatch::{DispatchError, DispatchResult},
 	ensure,
+	storage::migration::{take_storage_value, StorageKeyIterator},
 	traits::{Currency, Get, Imbalance, OnUnbalanced, ReservableCurrency},
 	weights::Weight,
-	IterableStorageMap, StorageMap, StorageValue,
+	IterableStorageMap, StorageMap, StorageValue, Twox64Concat,
 };
 use pallet_treasury as treasury;
 use sp_runtime::{
 	traits::{CheckedAdd, CheckedDiv, CheckedSub, SaturatedConversion, Saturating},
+	transaction_validity::{
+		InvalidTransaction, TransactionPriority, TransactionSource, TransactionValidity,
+		TransactionValidityError, ValidTransaction,
+	},
 	Percent,
 };
+use sp_io::hashing::blake2_256;
 use sp_std::result;
 use sp_std::vec;
 use sp_std::vec::Vec;
-use system::{ensure_root, ensure_signed};
+use system::{ensure_none, ensure_root, ensure_signed};
 
+use crate::bounded_history::{BoundedHistory, BoundedList, Cursor};
 use crate::ipse_traits::PocHandler;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
 use conjugate_poc::{
 	nonce::noncegen_rust,
 	poc_hashing::{calculate_scoop, find_best_deadline_rust},
@@ -71,6 +85,53 @@ type PositiveImbalanceOf<T> = <<T as staking::Trait>::StakingCurrency as Currenc
 	<T as system::Trait>::AccountId,
 >>::PositiveImbalance;
 
+type HistoryOf<T> = BoundedHistory<
+	<T as system::Trait>::AccountId,
+	(<T as system::Trait>::BlockNumber, BalanceOf<T>),
+	HistoryCursors<T>,
+	HistoryItems<T>,
+>;
+
+type UserRewardHistoryOf<T> = BoundedHistory<
+	<T as system::Trait>::AccountId,
+	(<T as system::Trait>::BlockNumber, BalanceOf<T>),
+	UserRewardHistoryCursors<T>,
+	UserRewardHistoryItems<T>,
+>;
+
+type DlInfoOf<T> =
+	BoundedList<MiningInfo<<T as system::Trait>::AccountId>, DlInfoCursor, DlInfoItems<T>>;
+
+pub trait WeightInfo {
+	fn set_difficulty() -> Weight;
+	fn set_adjust_difficulty_duration() -> Weight;
+	fn set_capacity_price() -> Weight;
+	fn set_capacity_of_per_difficulty() -> Weight;
+	fn mining() -> Weight;
+	fn submit_deadline() -> Weight;
+}
+
+impl WeightInfo for () {
+	fn set_difficulty() -> Weight {
+		1_000_000_000
+	}
+	fn set_adjust_difficulty_duration() -> Weight {
+		1_000_000_000
+	}
+	fn set_capacity_price() -> Weight {
+		1_000_000_000
+	}
+	fn set_capacity_of_per_difficulty() -> Weight {
+		1_000_000_000
+	}
+	fn mining() -> Weight {
+		1_000_000_000
+	}
+	fn submit_deadline() -> Weight {
+		1_000_000_000
+	}
+}
+
 pub trait Trait: system::Trait + timestamp::Trait + treasury::Trait + staking::Trait {
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 
@@ -84,6 +145,21 @@ pub trait Trait: system::Trait + timestamp::Trait + treasury::Trait + staking::T
 	type ProbabilityDeviationValue: Get<Percent>;
 
 	type MaxDeadlineValue: Get<u64>;
+
+	/// how many latest rewards are kept for every miner.
+	type HistoryLen: Get<u32>;
+
+	/// how many latest rewards are kept for every user.
+	type UserRewardHistoryLen: Get<u32>;
+
+	/// how many latest deadlines are kept.
+	type DlInfoLen: Get<u32>;
+
+	/// the base priority of the unsigned deadlines, the lower deadline is given the higher one.
+	type UnsignedPriority: Get<TransactionPriority>;
+
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
 }
 
 #[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
@@ -96,8 +172,24 @@ pub struct MiningInfo<AccountId> {
 
 #[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
 pub struct MiningHistory<Balance, BlockNumber> {
-	total_num: u64,
-	history: Vec<(BlockNumber, Balance)>,
+	pub total_num: u64,
+	pub history: Vec<(BlockNumber, Balance)>,
+}
+
+// A value placed in storage that represents the current version of the poc storage.
+// It is used by `on_runtime_upgrade` to decide whether the storage migration should run.
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Releases {
+	// the histories are kept in `Vec`s.
+	V1_0_0,
+	// the histories are kept in ring buffers.
+	V2_0_0,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1_0_0
+	}
 }
 
 #[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
@@ -114,14 +206,20 @@ decl_storage! {
 		/// difficulties of some duration(50 blocks).
 		pub TargetInfo get(fn target_info): Vec<Difficulty>;
 
-		/// deadlines of the mining.
-		pub DlInfo get(fn dl_info): Vec<MiningInfo<T::AccountId>>;
+		/// deadlines of the mining, in a ring buffer, see `dl_info`.
+		pub DlInfoCursor: Cursor;
+		pub DlInfoItems: map hasher(twox_64_concat) u32 => Option<MiningInfo<T::AccountId>>;
 
-		/// the mining history of miners.
-		pub History get(fn history): map hasher(twox_64_concat) T::AccountId => Option<MiningHistory<BalanceOf<T>, T::BlockNumber>>;
+		/// how many times miners mine a block.
+		pub MiningNums get(fn mining_num): map hasher(twox_64_concat) T::AccountId => u64;
 
-		/// the reward history of users.
-		pub UserRewardHistory get(fn user_reward_history): map hasher(twox_64_concat) T::AccountId => Vec<(T::BlockNumber, BalanceOf<T>)>;
+		/// the mining history of miners, in ring buffers, see `history`.
+		pub HistoryCursors: map hasher(twox_64_concat) T::AccountId => Cursor;
+		pub HistoryItems: double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) u32 => Option<(T::BlockNumber, BalanceOf<T>)>;
+
+		/// the reward history of users, in ring buffers, see `user_reward_history`.
+		pub UserRewardHistoryCursors: map hasher(twox_64_concat) T::AccountId => Cursor;
+		pub UserRewardHistoryItems: double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) u32 => Option<(T::BlockNumber, BalanceOf<T>)>;
 
 		/// the net power(how much capacity)
 		pub NetPower get(fn net_power): u64;
@@ -138,6 +236,15 @@ decl_storage! {
 		/// active miners (now_count, [account_id..], last_count, [account_id..])
 		pub ActiveMiners get(fn active_miners): (u32, BTreeSet<T::AccountId>, u32, BTreeSet<T::AccountId>);
 
+		/// the version of storage.
+		pub StorageVersion get(fn storage_version) build(|_| Releases::V2_0_0): Releases;
+
+		/// the generation signature of the mining cycle, the deadlines are mined with it.
+		pub GenerationSignature get(fn generation_signature): [u8; 32];
+
+		/// the first block of the mining cycle, the deadlines are mined at its height.
+		pub MiningHeight get(fn mining_height): u64;
+
 	}
 }
 
@@ -176,9 +283,18 @@ decl_module! {
 		/// max deadine(you should not submit the value up this value).
 		const MaxDeadlineValue: u64 = T::MaxDeadlineValue::get();
 
+		/// how many latest rewards are kept for every miner.
+		const HistoryLen: u32 = T::HistoryLen::get();
+
+		/// how many latest rewards are kept for every user.
+		const UserRewardHistoryLen: u32 = T::UserRewardHistoryLen::get();
+
+		/// how many latest deadlines are kept.
+		const DlInfoLen: u32 = T::DlInfoLen::get();
+
 
 		/// set the difficulty
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::set_difficulty()]
 		fn set_difficulty(origin, difficulty: u64) {
 
 			ensure_root(origin)?;
@@ -202,7 +318,7 @@ decl_module! {
 
 
 		/// how often to adjust the difficulty.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::set_adjust_difficulty_duration()]
 		fn set_adjust_difficulty_duration(origin, block_num: u64) {
 			ensure_root(origin)?;
 			ensure!(block_num > 0u64, Error::<T>::DurationIsZero);
@@ -211,7 +327,7 @@ decl_module! {
 		}
 
 		/// how much IPSE that one Gib should staking.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::set_capacity_price()]
 		fn set_capacity_price(origin, price: BalanceOf<T>) {
 			ensure_root(origin)?;
 			<CapacityPrice<T>>::put(price);
@@ -221,7 +337,7 @@ decl_module! {
 
 
 		/// how much capacity that one difficulty.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::set_capacity_of_per_difficulty()]
 		fn set_capacity_of_per_difficulty(origin, capacity: u64) {
 			ensure_root(origin)?;
 			ensure!(capacity != 0u64, Error::<T>::CapacityIsZero);
@@ -231,80 +347,39 @@ decl_module! {
 		}
 
 
-		/// submit deadline.
-		#[weight = 50_000_000 as Weight + T::DbWeight::get().reads(8 as Weight).saturating_add(T::DbWeight::get().writes(3 as Weight))]
+		/// submit deadline, mined with the generation signature of the mining cycle.
+		#[weight = <T as Trait>::WeightInfo::mining()]
 		fn mining(origin, account_id: u64, height: u64, sig: [u8; 32], nonce: u64, deadline: u64) -> DispatchResult {
 
 			let miner = ensure_signed(origin)?;
 
-			<ActiveMiners<T>>::mutate(|h| if h.1.insert(miner.clone()) {
-				h.0 += 1;
-			});
-
-			debug::info!("miner: {:?},  submit deadline!, height = {}, deadline = {}", miner.clone(), height, deadline);
-
-			ensure!(deadline <= T::MaxDeadlineValue::get(), Error::<T>::DeadlineTooLarge);
-
-			ensure!(<staking::Module<T>>::is_can_mining(miner.clone())?, Error::<T>::NotRegister);
+			Self::do_mining(miner, account_id, height, sig, nonce, deadline)
+		}
 
-			let real_pid = <staking::Module<T>>::disk_of(&miner).unwrap().numeric_id;
 
-			ensure!(real_pid == account_id.into(), Error::<T>::PidErr);
+		/// submit deadline without fees, for the miner of the plot `account_id`.
+		///
+		/// The deadline is checked in the transaction pool against the best one of the mining
+		/// cycle, only the lowest deadline of the cycle is kept in the pool.
+		#[weight = <T as Trait>::WeightInfo::submit_deadline()]
+		fn submit_deadline(origin, account_id: u64, height: u64, sig: [u8; 32], nonce: u64, deadline: u64) -> DispatchResult {
 
-			let current_block = <system::Module<T>>::block_number().saturated_into::<u64>();
+			ensure_none(origin)?;
 
-			debug::info!("starting Verify Deadline !!!");
+			let miner = <staking::Module<T>>::accouont_id_of_pid(u128::from(account_id)).ok_or(Error::<T>::NotRegister)?;
 
-			if !(current_block / MiningExpire == height / MiningExpire && current_block >= height)
-			{
-				debug::info!("expire! ：{:?}, off chain get info block: {:?}, deadline is: {:?}", height, current_block, deadline);
+			Self::do_mining(miner, account_id, height, sig, nonce, deadline)
+		}
 
-				return Err(Error::<T>::HeightNotInDuration)?;
-			}
 
-			let dl = Self::dl_info();
-			let (block, best_dl) = if let Some(dl_info) = dl.iter().last() {
-				(dl_info.clone().block, dl_info.best_dl)
+		fn on_runtime_upgrade() -> Weight {
+			if Self::storage_version() == Releases::V1_0_0 {
+				Self::migrate_to_ring_buffers();
+				StorageVersion::put(Releases::V2_0_0);
+				T::MaximumBlockWeight::get()
 			} else {
-				(0, core::u64::MAX)
-			};
-
-
-			// Someone(miner) has mined a better deadline at this mining cycle before.
-			if best_dl <= deadline && current_block / MiningExpire == block / MiningExpire {
-
-				debug::info!("not best deadline! best_dl = {}, submit deadline = {}!", best_dl, deadline);
-
-				return Err(Error::<T>::NotBestDeadline)?;
+				0
 			}
-
-			let verify_ok = Self::verify_dl(account_id, height, sig, nonce, deadline);
-
-			if verify_ok.0 {
-				debug::info!("verify is ok!, deadline = {}", deadline);
-
-				if current_block / MiningExpire == block / MiningExpire {
-
-					DlInfo::<T>::mutate(|dl| dl.pop());
-
-				}
-
-				Self::append_dl_info(MiningInfo{
-						miner: Some(miner.clone()),
-						best_dl: deadline,
-						block: current_block,
-					});
-
-				Self::deposit_event(RawEvent::Minning(miner, deadline));
-
-			}
-
-			else {
-				debug::info!("verify failed! deadline = {:?}, target = {:?}, base_target = {:?}", verify_ok.1 / verify_ok.2, verify_ok.1, verify_ok.2);
-				return Err(Error::<T>::VerifyFaile)?;
-			}
-
-			Ok(())
 		}
 
 
@@ -316,6 +391,7 @@ decl_module! {
 						net_difficulty: 1,
 						block: 1,
 					});
+			Self::chain_generation_signature(None, 1);
 			}
 			0
 		}
@@ -336,6 +412,14 @@ decl_module! {
 
 			debug::info!("current-block = {}, last-mining-block = {}", current_block, last_mining_block);
 
+			// the winner of the cycle chains the generation signature of the next one.
+			if (current_block + 1) % MiningExpire == 0 {
+				let winner = DlInfoOf::<T>::last()
+					.filter(|dl| dl.block / MiningExpire == current_block / MiningExpire)
+					.and_then(|dl| dl.miner);
+				Self::chain_generation_signature(winner, current_block + 1);
+			}
+
 			let reward_result = Self::get_reward_amount();
 
 			let mut reward: BalanceOf<T>;
@@ -351,7 +435,7 @@ decl_module! {
 
 				if current_block / MiningExpire == last_mining_block / MiningExpire {
 
-					if let Some(miner_info) = Self::dl_info().last() {
+					if let Some(miner_info) = DlInfoOf::<T>::last() {
 						let miner: Option<T::AccountId> = miner_info.clone().miner;
 						if miner.is_some() {
 							Self::reward(miner.unwrap(), reward);
@@ -381,6 +465,192 @@ decl_module! {
 }
 
 impl<T: Trait> Module<T> {
+	fn do_mining(
+		miner: T::AccountId,
+		account_id: u64,
+		height: u64,
+		sig: [u8; 32],
+		nonce: u64,
+		deadline: u64,
+	) -> DispatchResult {
+		<ActiveMiners<T>>::mutate(|h| {
+			if h.1.insert(miner.clone()) {
+				h.0 += 1;
+			}
+		});
+
+		debug::info!(
+			"miner: {:?},  submit deadline!, height = {}, deadline = {}",
+			miner.clone(),
+			height,
+			deadline
+		);
+
+		let current_block = <system::Module<T>>::block_number().saturated_into::<u64>();
+
+		Self::check_deadline(&miner, account_id, height, sig, deadline, current_block)?;
+		Self::verify_deadline(account_id, height, sig, nonce, deadline)?;
+
+		debug::info!("verify is ok!, deadline = {}", deadline);
+
+		if current_block / MiningExpire == Self::get_last_mining_block() / MiningExpire {
+			DlInfoOf::<T>::pop();
+		}
+
+		Self::append_dl_info(MiningInfo {
+			miner: Some(miner.clone()),
+			best_dl: deadline,
+			block: current_block,
+		});
+
+		Self::deposit_event(RawEvent::Minning(miner, deadline));
+
+		Ok(())
+	}
+
+	/// check the deadline of the miner as if it is mined in the block `current_block`, it should
+	/// be better than the best deadline of the mining cycle.
+	///
+	/// These checks are cheap, the nonce is generated by `verify_deadline` only after them.
+	fn check_deadline(
+		miner: &T::AccountId,
+		account_id: u64,
+		height: u64,
+		sig: [u8; 32],
+		deadline: u64,
+		current_block: u64,
+	) -> DispatchResult {
+		ensure!(deadline <= T::MaxDeadlineValue::get(), Error::<T>::DeadlineTooLarge);
+
+		if !(current_block / MiningExpire == height / MiningExpire && current_block >= height) {
+			debug::info!(
+				"expire! ：{:?}, off chain get info block: {:?}, deadline is: {:?}",
+				height,
+				current_block,
+				deadline
+			);
+
+			return Err(Error::<T>::HeightNotInDuration)?
+		}
+
+		// the miners can't pick the height, which picks the scoop.
+		ensure!(height == Self::mining_height(), Error::<T>::WrongMiningHeight);
+
+		ensure!(sig == Self::generation_signature(), Error::<T>::WrongGenerationSignature);
+
+		let (block, best_dl) = if let Some(dl_info) = DlInfoOf::<T>::last() {
+			(dl_info.block, dl_info.best_dl)
+		} else {
+			(0, core::u64::MAX)
+		};
+
+		// Someone(miner) has mined a better deadline at this mining cycle before.
+		if best_dl <= deadline && current_block / MiningExpire == block / MiningExpire {
+			debug::info!("not best deadline! best_dl = {}, submit deadline = {}!", best_dl, deadline);
+
+			return Err(Error::<T>::NotBestDeadline)?
+		}
+
+		ensure!(<staking::Module<T>>::is_can_mining(miner.clone())?, Error::<T>::NotRegister);
+
+		let real_pid = <staking::Module<T>>::disk_of(miner).unwrap().numeric_id;
+
+		ensure!(real_pid == account_id.into(), Error::<T>::PidErr);
+
+		Ok(())
+	}
+
+	/// generate the nonce and verify the deadline of it.
+	fn verify_deadline(
+		account_id: u64,
+		height: u64,
+		sig: [u8; 32],
+		nonce: u64,
+		deadline: u64,
+	) -> DispatchResult {
+		debug::info!("starting Verify Deadline !!!");
+
+		let verify_ok = Self::verify_dl(account_id, height, sig, nonce, deadline);
+
+		if !verify_ok.0 {
+			debug::info!(
+				"verify failed! deadline = {:?}, target = {:?}, base_target = {:?}",
+				verify_ok.1 / verify_ok.2,
+				verify_ok.1,
+				verify_ok.2
+			);
+			return Err(Error::<T>::VerifyFaile)?
+		}
+
+		Ok(())
+	}
+
+	/// the cheap checks of the unsigned deadline if it is mined in the block `current_block`, see
+	/// `check_deadline`.
+	fn check_unsigned_deadline(
+		account_id: u64,
+		height: u64,
+		sig: [u8; 32],
+		deadline: u64,
+		current_block: u64,
+	) -> Result<(), TransactionValidityError> {
+		let miner = <staking::Module<T>>::accouont_id_of_pid(u128::from(account_id))
+			.ok_or(InvalidTransaction::BadProof)?;
+
+		Self::check_deadline(&miner, account_id, height, sig, deadline, current_block).map_err(|e| {
+			if e == DispatchError::from(Error::<T>::HeightNotInDuration) ||
+				e == DispatchError::from(Error::<T>::NotBestDeadline)
+			{
+				InvalidTransaction::Stale.into()
+			} else {
+				InvalidTransaction::BadProof.into()
+			}
+		})
+	}
+
+	/// the validity of the unsigned deadline if it is mined in the block `current_block`.
+	///
+	/// All the deadlines of a mining cycle provide the same tag, so the pool keeps the one with
+	/// the highest priority, which is the lowest deadline.
+	fn validate_deadline(
+		account_id: u64,
+		height: u64,
+		sig: [u8; 32],
+		nonce: u64,
+		deadline: u64,
+		current_block: u64,
+	) -> TransactionValidity {
+		Self::check_unsigned_deadline(account_id, height, sig, deadline, current_block)?;
+
+		Self::verify_deadline(account_id, height, sig, nonce, deadline)
+			.map_err(|_| InvalidTransaction::BadProof)?;
+
+		Ok(ValidTransaction {
+			priority: <T as Trait>::UnsignedPriority::get().saturating_sub(deadline),
+			requires: vec![],
+			provides: vec![(b"poc_deadline", current_block / MiningExpire).encode()],
+			longevity: MiningExpire - current_block % MiningExpire,
+			propagate: true,
+		})
+	}
+
+	/// the mining info for the deadlines of the next block: the height and the generation
+	/// signature of its mining cycle, and the base target.
+	pub fn mining_info() -> (u64, [u8; 32], u64) {
+		(Self::mining_height(), Self::generation_signature(), Self::get_last_base_target().0)
+	}
+
+	/// the generation signature of the next mining cycle, from the one of this cycle, its winner
+	/// (`None` for the treasury) and the hash of the parent block, after Burst. The cycle starts
+	/// at the block `height`.
+	fn chain_generation_signature(winner: Option<T::AccountId>, height: u64) {
+		let parent_hash = <system::Module<T>>::parent_hash();
+		let sig = (b"poc/gensig", Self::generation_signature(), winner, parent_hash)
+			.using_encoded(blake2_256);
+		GenerationSignature::put(sig);
+		MiningHeight::put(height);
+	}
+
 	fn adjust_difficulty(block: u64) {
 		debug::info!("[ADJUST] difficulty on block {}", block);
 
@@ -436,12 +706,7 @@ impl<T: Trait> Module<T> {
 	}
 
 	fn get_last_mining_block() -> u64 {
-		let dl = Self::dl_info();
-		if let Some(dl) = dl.iter().last() {
-			dl.block
-		} else {
-			0
-		}
+		DlInfoOf::<T>::last().map_or(0, |dl| dl.block)
 	}
 
 	fn get_last_base_target() -> (u64, u64) {
@@ -455,22 +720,15 @@ impl<T: Trait> Module<T> {
 	}
 
 	fn get_ave_deadline() -> (u64, u64) {
-		let dl = Self::dl_info();
-		let mut iter = dl.iter().rev();
-		let mut count = 0_u64;
+		let count = <AdjustDifficultyDuration>::get() / MiningExpire;
 		let mut real_count = 0_u64;
 		let mut deadline = 0_u64;
 
-		while let Some(dl) = iter.next() {
-			if count == <AdjustDifficultyDuration>::get() / MiningExpire {
-				break
-			}
+		for dl in DlInfoOf::<T>::latest(count.saturated_into::<usize>()) {
 			if dl.miner.is_some() {
 				real_count += 1;
 				deadline += dl.best_dl;
 			}
-
-			count += 1;
 		}
 
 		if real_count == 0 {
@@ -487,6 +745,29 @@ impl<T: Trait> Module<T> {
 		nonce: u64,
 		deadline: u64,
 	) -> (bool, u64, u64) {
+		let target = Self::calculate_target(account_id, height, sig, nonce);
+		debug::info!("target: {:?}", target);
+		let base_target = Self::get_current_base_target();
+		let deadline_ = target / base_target;
+		debug::info!("deadline: {:?}", deadline_);
+		(deadline == target / base_target, target, base_target)
+	}
+
+	/// the target of the nonce, computed natively by the host function of the node.
+	#[cfg(not(feature = "wasm-poc"))]
+	fn calculate_target(account_id: u64, height: u64, sig: [u8; 32], nonce: u64) -> u64 {
+		ipse_io::poc::calculate_target(account_id, height, sig, nonce)
+	}
+
+	/// the target of the nonce, computed in Wasm for the nodes without the poc host functions.
+	#[cfg(feature = "wasm-poc")]
+	fn calculate_target(account_id: u64, height: u64, sig: [u8; 32], nonce: u64) -> u64 {
+		Self::calculate_target_in_wasm(account_id, height, sig, nonce)
+	}
+
+	/// the target of the nonce computed in the runtime, it generates the whole nonce (256 KiB).
+	/// It gives the same target as `ipse_io::poc::calculate_target`.
+	pub fn calculate_target_in_wasm(account_id: u64, height: u64, sig: [u8; 32], nonce: u64) -> u64 {
 		let scoop_data = calculate_scoop(height, &sig) as u64;
 		debug::info!("scoop_data: {:?}", scoop_data);
 		debug::info!("sig: {:?}", sig);
@@ -498,11 +779,7 @@ impl<T: Trait> Module<T> {
 		let mirror_scoop_data = Self::gen_mirror_scoop_data(scoop_data, cache);
 
 		let (target, _) = find_best_deadline_rust(mirror_scoop_data.as_ref(), 1, &sig);
-		debug::info!("target: {:?}", target);
-		let base_target = Self::get_current_base_target();
-		let deadline_ = target / base_target;
-		debug::info!("deadline: {:?}", deadline_);
-		(deadline == target / base_target, target, base_target)
+		target
 	}
 
 	fn gen_mirror_scoop_data(scoop_data: u64, cache: Vec<u8>) -> Vec<u8> {
@@ -569,10 +846,7 @@ impl<T: Trait> Module<T> {
 		let disk = machine_info.clone().plot_size;
 		let update_time = machine_info.clone().update_time;
 
-		let mut miner_mining_num = match <History<T>>::get(&miner) {
-			Some(h) => h.total_num + 1u64,
-			None => 1u64,
-		};
+		let mut miner_mining_num = Self::mining_num(&miner) + 1u64;
 
 		let now = <staking::Module<T>>::now();
 
@@ -650,26 +924,8 @@ impl<T: Trait> Module<T> {
 			Self::reward_treasury(Percent::from_percent(90) * all_reward);
 		}
 
-		let history_opt = <History<T>>::get(&miner);
-
-		if history_opt.is_some() {
-			let mut his = history_opt.unwrap();
-			his.total_num = miner_mining_num;
-			his.history.push((now, reward));
-
-			if his.history.len() >= 300 {
-				let mut old_history = his.history.clone();
-				let new_history = old_history.split_off(1);
-				his.history = new_history;
-			}
-			<History<T>>::insert(miner.clone(), his);
-		} else {
-			let history = vec![(now, reward)];
-			<History<T>>::insert(
-				miner.clone(),
-				MiningHistory { total_num: miner_mining_num, history },
-			);
-		}
+		<MiningNums<T>>::insert(&miner, miner_mining_num);
+		HistoryOf::<T>::append(&miner, (now, reward), T::HistoryLen::get());
 
 		Ok(())
 	}
@@ -769,31 +1025,68 @@ impl<T: Trait> Module<T> {
 		amount: BalanceOf<T>,
 		block_num: T::BlockNumber,
 	) {
-		let mut reward_history = <UserRewardHistory<T>>::get(account_id.clone());
+		UserRewardHistoryOf::<T>::append(
+			&account_id,
+			(block_num, amount),
+			T::UserRewardHistoryLen::get(),
+		);
+	}
 
-		reward_history.push((block_num, amount));
+	fn append_dl_info(dl_info: MiningInfo<T::AccountId>) {
+		DlInfoOf::<T>::append(dl_info, T::DlInfoLen::get());
+	}
 
-		if reward_history.len() >= 300 {
-			let mut old_history = reward_history.clone();
-			let new_history = old_history.split_off(1);
-			<UserRewardHistory<T>>::insert(account_id, new_history);
-		} else {
-			<UserRewardHistory<T>>::insert(account_id, reward_history);
+	/// the latest deadlines of the mining, from the oldest.
+	pub fn dl_info() -> Vec<MiningInfo<T::AccountId>> {
+		DlInfoOf::<T>::items()
+	}
+
+	/// the latest rewards of the miner, `None` if it never mines a block.
+	pub fn history(miner: &T::AccountId) -> Option<MiningHistory<BalanceOf<T>, T::BlockNumber>> {
+		let total_num = Self::mining_num(miner);
+		if total_num == 0 {
+			return None
 		}
+		Some(MiningHistory { total_num, history: HistoryOf::<T>::items(miner) })
 	}
 
-	fn append_dl_info(dl_info: MiningInfo<T::AccountId>) {
-		let mut old_dl_info_vec = <DlInfo<T>>::get();
-		let len = old_dl_info_vec.len();
+	/// the latest rewards of the user, from the oldest.
+	pub fn user_reward_history(account_id: &T::AccountId) -> Vec<(T::BlockNumber, BalanceOf<T>)> {
+		UserRewardHistoryOf::<T>::items(account_id)
+	}
 
-		old_dl_info_vec.push(dl_info);
+	/// Move `DlInfo`, `History` and `UserRewardHistory` into the ring buffers, the items over the
+	/// caps are dropped.
+	fn migrate_to_ring_buffers() {
+		let dl_info = take_storage_value::<Vec<MiningInfo<T::AccountId>>>(b"PoC", b"DlInfo", &[])
+			.unwrap_or_default();
+		for dl in dl_info {
+			DlInfoOf::<T>::append(dl, T::DlInfoLen::get());
+		}
 
-		if len >= 2000 {
-			let new_dl_info = old_dl_info_vec.split_off(len - 2000);
-			old_dl_info_vec = new_dl_info;
+		let histories = StorageKeyIterator::<
+			T::AccountId,
+			MiningHistory<BalanceOf<T>, T::BlockNumber>,
+			Twox64Concat,
+		>::new(b"PoC", b"History");
+		for (miner, history) in histories.drain() {
+			<MiningNums<T>>::insert(&miner, history.total_num);
+			for reward in history.history {
+				HistoryOf::<T>::append(&miner, reward, T::HistoryLen::get());
+			}
 		}
 
-		<DlInfo<T>>::put(old_dl_info_vec);
+		let histories = StorageKeyIterator::<
+			T::AccountId,
+			Vec<(T::BlockNumber, BalanceOf<T>)>,
+			Twox64Concat,
+		>::new(b"PoC", b"UserRewardHistory");
+		for (account_id, history) in histories.drain() {
+			for reward in history {
+				let cap = T::UserRewardHistoryLen::get();
+				UserRewardHistoryOf::<T>::append(&account_id, reward, cap);
+			}
+		}
 	}
 
 	fn append_target_info(difficulty: Difficulty) {
@@ -811,7 +1104,8 @@ impl<T: Trait> Module<T> {
 
 impl<T: Trait> PocHandler<T::AccountId> for Module<T> {
 	fn remove_history(miner: T::AccountId) {
-		<History<T>>::remove(miner);
+		<MiningNums<T>>::remove(&miner);
+		HistoryOf::<T>::clear(&miner);
 	}
 }
 
@@ -842,5 +1136,45 @@ decl_error! {
 		DifficultyIsZero,
 		/// the difficulty up max value.
 		DifficultyIsTooLarge,
+		/// the generation signature is not the one of the mining cycle.
+		WrongGenerationSignature,
+		/// the height is not the first block of the mining cycle.
+		WrongMiningHeight,
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// The api to query the mining cycle of the poc.
+	pub trait PocApi {
+		/// The height and the generation signature of the mining cycle of the next block, and the
+		/// base target, its deadlines are mined with them.
+		fn mining_info() -> (u64, [u8; 32], u64);
+	}
+}
+
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+		match call {
+			// the deadline is mined in the next block.
+			Call::submit_deadline(account_id, height, sig, nonce, deadline) => {
+				let next_block = <system::Module<T>>::block_number().saturated_into::<u64>() + 1;
+				Self::validate_deadline(*account_id, *height, *sig, *nonce, *deadline, next_block)
+			},
+			_ => InvalidTransaction::Call.into(),
+		}
+	}
+
+	// the deadline is verified by the call, the nonce is not generated twice.
+	fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
+		match call {
+			// the deadline is mined in the current block.
+			Call::submit_deadline(account_id, height, sig, _, deadline) => {
+				let current_block = <system::Module<T>>::block_number().saturated_into::<u64>();
+				Self::check_unsigned_deadline(*account_id, *height, *sig, *deadline, current_block)
+			},
+			_ => Err(InvalidTransaction::Call.into()),
+		}
 	}
 }
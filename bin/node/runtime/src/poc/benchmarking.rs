@@ -0,0 +1,112 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks of the poc module.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use frame_benchmarking::{benchmarks, whitelisted_caller};
+use frame_system::RawOrigin;
+
+const NUMERIC_ID: u64 = 0x1d5d_0a6e_43b3_2c10;
+
+/// register the miner in the staking module with the plot id `NUMERIC_ID`.
+fn register_miner<T: Trait>(miner: &T::AccountId) {
+	staking::DiskOf::<T>::insert(miner, staking::MachineInfo {
+		plot_size: GIB,
+		numeric_id: NUMERIC_ID as u128,
+		reward_dest: miner.clone(),
+		..Default::default()
+	});
+	staking::StakingInfoOf::<T>::insert(miner, staking::StakingInfo {
+		miner: miner.clone(),
+		miner_proportion: Percent::from_percent(20),
+		..Default::default()
+	});
+	AccountIdOfPid::<T>::insert(NUMERIC_ID as u128, miner.clone());
+}
+
+/// register the miner and give the deadline of a nonce that is better than the one submitted
+/// before in the same mining cycle: `(height, sig, nonce, deadline)`.
+fn mining_setup<T: Trait>(miner: &T::AccountId) -> (u64, [u8; 32], u64, u64) {
+	register_miner::<T>(miner);
+
+	// the first block of a mining cycle.
+	let height = MiningExpire;
+	frame_system::Module::<T>::set_block_number(height.saturated_into());
+	MiningHeight::put(height);
+
+	// any deadline is valid at the lowest difficulty.
+	Module::<T>::append_target_info(Difficulty {
+		base_target: u64::max_value(),
+		net_difficulty: 1,
+		block: 0,
+	});
+	Module::<T>::append_dl_info(MiningInfo { miner: None, best_dl: u64::max_value(), block: height });
+
+	let sig = Module::<T>::generation_signature();
+	let nonce = 42;
+	let (_, target, base_target) = Module::<T>::verify_dl(NUMERIC_ID, height, sig, nonce, 0);
+	(height, sig, nonce, target / base_target)
+}
+
+benchmarks! {
+	_ { }
+
+	set_difficulty {
+		let difficulty = T::GENESIS_BASE_TARGET::get();
+	}: _(RawOrigin::Root, difficulty)
+	verify {
+		assert_eq!(Module::<T>::get_current_base_target(), 1);
+	}
+
+	set_adjust_difficulty_duration {
+	}: _(RawOrigin::Root, 100)
+	verify {
+		assert_eq!(AdjustDifficultyDuration::get(), 100);
+	}
+
+	set_capacity_price {
+		let price = 20.saturated_into::<BalanceOf<T>>() * DOLLARS.saturated_into::<BalanceOf<T>>();
+	}: _(RawOrigin::Root, price)
+	verify {
+		assert_eq!(CapacityPrice::<T>::get(), price);
+	}
+
+	set_capacity_of_per_difficulty {
+	}: _(RawOrigin::Root, 10)
+	verify {
+		assert_eq!(CapacityOfPerDifficulty::get(), 10);
+	}
+
+	// the nonce is generated to verify the deadline.
+	mining {
+		let miner: T::AccountId = whitelisted_caller();
+		let (height, sig, nonce, deadline) = mining_setup::<T>(&miner);
+	}: _(RawOrigin::Signed(miner.clone()), NUMERIC_ID, height, sig, nonce, deadline)
+	verify {
+		assert_eq!(DlInfoOf::<T>::last().and_then(|dl| dl.miner), Some(miner));
+	}
+
+	// the same as `mining`, the miner is found by the plot id.
+	submit_deadline {
+		let miner: T::AccountId = whitelisted_caller();
+		let (height, sig, nonce, deadline) = mining_setup::<T>(&miner);
+	}: _(RawOrigin::None, NUMERIC_ID, height, sig, nonce, deadline)
+	verify {
+		assert_eq!(DlInfoOf::<T>::last().and_then(|dl| dl.miner), Some(miner));
+	}
+}
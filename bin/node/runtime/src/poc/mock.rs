@@ -0,0 +1,383 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test utilities of the poc module.
+
+use super::{Module, Trait};
+use crate::poc_staking;
+use frame_support::{
+	impl_outer_dispatch, impl_outer_origin, parameter_types,
+	traits::{Contains, ContainsLengthBound, KeyOwnerProofSystem, OnFinalize, OnInitialize},
+	weights::Weight,
+};
+use pallet_staking::EraIndex;
+use sp_consensus_babe::AuthorityId;
+use sp_core::{crypto::KeyTypeId, H256};
+use sp_runtime::{
+	curve::PiecewiseLinear,
+	impl_opaque_keys,
+	testing::{Header, TestXt},
+	traits::{BlakeTwo256, Convert, IdentityLookup, OpaqueKeys, SaturatedConversion},
+	ModuleId, Perbill, Percent, Permill,
+};
+use sp_staking::SessionIndex;
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		staking::Staking,
+	}
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Call = Call;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type MaximumBlockLength = MaximumBlockLength;
+	type Version = ();
+	type PalletInfo = ();
+	type AccountData = pallet_balances::AccountData<u128>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Test
+where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = TestXt<Call, ()>;
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u128 = 1;
+}
+
+impl pallet_balances::Trait for Test {
+	type MaxLocks = ();
+	type Balance = u128;
+	type DustRemoval = ();
+	type Event = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const MinimumPeriod: u64 = 1;
+}
+
+impl pallet_timestamp::Trait for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const EpochDuration: u64 = 10;
+	pub const ExpectedBlockTime: u64 = 1;
+}
+
+impl pallet_babe::Trait for Test {
+	type EpochDuration = EpochDuration;
+	type ExpectedBlockTime = ExpectedBlockTime;
+	type EpochChangeTrigger = pallet_babe::ExternalTrigger;
+	type KeyOwnerProofSystem = ();
+	type KeyOwnerProof =
+		<Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(KeyTypeId, AuthorityId)>>::Proof;
+	type KeyOwnerIdentification = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		AuthorityId,
+	)>>::IdentificationTuple;
+	type HandleEquivocation = ();
+	type WeightInfo = ();
+}
+
+impl_opaque_keys! {
+	pub struct MockSessionKeys {
+		pub babe: Babe,
+	}
+}
+
+parameter_types! {
+	pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(16);
+}
+
+impl pallet_session::Trait for Test {
+	type Event = ();
+	type ValidatorId = u64;
+	type ValidatorIdOf = pallet_staking::StashOf<Self>;
+	type ShouldEndSession = Babe;
+	type NextSessionRotation = Babe;
+	type SessionManager = pallet_session::historical::NoteHistoricalRoot<Self, Staking>;
+	type SessionHandler = <MockSessionKeys as OpaqueKeys>::KeyTypeIdProviders;
+	type Keys = MockSessionKeys;
+	type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
+	type WeightInfo = ();
+}
+
+impl pallet_session::historical::Trait for Test {
+	type FullIdentification = pallet_staking::Exposure<u64, u128>;
+	type FullIdentificationOf = pallet_staking::ExposureOf<Self>;
+}
+
+pallet_staking_reward_curve::build! {
+	const REWARD_CURVE: PiecewiseLinear<'static> = curve!(
+		min_inflation: 0_025_000u64,
+		max_inflation: 0_100_000,
+		ideal_stake: 0_500_000,
+		falloff: 0_050_000,
+		max_piece_count: 40,
+		test_precision: 0_005_000,
+	);
+}
+
+parameter_types! {
+	pub const SessionsPerEra: SessionIndex = 6;
+	pub const BondingDuration: EraIndex = 3;
+	pub const SlashDeferDuration: EraIndex = 0;
+	pub const RewardCurve: &'static PiecewiseLinear<'static> = &REWARD_CURVE;
+	pub const MaxNominatorRewardedPerValidator: u32 = 64;
+	pub const ElectionLookahead: u64 = 0;
+	pub const StakingUnsignedPriority: u64 = u64::max_value() / 2;
+	pub const MinBondAmount: u128 = 1;
+}
+
+pub struct CurrencyToVoteHandler;
+
+impl Convert<u128, u128> for CurrencyToVoteHandler {
+	fn convert(x: u128) -> u128 {
+		x
+	}
+}
+
+impl Convert<u128, u64> for CurrencyToVoteHandler {
+	fn convert(x: u128) -> u64 {
+		x.saturated_into()
+	}
+}
+
+impl pallet_staking::Trait for Test {
+	type RewardRemainder = ();
+	type CurrencyToVote = CurrencyToVoteHandler;
+	type Event = ();
+	type Currency = Balances;
+	type Slash = ();
+	type Reward = ();
+	type SessionsPerEra = SessionsPerEra;
+	type BondingDuration = BondingDuration;
+	type SlashDeferDuration = SlashDeferDuration;
+	type SlashCancelOrigin = frame_system::EnsureRoot<Self::AccountId>;
+	type SessionInterface = Self;
+	type UnixTime = pallet_timestamp::Module<Test>;
+	type RewardCurve = RewardCurve;
+	type MaxNominatorRewardedPerValidator = MaxNominatorRewardedPerValidator;
+	type NextNewSession = Session;
+	type ElectionLookahead = ElectionLookahead;
+	type MinBondAmount = MinBondAmount;
+	type Call = Call;
+	type UnsignedPriority = StakingUnsignedPriority;
+	type MaxIterations = ();
+	type MinSolutionScoreBump = ();
+	type OffchainSolutionWeightLimit = ();
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const ChillDuration: u64 = 10;
+	pub const StakingDeposit: u128 = 1;
+	pub const PocStakingMinAmount: u128 = 100;
+	pub const StakerMaxNumber: usize = 2;
+	pub const StakingLockExpire: u64 = 20;
+	pub const RecommendLockExpire: u64 = 20;
+	pub const RecommendMaxNumber: usize = 2;
+}
+
+impl poc_staking::Trait for Test {
+	type Event = ();
+	type ChillDuration = ChillDuration;
+	type StakingCurrency = Balances;
+	type StakingDeposit = StakingDeposit;
+	type PocStakingMinAmount = PocStakingMinAmount;
+	type StakingSlash = ();
+	type StakerMaxNumber = StakerMaxNumber;
+	type PocHandler = PoC;
+	type StakingLockExpire = StakingLockExpire;
+	type RecommendLockExpire = RecommendLockExpire;
+	type RecommendMaxNumber = RecommendMaxNumber;
+	type WeightInfo = ();
+}
+
+/// nobody can tip.
+pub struct Tippers;
+
+impl Contains<u64> for Tippers {
+	fn sorted_members() -> Vec<u64> {
+		vec![]
+	}
+}
+
+impl ContainsLengthBound for Tippers {
+	fn min_len() -> usize {
+		0
+	}
+	fn max_len() -> usize {
+		0
+	}
+}
+
+parameter_types! {
+	pub const TreasuryModuleId: ModuleId = ModuleId(*b"py/trsry");
+	pub const ProposalBond: Permill = Permill::from_percent(5);
+	pub const ProposalBondMinimum: u128 = 1;
+	pub const SpendPeriod: u64 = 2;
+	pub const Burn: Permill = Permill::from_percent(50);
+	pub const TipCountdown: u64 = 1;
+	pub const TipFindersFee: Percent = Percent::from_percent(20);
+	pub const TipReportDepositBase: u128 = 1;
+	pub const DataDepositPerByte: u128 = 1;
+	pub const BountyDepositBase: u128 = 80;
+	pub const BountyDepositPayoutDelay: u64 = 3;
+	pub const BountyUpdatePeriod: u64 = 20;
+	pub const MaximumReasonLength: u32 = 16384;
+	pub const BountyCuratorDeposit: Permill = Permill::from_percent(50);
+	pub const BountyValueMinimum: u128 = 1;
+}
+
+impl pallet_treasury::Trait for Test {
+	type ModuleId = TreasuryModuleId;
+	type Currency = Balances;
+	type ApproveOrigin = frame_system::EnsureRoot<u64>;
+	type RejectOrigin = frame_system::EnsureRoot<u64>;
+	type Tippers = Tippers;
+	type TipCountdown = TipCountdown;
+	type TipFindersFee = TipFindersFee;
+	type TipReportDepositBase = TipReportDepositBase;
+	type DataDepositPerByte = DataDepositPerByte;
+	type Event = ();
+	type OnSlash = ();
+	type ProposalBond = ProposalBond;
+	type ProposalBondMinimum = ProposalBondMinimum;
+	type SpendPeriod = SpendPeriod;
+	type Burn = Burn;
+	type BountyDepositBase = BountyDepositBase;
+	type BountyDepositPayoutDelay = BountyDepositPayoutDelay;
+	type BountyUpdatePeriod = BountyUpdatePeriod;
+	type BountyCuratorDeposit = BountyCuratorDeposit;
+	type BountyValueMinimum = BountyValueMinimum;
+	type MaximumReasonLength = MaximumReasonLength;
+	type BurnDestination = ();
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const GenesisBaseTarget: u64 = 366503875925;
+	/// `REWARD` is mined in every mining cycle of the first two years.
+	pub const TotalMiningReward: u128 = 2 * super::YEAR as u128 * REWARD;
+	pub const ProbabilityDeviationValue: Percent = Percent::from_percent(50);
+	pub const MaxDeadlineValue: u64 = 12000;
+	pub const HistoryLen: u32 = 10;
+	pub const UserRewardHistoryLen: u32 = 10;
+	pub const DlInfoLen: u32 = 100;
+	pub const PocUnsignedPriority: u64 = u64::max_value() / 2;
+}
+
+impl Trait for Test {
+	type Event = ();
+	type PocAddOrigin = ();
+	type GENESIS_BASE_TARGET = GenesisBaseTarget;
+	type TotalMiningReward = TotalMiningReward;
+	type ProbabilityDeviationValue = ProbabilityDeviationValue;
+	type MaxDeadlineValue = MaxDeadlineValue;
+	type HistoryLen = HistoryLen;
+	type UserRewardHistoryLen = UserRewardHistoryLen;
+	type DlInfoLen = DlInfoLen;
+	type UnsignedPriority = PocUnsignedPriority;
+	type WeightInfo = ();
+}
+
+pub type System = frame_system::Module<Test>;
+pub type Balances = pallet_balances::Module<Test>;
+pub type Babe = pallet_babe::Module<Test>;
+pub type Session = pallet_session::Module<Test>;
+pub type Staking = pallet_staking::Module<Test>;
+pub type PocStaking = poc_staking::Module<Test>;
+pub type Treasury = pallet_treasury::Module<Test>;
+pub type PoC = Module<Test>;
+
+/// the balance every account has at genesis.
+pub const INITIAL_BALANCE: u128 = 1_000;
+
+/// the reward of a mining cycle.
+pub const REWARD: u128 = 1_000;
+
+/// accounts `1..=5` own `INITIAL_BALANCE`, the chain is at block 1 with the genesis difficulty.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: (1..=5).map(|who| (who, INITIAL_BALANCE)).collect(),
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	super::GenesisConfig::default().assimilate_storage::<Test>(&mut t).unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		PoC::on_initialize(1);
+	});
+	ext
+}
+
+/// run the hooks of the poc staking and the poc modules until the block `n`.
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		PoC::on_finalize(System::block_number());
+		PocStaking::on_finalize(System::block_number());
+		System::set_block_number(System::block_number() + 1);
+		PocStaking::on_initialize(System::block_number());
+		PoC::on_initialize(System::block_number());
+	}
+}
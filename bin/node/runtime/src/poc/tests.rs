@@ -0,0 +1,555 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests of the poc module.
+
+use super::mock::*;
+use super::{Call, Difficulty, Error, MiningExpire, MiningInfo, MiningNums, Releases};
+use crate::poc_staking::{self as staking, IsChillTime};
+use codec::Encode;
+use frame_support::{
+	assert_err, assert_noop, assert_ok,
+	dispatch::DispatchResultWithPostInfo,
+	traits::{OnFinalize, OnRuntimeUpgrade, UnfilteredDispatchable},
+	unsigned::ValidateUnsigned,
+	StorageMap, StorageValue,
+};
+use sp_io::hashing::blake2_256;
+use sp_runtime::{
+	traits::BadOrigin,
+	transaction_validity::{InvalidTransaction, TransactionSource, TransactionValidity},
+};
+
+const NUMERIC_ID: u64 = 0x1d5d_0a6e_43b3_2c10;
+
+const SIG: [u8; 32] = [7u8; 32];
+
+const NONCE: u64 = 42;
+
+/// dispatch a call of the poc staking module.
+fn dispatch(who: u64, call: staking::Call<Test>) -> DispatchResultWithPostInfo {
+	call.dispatch_bypass_filter(Origin::signed(who))
+}
+
+/// register a miner of 1 GiB, `numeric_id` is the plot id.
+fn register(miner: u64, numeric_id: u64, reward_dest: Option<u64>) {
+	let pid = numeric_id as u128;
+	assert_ok!(dispatch(miner, staking::Call::register(1, pid, 20, reward_dest)));
+}
+
+/// any deadline is valid at the lowest difficulty, it is `0` then.
+fn lowest_difficulty() {
+	PoC::append_target_info(Difficulty {
+		base_target: u64::max_value(),
+		net_difficulty: 1,
+		block: 0,
+	});
+}
+
+/// the generation signature of the mining cycle.
+fn sig() -> [u8; 32] {
+	PoC::generation_signature()
+}
+
+/// the deadline of the nonce `NONCE` in the plot `NUMERIC_ID`.
+fn deadline_of(height: u64) -> u64 {
+	let (_, target, base_target) = PoC::verify_dl(NUMERIC_ID, height, sig(), NONCE, 0);
+	target / base_target
+}
+
+/// one GiB of the plot needs 100 staked, and the net has 1 GiB.
+fn set_staking_rules() {
+	assert_ok!(PoC::set_capacity_price(Origin::root(), 100));
+	assert_ok!(PoC::set_capacity_of_per_difficulty(Origin::root(), 1));
+}
+
+fn stake(staker: u64, miner: u64, amount: u128) {
+	IsChillTime::put(false);
+	assert_ok!(dispatch(staker, staking::Call::staking(miner, amount)));
+}
+
+/// the miner `1` submits `deadline` in every mining cycle until the block `n`.
+fn mine_until(n: u64, deadline: u64) {
+	while System::block_number() < n {
+		let now = System::block_number();
+		if now % MiningExpire == 0 {
+			PoC::append_dl_info(MiningInfo { miner: Some(1), best_dl: deadline, block: now });
+		}
+		run_to_block(now + 1);
+	}
+}
+
+/// the validity of the unsigned deadline in the pool.
+fn validate(height: u64, nonce: u64, deadline: u64) -> TransactionValidity {
+	let call = Call::submit_deadline(NUMERIC_ID, height, sig(), nonce, deadline);
+	PoC::validate_unsigned(TransactionSource::External, &call)
+}
+
+fn treasury_balance() -> u128 {
+	Balances::free_balance(Treasury::account_id())
+}
+
+#[test]
+fn genesis_difficulty_is_set_at_the_first_block() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			PoC::target_info(),
+			vec![Difficulty { base_target: GenesisBaseTarget::get(), net_difficulty: 1, block: 1 }]
+		);
+		assert_eq!(PoC::net_power(), 0);
+		run_to_block(2);
+		assert_eq!(PoC::net_power(), 5 * super::GIB);
+	});
+}
+
+#[test]
+fn runtime_upgrade_does_nothing_on_a_new_chain() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(PoC::storage_version(), Releases::V2_0_0);
+		run_to_block(3);
+		let root = sp_io::storage::root();
+
+		assert_eq!(PoC::on_runtime_upgrade(), 0);
+		assert_eq!(sp_io::storage::root(), root);
+	});
+}
+
+#[test]
+fn only_root_sets_the_difficulty() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(PoC::set_difficulty(Origin::signed(1), 2), BadOrigin);
+		assert_noop!(PoC::set_difficulty(Origin::root(), 0), Error::<Test>::DifficultyIsZero);
+		assert_noop!(
+			PoC::set_difficulty(Origin::root(), GenesisBaseTarget::get() + 1),
+			Error::<Test>::DifficultyIsTooLarge
+		);
+
+		assert_ok!(PoC::set_difficulty(Origin::root(), 2));
+		assert_eq!(PoC::get_current_base_target(), GenesisBaseTarget::get() / 2);
+	});
+}
+
+#[test]
+fn host_function_gives_the_target_of_the_wasm_path() {
+	let mut cases = vec![
+		(NUMERIC_ID, 1, SIG, NONCE),
+		(NUMERIC_ID, 2, [0u8; 32], 0),
+		(1, 1_000_000, [0xff; 32], 7),
+		(u64::max_value(), u64::max_value(), SIG, u64::max_value()),
+	];
+	// the scoops of the heights and signatures, and the nonces of the plot.
+	for i in 0..16u64 {
+		let sig = blake2_256(&i.to_le_bytes());
+		cases.push((NUMERIC_ID ^ i, i * 997, sig, NONCE + i * 4_096));
+	}
+	let difficulties = [1, 2, 3, 1_000, GenesisBaseTarget::get()];
+
+	new_test_ext().execute_with(|| {
+		for &(numeric_id, height, sig, nonce) in cases.iter() {
+			let target = ipse_io::poc::calculate_target(numeric_id, height, sig, nonce);
+			assert_eq!(target, PoC::calculate_target_in_wasm(numeric_id, height, sig, nonce));
+			// the deadline of the host function is verified at every base target.
+			for &difficulty in difficulties.iter() {
+				assert_ok!(PoC::set_difficulty(Origin::root(), difficulty));
+				let base_target = GenesisBaseTarget::get() / difficulty;
+				let deadline = target / base_target;
+				assert_eq!(
+					PoC::verify_dl(numeric_id, height, sig, nonce, deadline),
+					(true, target, base_target)
+				);
+			}
+		}
+	});
+}
+
+#[test]
+fn generation_signature_is_chained_by_the_winner_of_every_cycle() {
+	new_test_ext().execute_with(|| {
+		register(1, NUMERIC_ID, None);
+		lowest_difficulty();
+		let chain = |sig: [u8; 32], winner: Option<u64>| {
+			(b"poc/gensig", sig, winner, System::parent_hash()).using_encoded(blake2_256)
+		};
+		let genesis = chain([0u8; 32], None);
+		assert_eq!(sig(), genesis);
+
+		// the treasury wins the first cycle.
+		run_to_block(2);
+		let treasury_sig = chain(genesis, None);
+		assert_eq!(sig(), treasury_sig);
+		assert_eq!(PoC::mining_info(), (2, treasury_sig, u64::max_value()));
+
+		assert_ok!(PoC::mining(Origin::signed(1), NUMERIC_ID, 2, sig(), NONCE, deadline_of(2)));
+		run_to_block(3);
+		assert_eq!(sig(), treasury_sig);
+		run_to_block(4);
+		assert_eq!(sig(), chain(treasury_sig, Some(1)));
+
+		// the deadlines of the last cycle are mined with the old signature.
+		assert_err!(
+			PoC::mining(Origin::signed(1), NUMERIC_ID, 4, treasury_sig, NONCE, 0),
+			Error::<Test>::WrongGenerationSignature
+		);
+	});
+}
+
+#[test]
+fn mining_accepts_a_verified_deadline() {
+	new_test_ext().execute_with(|| {
+		register(1, NUMERIC_ID, None);
+		run_to_block(2);
+		lowest_difficulty();
+
+		let deadline = deadline_of(2);
+		assert_ok!(PoC::mining(Origin::signed(1), NUMERIC_ID, 2, sig(), NONCE, deadline));
+		assert_eq!(
+			PoC::dl_info().last(),
+			Some(&MiningInfo { miner: Some(1), best_dl: deadline, block: 2 })
+		);
+		assert_eq!(PoC::active_miners().0, 1);
+
+		// the height of the last block of the cycle is still valid.
+		System::set_block_number(3);
+		assert_err!(
+			PoC::mining(Origin::signed(1), NUMERIC_ID, 2, sig(), NONCE, deadline),
+			Error::<Test>::NotBestDeadline
+		);
+	});
+}
+
+#[test]
+fn mining_rejects_bad_deadlines() {
+	new_test_ext().execute_with(|| {
+		register(1, NUMERIC_ID, None);
+		run_to_block(2);
+		lowest_difficulty();
+		let deadline = deadline_of(2);
+
+		assert_err!(
+			PoC::mining(Origin::signed(2), NUMERIC_ID, 2, sig(), NONCE, deadline),
+			staking::Error::<Test>::NotRegister
+		);
+		assert_err!(
+			PoC::mining(Origin::signed(1), NUMERIC_ID + 1, 2, sig(), NONCE, deadline),
+			Error::<Test>::PidErr
+		);
+		assert_err!(
+			PoC::mining(Origin::signed(1), NUMERIC_ID, 2, sig(), NONCE, MaxDeadlineValue::get() + 1),
+			Error::<Test>::DeadlineTooLarge
+		);
+		// the height is of the last cycle or of a future block.
+		assert_err!(
+			PoC::mining(Origin::signed(1), NUMERIC_ID, 1, sig(), NONCE, deadline),
+			Error::<Test>::HeightNotInDuration
+		);
+		assert_err!(
+			PoC::mining(Origin::signed(1), NUMERIC_ID, 3, sig(), NONCE, deadline),
+			Error::<Test>::HeightNotInDuration
+		);
+		// the height is the first block of the cycle, the miner doesn't pick it.
+		System::set_block_number(3);
+		assert_err!(
+			PoC::mining(Origin::signed(1), NUMERIC_ID, 3, sig(), NONCE, deadline),
+			Error::<Test>::WrongMiningHeight
+		);
+		System::set_block_number(2);
+		assert_err!(
+			PoC::mining(Origin::signed(1), NUMERIC_ID, 2, SIG, NONCE, deadline),
+			Error::<Test>::WrongGenerationSignature
+		);
+		assert_err!(
+			PoC::mining(Origin::signed(1), NUMERIC_ID, 2, sig(), NONCE + 1, deadline + 1),
+			Error::<Test>::VerifyFaile
+		);
+		assert!(PoC::dl_info().is_empty());
+
+		assert_ok!(dispatch(1, staking::Call::stop_mining()));
+		assert_err!(
+			PoC::mining(Origin::signed(1), NUMERIC_ID, 2, sig(), NONCE, deadline),
+			staking::Error::<Test>::AlreadyStopMining
+		);
+	});
+}
+
+#[test]
+fn a_better_deadline_replaces_the_one_of_the_same_cycle() {
+	new_test_ext().execute_with(|| {
+		register(1, NUMERIC_ID, None);
+		run_to_block(2);
+		lowest_difficulty();
+		PoC::append_dl_info(MiningInfo { miner: Some(2), best_dl: 100, block: 2 });
+
+		let deadline = deadline_of(2);
+		assert_ok!(PoC::mining(Origin::signed(1), NUMERIC_ID, 2, sig(), NONCE, deadline));
+		assert_eq!(
+			PoC::dl_info(),
+			vec![MiningInfo { miner: Some(1), best_dl: deadline, block: 2 }]
+		);
+	});
+}
+
+#[test]
+fn unsigned_deadlines_are_validated_for_the_next_block() {
+	new_test_ext().execute_with(|| {
+		register(1, NUMERIC_ID, None);
+		// the pool validates against the state after the best block.
+		PoC::on_finalize(1);
+		lowest_difficulty();
+		let deadline = deadline_of(2);
+
+		// the cycle of the blocks 2 and 3 starts in the next block.
+		let valid = validate(2, NONCE, deadline).unwrap();
+		assert_eq!(valid.priority, PocUnsignedPriority::get() - deadline);
+		assert_eq!(valid.provides, vec![(b"poc_deadline", 1u64).encode()]);
+		assert_eq!(valid.longevity, 2);
+		assert!(valid.propagate);
+
+		System::set_block_number(2);
+		assert_eq!(validate(2, NONCE, deadline).unwrap().longevity, 1);
+
+		// the next block is of the next cycle.
+		System::set_block_number(3);
+		assert_eq!(validate(2, NONCE, deadline), InvalidTransaction::Stale.into());
+
+		let call = Call::set_adjust_difficulty_duration(10);
+		assert_eq!(
+			PoC::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Call.into()
+		);
+	});
+}
+
+#[test]
+fn pool_rejects_the_deadlines_not_better_than_the_best_one() {
+	new_test_ext().execute_with(|| {
+		register(1, NUMERIC_ID, None);
+		run_to_block(2);
+		lowest_difficulty();
+		let deadline = deadline_of(2);
+
+		assert_eq!(validate(2, NONCE + 1, deadline + 1), InvalidTransaction::BadProof.into());
+		let call = Call::submit_deadline(NUMERIC_ID, 2, SIG, NONCE, deadline);
+		assert_eq!(
+			PoC::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::BadProof.into()
+		);
+		let call = Call::submit_deadline(NUMERIC_ID + 1, 2, sig(), NONCE, deadline);
+		assert_eq!(
+			PoC::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::BadProof.into()
+		);
+
+		PoC::append_dl_info(MiningInfo { miner: Some(2), best_dl: deadline, block: 2 });
+		assert_eq!(validate(2, NONCE, deadline), InvalidTransaction::Stale.into());
+	});
+}
+
+#[test]
+fn submit_deadline_mines_for_the_owner_of_the_plot() {
+	new_test_ext().execute_with(|| {
+		register(1, NUMERIC_ID, None);
+		run_to_block(2);
+		lowest_difficulty();
+		let deadline = deadline_of(2);
+
+		assert_noop!(
+			PoC::submit_deadline(Origin::signed(1), NUMERIC_ID, 2, sig(), NONCE, deadline),
+			BadOrigin
+		);
+		assert_noop!(
+			PoC::submit_deadline(Origin::none(), NUMERIC_ID + 1, 2, sig(), NONCE, deadline),
+			Error::<Test>::NotRegister
+		);
+
+		// the nonce is generated once, by the call.
+		let bad = Call::submit_deadline(NUMERIC_ID, 2, sig(), NONCE + 1, deadline + 1);
+		assert_ok!(PoC::pre_dispatch(&bad));
+		assert_err!(
+			PoC::submit_deadline(Origin::none(), NUMERIC_ID, 2, sig(), NONCE + 1, deadline + 1),
+			Error::<Test>::VerifyFaile
+		);
+
+		let call = Call::submit_deadline(NUMERIC_ID, 2, sig(), NONCE, deadline);
+		assert_ok!(PoC::pre_dispatch(&call));
+		assert_ok!(PoC::submit_deadline(Origin::none(), NUMERIC_ID, 2, sig(), NONCE, deadline));
+		assert_eq!(
+			PoC::dl_info().last(),
+			Some(&MiningInfo { miner: Some(1), best_dl: deadline, block: 2 })
+		);
+
+		// the same deadline is not included again in the block.
+		assert_eq!(PoC::pre_dispatch(&call), Err(InvalidTransaction::Stale.into()));
+	});
+}
+
+#[test]
+fn the_best_deadline_of_a_cycle_wins_the_reward() {
+	new_test_ext().execute_with(|| {
+		register(1, NUMERIC_ID, None);
+		run_to_block(2);
+		lowest_difficulty();
+		assert_ok!(PoC::mining(Origin::signed(1), NUMERIC_ID, 2, sig(), NONCE, deadline_of(2)));
+
+		// the reward is given at the end of the cycle, the miner stakes nothing.
+		run_to_block(3);
+		assert_eq!(PoC::mining_num(&1), 0);
+		run_to_block(4);
+		assert_eq!(PoC::mining_num(&1), 1);
+		assert_eq!(Balances::free_balance(1), INITIAL_BALANCE + REWARD / 10);
+		assert_eq!(treasury_balance(), REWARD * 9 / 10);
+	});
+}
+
+#[test]
+fn treasury_mines_the_cycles_without_deadlines() {
+	new_test_ext().execute_with(|| {
+		run_to_block(6);
+		assert_eq!(treasury_balance(), 2 * REWARD);
+		let treasury_dl =
+			|block| MiningInfo { miner: None, best_dl: MaxDeadlineValue::get(), block };
+		assert_eq!(PoC::dl_info(), vec![treasury_dl(3), treasury_dl(5)]);
+	});
+}
+
+#[test]
+fn difficulty_follows_the_average_deadline() {
+	new_test_ext().execute_with(|| {
+		let genesis = GenesisBaseTarget::get();
+		let difficulty = |base_target: u64, block: u64| Difficulty {
+			base_target,
+			net_difficulty: genesis / base_target,
+			block,
+		};
+
+		// fast deadlines make it more difficult.
+		mine_until(51, 1000);
+		let harder = genesis * 10 / 11;
+		assert_eq!(PoC::target_info().last(), Some(&difficulty(harder, 50)));
+
+		// slow deadlines make it easier.
+		mine_until(101, 5000);
+		let easier = harder * 11 / 10;
+		assert_eq!(PoC::target_info().last(), Some(&difficulty(easier, 100)));
+
+		// the difficulty is kept if the deadlines are in the expected range.
+		mine_until(151, 2500);
+		assert_eq!(PoC::target_info().last(), Some(&difficulty(easier, 150)));
+
+		// and if only the treasury mines.
+		run_to_block(251);
+		assert_eq!(PoC::target_info().last(), Some(&difficulty(easier, 250)));
+		assert_eq!(PoC::target_info().len(), 6);
+	});
+}
+
+#[test]
+fn miner_without_enough_staking_gets_a_tenth() {
+	new_test_ext().execute_with(|| {
+		set_staking_rules();
+		register(1, NUMERIC_ID, None);
+
+		assert_ok!(PoC::reward(1, REWARD));
+		assert_eq!(Balances::free_balance(1), INITIAL_BALANCE + 100);
+		assert_eq!(treasury_balance(), 900);
+		assert_eq!(PoC::user_reward_history(&1), vec![(1, 100)]);
+		let history = PoC::history(&1).unwrap();
+		assert_eq!(history.total_num, 1);
+		assert_eq!(history.history, vec![(1, 100)]);
+	});
+}
+
+#[test]
+fn stakers_share_the_reward_pro_rata() {
+	new_test_ext().execute_with(|| {
+		set_staking_rules();
+		register(1, NUMERIC_ID, None);
+		stake(2, 1, 300);
+		stake(3, 1, 100);
+
+		// the miner takes 20%, the stakers share the rest.
+		assert_ok!(PoC::reward(1, REWARD));
+		assert_eq!(Balances::free_balance(1), INITIAL_BALANCE + 200);
+		assert_eq!(PoC::user_reward_history(&2), vec![(1, 600)]);
+		assert_eq!(Balances::free_balance(2), INITIAL_BALANCE - 301 + 600);
+		assert_eq!(PoC::user_reward_history(&3), vec![(1, 200)]);
+		assert_eq!(Balances::free_balance(3), INITIAL_BALANCE - 101 + 200);
+		assert_eq!(treasury_balance(), 0);
+	});
+}
+
+#[test]
+fn reward_dest_takes_nine_tenths_of_the_miner_reward() {
+	new_test_ext().execute_with(|| {
+		set_staking_rules();
+		register(1, NUMERIC_ID, Some(5));
+		stake(2, 1, 100);
+
+		assert_ok!(PoC::reward(1, REWARD));
+		assert_eq!(PoC::user_reward_history(&1), vec![(1, 20)]);
+		assert_eq!(PoC::user_reward_history(&5), vec![(1, 180)]);
+		assert_eq!(Balances::free_balance(5), INITIAL_BALANCE + 180);
+		assert_eq!(PoC::user_reward_history(&2), vec![(1, 800)]);
+	});
+}
+
+#[test]
+fn miner_mining_too_often_for_its_capacity_gets_a_tenth() {
+	new_test_ext().execute_with(|| {
+		set_staking_rules();
+		// the net has 4 GiB, the miner should mine a quarter of the blocks.
+		assert_ok!(PoC::set_capacity_of_per_difficulty(Origin::root(), 4));
+		register(1, NUMERIC_ID, None);
+		stake(2, 1, 100);
+
+		assert_ok!(PoC::reward(1, REWARD));
+		assert_eq!(PoC::user_reward_history(&1), vec![(1, 20)]);
+		assert_eq!(PoC::user_reward_history(&2), vec![(1, 80)]);
+		assert_eq!(treasury_balance(), 900);
+	});
+}
+
+#[test]
+fn miner_mining_too_rarely_for_its_capacity_gets_a_tenth() {
+	new_test_ext().execute_with(|| {
+		set_staking_rules();
+		register(1, NUMERIC_ID, None);
+		stake(2, 1, 100);
+
+		// the miner mines once in 100 cycles, it should mine in every cycle.
+		System::set_block_number(201);
+		assert_ok!(PoC::reward(1, REWARD));
+		assert_eq!(PoC::user_reward_history(&1), vec![(201, 20)]);
+		assert_eq!(treasury_balance(), 900);
+
+		// the probability is in the deviation range.
+		MiningNums::<Test>::insert(&1, 99u64);
+		assert_ok!(PoC::reward(1, REWARD));
+		assert_eq!(PoC::user_reward_history(&1), vec![(201, 20), (201, 200)]);
+		assert_eq!(treasury_balance(), 900);
+	});
+}
+
+#[test]
+fn updating_the_plot_size_clears_the_mining_history() {
+	new_test_ext().execute_with(|| {
+		register(1, NUMERIC_ID, None);
+		assert_ok!(PoC::reward(1, REWARD));
+		assert!(PoC::history(&1).is_some());
+
+		assert_ok!(dispatch(1, staking::Call::update_plot_size(2)));
+		assert_eq!(PoC::history(&1), None);
+		assert_eq!(PoC::mining_num(&1), 0);
+	});
+}
@@ -47,6 +47,13 @@ use sp_std::vec;
 use sp_std::vec::Vec;
 use system::ensure_signed;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
 const Staking_ID: LockIdentifier = *b"pocstake";
 
 type BalanceOf<T> =
@@ -55,6 +62,68 @@ type NegativeImbalanceOf<T> = <<T as Trait>::StakingCurrency as Currency<
 	<T as frame_system::Trait>::AccountId,
 >>::NegativeImbalance;
 
+pub trait WeightInfo {
+	fn register() -> Weight;
+	fn request_up_to_list(l: u32) -> Weight;
+	fn request_down_from_list(l: u32) -> Weight;
+	fn update_reward_dest() -> Weight;
+	fn update_numeric_id() -> Weight;
+	fn update_plot_size() -> Weight;
+	fn stop_mining() -> Weight;
+	fn restart_mining() -> Weight;
+	fn remove_staker(s: u32) -> Weight;
+	fn staking(s: u32) -> Weight;
+	fn update_staking(s: u32) -> Weight;
+	fn unlock() -> Weight;
+	fn exit_staking(s: u32) -> Weight;
+	fn update_proportion(s: u32) -> Weight;
+}
+
+impl WeightInfo for () {
+	fn register() -> Weight {
+		1_000_000_000
+	}
+	fn request_up_to_list(_l: u32) -> Weight {
+		1_000_000_000
+	}
+	fn request_down_from_list(_l: u32) -> Weight {
+		1_000_000_000
+	}
+	fn update_reward_dest() -> Weight {
+		1_000_000_000
+	}
+	fn update_numeric_id() -> Weight {
+		1_000_000_000
+	}
+	fn update_plot_size() -> Weight {
+		1_000_000_000
+	}
+	fn stop_mining() -> Weight {
+		1_000_000_000
+	}
+	fn restart_mining() -> Weight {
+		1_000_000_000
+	}
+	fn remove_staker(_s: u32) -> Weight {
+		1_000_000_000
+	}
+	fn staking(_s: u32) -> Weight {
+		1_000_000_000
+	}
+	fn update_staking(_s: u32) -> Weight {
+		1_000_000_000
+	}
+	fn unlock() -> Weight {
+		1_000_000_000
+	}
+	fn exit_staking(_s: u32) -> Weight {
+		1_000_000_000
+	}
+	fn update_proportion(_s: u32) -> Weight {
+		1_000_000_000
+	}
+}
+
 pub trait Trait:
 	system::Trait + timestamp::Trait + balances::Trait + babe::Trait + staking::Trait
 {
@@ -81,6 +150,9 @@ pub trait Trait:
 	type RecommendLockExpire: Get<Self::BlockNumber>;
 
 	type RecommendMaxNumber: Get<usize>;
+
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
 }
 
 #[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
@@ -211,7 +283,7 @@ decl_module! {
 
 
 		/// register.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::register()]
 		fn register(origin, plot_size: GIB, numeric_id: u128, miner_proportion: u32, reward_dest: Option<T::AccountId>) {
 
 			let miner_proportion = Percent::from_percent(miner_proportion as u8);
@@ -272,7 +344,7 @@ decl_module! {
 
 
 		/// request to expose in recommend list.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::request_up_to_list(T::RecommendMaxNumber::get() as u32)]
 		fn request_up_to_list(origin, amount: BalanceOf<T>) {
 
 			let miner = ensure_signed(origin)?;
@@ -287,7 +359,7 @@ decl_module! {
 
 
 		/// request to down from the recommended list
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::request_down_from_list(T::RecommendMaxNumber::get() as u32)]
 		fn request_down_from_list(origin) {
 			let miner = ensure_signed(origin)?;
 			let mut list = <RecommendList<T>>::get();
@@ -311,7 +383,7 @@ decl_module! {
 		}
 
 		/// the miner modify income address.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::update_reward_dest()]
 		fn update_reward_dest(origin, dest: T::AccountId) {
 			let miner = ensure_signed(origin)?;
 			ensure!(Self::is_register(miner.clone()), Error::<T>::NotRegister);
@@ -327,7 +399,7 @@ decl_module! {
 
 
 		/// the miner modify plot id.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::update_numeric_id()]
 		fn update_numeric_id(origin, numeric_id: u128) {
 			let miner = ensure_signed(origin)?;
 
@@ -357,7 +429,7 @@ decl_module! {
 
 
 		/// the miner modify the plot size.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::update_plot_size()]
 		fn update_plot_size(origin, plot_size: GIB) {
 
 			let miner = ensure_signed(origin)?;
@@ -398,7 +470,7 @@ decl_module! {
 
 
 		/// the miner stop the machine.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::stop_mining()]
 		fn stop_mining(origin) {
 
 			let miner = ensure_signed(origin)?;
@@ -418,7 +490,7 @@ decl_module! {
 
 
 		/// the miner restart mining.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::restart_mining()]
 		fn restart_mining(origin) {
 			let miner = ensure_signed(origin)?;
 
@@ -441,7 +513,7 @@ decl_module! {
 
 
 		/// the delete him staker.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::remove_staker(T::StakerMaxNumber::get() as u32)]
 		fn remove_staker(origin, staker: T::AccountId) {
 
 			let miner = ensure_signed(origin)?;
@@ -455,7 +527,7 @@ decl_module! {
 
 
 		/// the user stake for miners.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::staking(T::StakerMaxNumber::get() as u32)]
 		fn staking(origin, miner: T::AccountId, amount: BalanceOf<T>) {
 
 			let who = ensure_signed(origin)?;
@@ -499,7 +571,7 @@ decl_module! {
 
 
 		/// users update their staking amount.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::update_staking(T::StakerMaxNumber::get() as u32)]
 		fn update_staking(origin, miner: T::AccountId, operate: Operate , amount: BalanceOf<T>) {
 
 			let staker = ensure_signed(origin)?;
@@ -512,7 +584,7 @@ decl_module! {
 
 
 		/// unlock
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::unlock()]
 		fn unlock(origin) {
 			let staker = ensure_signed(origin)?;
 			Self::lock_sub_amount(staker.clone());
@@ -522,7 +594,7 @@ decl_module! {
 
 
 		/// the user exit staking.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::exit_staking(T::StakerMaxNumber::get() as u32)]
 		fn exit_Staking(origin, miner: T::AccountId) {
 			let staker = ensure_signed(origin)?;
 			Self::update_staking_info(miner.clone(), staker.clone(), Operate ::Sub, None, false)?;
@@ -533,7 +605,7 @@ decl_module! {
 
 
 		/// miners update their mining reward proportion.
-		#[weight = 10_000]
+		#[weight = <T as Trait>::WeightInfo::update_proportion(T::StakerMaxNumber::get() as u32)]
 		fn update_proportion(origin, proportion: Percent) {
 
 			let miner = ensure_signed(origin)?;
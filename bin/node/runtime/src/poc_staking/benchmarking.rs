@@ -0,0 +1,230 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks of the poc staking module.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_system::RawOrigin;
+
+const SEED: u32 = 0;
+
+const LOCKS: u32 = 10;
+
+fn funded_account<T: Trait>(name: &'static str, index: u32) -> T::AccountId {
+	let who = account(name, index, SEED);
+	fund::<T>(&who);
+	who
+}
+
+fn fund<T: Trait>(who: &T::AccountId) {
+	let balance = T::PocStakingMinAmount::get() * 1_000_000u32.into();
+	T::StakingCurrency::make_free_balance_be(who, balance);
+}
+
+fn register_miner<T: Trait>(miner: &T::AccountId, numeric_id: u128) -> Result<(), &'static str> {
+	fund::<T>(miner);
+	Module::<T>::register(RawOrigin::Signed(miner.clone()).into(), 100, numeric_id, 20, None)?;
+	Ok(())
+}
+
+/// `s` users stake for the miner, they are returned in the order of staking.
+fn add_stakers<T: Trait>(miner: &T::AccountId, s: u32) -> Result<Vec<T::AccountId>, &'static str> {
+	IsChillTime::put(false);
+	let mut stakers = vec![];
+	for i in 0..s {
+		let staker = funded_account::<T>("staker", i);
+		let amount = T::PocStakingMinAmount::get();
+		Module::<T>::staking(RawOrigin::Signed(staker.clone()).into(), miner.clone(), amount)?;
+		stakers.push(staker);
+	}
+	Ok(stakers)
+}
+
+/// `l` other accounts are in the recommend list, the higher amount goes first.
+fn fill_recommend_list<T: Trait>(l: u32) -> Result<(), &'static str> {
+	let mut list = vec![];
+	for i in 0..l {
+		let who = funded_account::<T>("recommended", i);
+		let amount = T::PocStakingMinAmount::get() * (l - i).into();
+		T::StakingCurrency::reserve(&who, amount)?;
+		list.push((who, amount));
+	}
+	RecommendList::<T>::put(list);
+	Ok(())
+}
+
+benchmarks! {
+	_ { }
+
+	register {
+		let caller: T::AccountId = whitelisted_caller();
+	}: _(RawOrigin::Signed(caller.clone()), 100, 1, 20, None)
+	verify {
+		assert!(Module::<T>::is_register(caller));
+	}
+
+	// the miner goes first, the last one drops from the full list.
+	request_up_to_list {
+		let l in 1 .. T::RecommendMaxNumber::get() as u32;
+		let caller: T::AccountId = whitelisted_caller();
+		register_miner::<T>(&caller, 1)?;
+		fill_recommend_list::<T>(l)?;
+		let amount = T::PocStakingMinAmount::get() * (l + 1).into();
+	}: _(RawOrigin::Signed(caller.clone()), amount)
+	verify {
+		assert_eq!(RecommendList::<T>::get()[0], (caller, amount));
+	}
+
+	// the miner is the last one of the list.
+	request_down_from_list {
+		let l in 1 .. T::RecommendMaxNumber::get() as u32;
+		let caller: T::AccountId = whitelisted_caller();
+		register_miner::<T>(&caller, 1)?;
+		fill_recommend_list::<T>(l - 1)?;
+		let amount = T::PocStakingMinAmount::get();
+		T::StakingCurrency::reserve(&caller, amount)?;
+		RecommendList::<T>::mutate(|list| list.push((caller.clone(), amount)));
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(RecommendList::<T>::get().iter().all(|(who, _)| who != &caller));
+	}
+
+	update_reward_dest {
+		let caller: T::AccountId = whitelisted_caller();
+		register_miner::<T>(&caller, 1)?;
+		let dest: T::AccountId = account("dest", 0, SEED);
+	}: _(RawOrigin::Signed(caller.clone()), dest.clone())
+	verify {
+		assert_eq!(Module::<T>::disk_of(&caller).map(|disk| disk.reward_dest), Some(dest));
+	}
+
+	update_numeric_id {
+		let caller: T::AccountId = whitelisted_caller();
+		register_miner::<T>(&caller, 1)?;
+	}: _(RawOrigin::Signed(caller.clone()), 2)
+	verify {
+		assert_eq!(Module::<T>::accouont_id_of_pid(2u128), Some(caller));
+	}
+
+	update_plot_size {
+		let caller: T::AccountId = whitelisted_caller();
+		register_miner::<T>(&caller, 1)?;
+		IsChillTime::put(true);
+	}: _(RawOrigin::Signed(caller.clone()), 200)
+	verify {
+		let plot_size = Module::<T>::disk_of(&caller).map(|disk| disk.plot_size);
+		assert_eq!(plot_size, Some(200 * 1024 * 1024 * 1024));
+	}
+
+	stop_mining {
+		let caller: T::AccountId = whitelisted_caller();
+		register_miner::<T>(&caller, 1)?;
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(!MiningMiners::<T>::get().contains(&caller));
+	}
+
+	restart_mining {
+		let caller: T::AccountId = whitelisted_caller();
+		register_miner::<T>(&caller, 1)?;
+		Module::<T>::stop_mining(RawOrigin::Signed(caller.clone()).into())?;
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(MiningMiners::<T>::get().contains(&caller));
+	}
+
+	// the staker is the last one of the miner.
+	remove_staker {
+		let s in 1 .. T::StakerMaxNumber::get() as u32;
+		let caller: T::AccountId = whitelisted_caller();
+		register_miner::<T>(&caller, 1)?;
+		let staker = add_stakers::<T>(&caller, s)?.pop().expect("s > 0");
+	}: _(RawOrigin::Signed(caller.clone()), staker.clone())
+	verify {
+		assert!(Module::<T>::staker_pos(caller, staker).is_none());
+	}
+
+	staking {
+		let s in 0 .. T::StakerMaxNumber::get() as u32 - 1;
+		let miner: T::AccountId = account("miner", 0, SEED);
+		register_miner::<T>(&miner, 1)?;
+		add_stakers::<T>(&miner, s)?;
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+		let amount = T::PocStakingMinAmount::get();
+	}: _(RawOrigin::Signed(caller.clone()), miner.clone(), amount)
+	verify {
+		assert!(Module::<T>::staker_pos(miner, caller).is_some());
+	}
+
+	// the staker is the last one of the miner, a part of the staking is locked.
+	update_staking {
+		let s in 1 .. T::StakerMaxNumber::get() as u32;
+		let miner: T::AccountId = account("miner", 0, SEED);
+		register_miner::<T>(&miner, 1)?;
+		add_stakers::<T>(&miner, s - 1)?;
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+		let amount = T::PocStakingMinAmount::get();
+		Module::<T>::staking(RawOrigin::Signed(caller.clone()).into(), miner.clone(), amount)?;
+		let sub = amount / 2u32.into();
+	}: _(RawOrigin::Signed(caller.clone()), miner, Operate::Sub, sub)
+	verify {
+		assert_eq!(Module::<T>::locks(&caller).map(|locks| locks.len()), Some(1));
+	}
+
+	unlock {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+		let now = Module::<T>::now();
+		for _ in 0..LOCKS {
+			Module::<T>::lock_add_amount(caller.clone(), T::PocStakingMinAmount::get(), now);
+		}
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert_eq!(Module::<T>::locks(&caller).map(|locks| locks.len()), Some(0));
+	}
+
+	// the staker is the last one of the miner.
+	exit_staking {
+		let s in 1 .. T::StakerMaxNumber::get() as u32;
+		let miner: T::AccountId = account("miner", 0, SEED);
+		register_miner::<T>(&miner, 1)?;
+		add_stakers::<T>(&miner, s - 1)?;
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+		let amount = T::PocStakingMinAmount::get();
+		Module::<T>::staking(RawOrigin::Signed(caller.clone()).into(), miner.clone(), amount)?;
+	}: exit_Staking(RawOrigin::Signed(caller.clone()), miner.clone())
+	verify {
+		assert!(Module::<T>::staker_pos(miner, caller).is_none());
+	}
+
+	update_proportion {
+		let s in 0 .. T::StakerMaxNumber::get() as u32;
+		let caller: T::AccountId = whitelisted_caller();
+		register_miner::<T>(&caller, 1)?;
+		add_stakers::<T>(&caller, s)?;
+		IsChillTime::put(true);
+		let proportion = Percent::from_percent(50);
+	}: _(RawOrigin::Signed(caller.clone()), proportion)
+	verify {
+		let info = Module::<T>::staking_info_of(&caller).expect("the miner is registered");
+		assert_eq!(info.miner_proportion, proportion);
+	}
+}
@@ -0,0 +1,295 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests of the poc staking module.
+
+use super::mock::*;
+use super::{DeclaredCapacity, Error, IsChillTime, MachineInfo, Operate};
+use frame_support::{assert_noop, assert_ok, traits::ReservableCurrency, StorageValue};
+use node_primitives::GIB;
+use sp_runtime::Percent;
+
+const GB: GIB = 1024 * 1024 * 1024;
+
+fn register(miner: u64, numeric_id: u128) {
+	assert_ok!(PocStaking::register(Origin::signed(miner), 1, numeric_id, 20, None));
+}
+
+/// leave the chill time, users can stake then.
+fn end_chill() {
+	run_to_block(ChillDuration::get());
+	assert!(!PocStaking::is_chill_time());
+}
+
+#[test]
+fn register_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(PocStaking::register(Origin::signed(1), 2, 10, 20, Some(5)));
+
+		assert_eq!(
+			PocStaking::disk_of(&1),
+			Some(MachineInfo {
+				plot_size: 2 * GB,
+				numeric_id: 10,
+				update_time: 1,
+				is_stop: false,
+				reward_dest: 5,
+			})
+		);
+		let info = PocStaking::staking_info_of(&1).unwrap();
+		assert_eq!(info.miner_proportion, Percent::from_percent(20));
+		assert_eq!(info.total_staking, 0);
+		assert_eq!(PocStaking::accouont_id_of_pid(10u128), Some(1));
+		assert_eq!(DeclaredCapacity::get(), 2 * GB);
+		assert!(PocStaking::miners().contains(&1));
+		assert!(PocStaking::mining_miners().contains(&1));
+	});
+}
+
+#[test]
+fn register_rejects_bad_requests() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			PocStaking::register(Origin::signed(1), 0, 10, 20, None),
+			Error::<Test>::PlotSizeIsZero
+		);
+		register(1, 10);
+		assert_noop!(
+			PocStaking::register(Origin::signed(1), 1, 11, 20, None),
+			Error::<Test>::AlreadyRegister
+		);
+		assert_noop!(
+			PocStaking::register(Origin::signed(2), 1, 10, 20, None),
+			Error::<Test>::NumericIdInUsing
+		);
+	});
+}
+
+#[test]
+fn numeric_id_moves_to_the_new_one() {
+	new_test_ext().execute_with(|| {
+		register(1, 10);
+		register(2, 20);
+		assert_noop!(
+			PocStaking::update_numeric_id(Origin::signed(1), 20),
+			Error::<Test>::NumericIdInUsing
+		);
+
+		assert_ok!(PocStaking::update_numeric_id(Origin::signed(1), 11));
+		assert_eq!(PocStaking::accouont_id_of_pid(10u128), None);
+		assert_eq!(PocStaking::accouont_id_of_pid(11u128), Some(1));
+		assert_eq!(PocStaking::disk_of(&1).unwrap().numeric_id, 11);
+	});
+}
+
+#[test]
+fn stop_and_restart_mining_update_the_capacity() {
+	new_test_ext().execute_with(|| {
+		register(1, 10);
+		assert_noop!(PocStaking::restart_mining(Origin::signed(1)), Error::<Test>::MiningNotStop);
+
+		assert_ok!(PocStaking::stop_mining(Origin::signed(1)));
+		assert_eq!(DeclaredCapacity::get(), 0);
+		assert!(!PocStaking::mining_miners().contains(&1));
+		assert_noop!(PocStaking::stop_mining(Origin::signed(1)), Error::<Test>::AlreadyStopMining);
+
+		assert_ok!(PocStaking::restart_mining(Origin::signed(1)));
+		assert_eq!(DeclaredCapacity::get(), GB);
+		assert!(PocStaking::mining_miners().contains(&1));
+	});
+}
+
+#[test]
+fn chill_time_comes_at_the_start_of_every_era() {
+	new_test_ext().execute_with(|| {
+		// an era is 6 chill durations.
+		run_to_block(9);
+		assert!(PocStaking::is_chill_time());
+		assert_eq!(PocStaking::chill_time(), (0, 10));
+
+		run_to_block(10);
+		assert!(!PocStaking::is_chill_time());
+		assert_eq!(PocStaking::chill_time(), (60, 70));
+
+		run_to_block(59);
+		assert!(!PocStaking::is_chill_time());
+
+		run_to_block(60);
+		assert!(PocStaking::is_chill_time());
+		assert_eq!(PocStaking::chill_time(), (60, 70));
+
+		run_to_block(70);
+		assert!(!PocStaking::is_chill_time());
+		assert_eq!(PocStaking::chill_time(), (120, 130));
+	});
+}
+
+#[test]
+fn miners_update_their_info_only_in_chill_time() {
+	new_test_ext().execute_with(|| {
+		register(1, 10);
+		assert!(IsChillTime::get());
+		assert_ok!(PocStaking::update_plot_size(Origin::signed(1), 3));
+		assert_eq!(DeclaredCapacity::get(), 3 * GB);
+		let proportion = Percent::from_percent(50);
+		assert_ok!(PocStaking::update_proportion(Origin::signed(1), proportion));
+		assert_eq!(PocStaking::staking_info_of(&1).unwrap().miner_proportion, proportion);
+
+		end_chill();
+		assert_noop!(PocStaking::update_plot_size(Origin::signed(1), 4), Error::<Test>::ChillTime);
+		assert_noop!(
+			PocStaking::update_proportion(Origin::signed(1), proportion),
+			Error::<Test>::NotChillTime
+		);
+	});
+}
+
+#[test]
+fn staking_reserves_the_amount_and_the_deposit() {
+	new_test_ext().execute_with(|| {
+		register(1, 10);
+		assert_noop!(PocStaking::staking(Origin::signed(2), 1, 100), Error::<Test>::ChillTime);
+
+		end_chill();
+		assert_noop!(PocStaking::staking(Origin::signed(2), 3, 100), Error::<Test>::NotRegister);
+		assert_noop!(
+			PocStaking::staking(Origin::signed(2), 1, 99),
+			Error::<Test>::StakingAmountooLow
+		);
+
+		assert_ok!(PocStaking::staking(Origin::signed(2), 1, 100));
+		assert_eq!(Balances::reserved_balance(2), 100 + StakingDeposit::get());
+		assert_eq!(PocStaking::staking_info_of(&1).unwrap().total_staking, 100);
+		assert_eq!(PocStaking::miners_of(&2), vec![1]);
+		assert_noop!(PocStaking::staking(Origin::signed(2), 1, 100), Error::<Test>::AlreadyStaking);
+
+		assert_ok!(PocStaking::staking(Origin::signed(3), 1, 200));
+		assert_eq!(PocStaking::staking_info_of(&1).unwrap().total_staking, 300);
+		assert_noop!(
+			PocStaking::staking(Origin::signed(4), 1, 100),
+			Error::<Test>::StakerNumberToMax
+		);
+	});
+}
+
+#[test]
+fn unstaking_locks_the_amount_until_it_expires() {
+	new_test_ext().execute_with(|| {
+		register(1, 10);
+		end_chill();
+		assert_ok!(PocStaking::staking(Origin::signed(2), 1, 300));
+
+		assert_ok!(PocStaking::update_staking(Origin::signed(2), 1, Operate::Sub, 100));
+		let expire = System::block_number() + StakingLockExpire::get();
+		assert_eq!(PocStaking::locks(&2), Some(vec![(expire, 100)]));
+		assert_eq!(locked(2), 100);
+		assert_eq!(Balances::reserved_balance(2), 200 + StakingDeposit::get());
+		assert_eq!(PocStaking::staking_info_of(&1).unwrap().total_staking, 200);
+
+		// the lock is kept before it expires.
+		run_to_block(expire - 1);
+		assert_ok!(PocStaking::unlock(Origin::signed(2)));
+		assert_eq!(locked(2), 100);
+		assert!(Balances::reserve(&2, INITIAL_BALANCE - 201).is_err());
+
+		run_to_block(expire);
+		assert_ok!(PocStaking::unlock(Origin::signed(2)));
+		assert_eq!(PocStaking::locks(&2), Some(vec![]));
+		assert_eq!(locked(2), 0);
+		assert_ok!(Balances::reserve(&2, INITIAL_BALANCE - 201));
+	});
+}
+
+#[test]
+fn adding_staking_reserves_more() {
+	new_test_ext().execute_with(|| {
+		register(1, 10);
+		end_chill();
+		assert_ok!(PocStaking::staking(Origin::signed(2), 1, 100));
+
+		assert_ok!(PocStaking::update_staking(Origin::signed(2), 1, Operate::Add, 50));
+		assert_eq!(Balances::reserved_balance(2), 150 + StakingDeposit::get());
+		assert_eq!(PocStaking::staking_info_of(&1).unwrap().others, vec![(2, 150, 1)]);
+		assert_noop!(
+			PocStaking::update_staking(Origin::signed(3), 1, Operate::Add, 50),
+			Error::<Test>::NotYourStaker
+		);
+	});
+}
+
+#[test]
+fn exit_staking_returns_the_deposit() {
+	new_test_ext().execute_with(|| {
+		register(1, 10);
+		end_chill();
+		assert_ok!(PocStaking::staking(Origin::signed(2), 1, 100));
+
+		assert_ok!(PocStaking::exit_Staking(Origin::signed(2), 1));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(locked(2), 100);
+		assert_eq!(Balances::free_balance(2), INITIAL_BALANCE);
+		let info = PocStaking::staking_info_of(&1).unwrap();
+		assert_eq!(info.total_staking, 0);
+		assert!(info.others.is_empty());
+		assert!(PocStaking::miners_of(&2).is_empty());
+	});
+}
+
+#[test]
+fn removed_stakers_lose_the_deposit() {
+	new_test_ext().execute_with(|| {
+		register(1, 10);
+		end_chill();
+		assert_ok!(PocStaking::staking(Origin::signed(2), 1, 100));
+
+		assert_ok!(PocStaking::remove_staker(Origin::signed(1), 2));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(locked(2), 100);
+		assert_eq!(Balances::free_balance(2), INITIAL_BALANCE - StakingDeposit::get());
+		assert!(PocStaking::miners_of(&2).is_empty());
+	});
+}
+
+#[test]
+fn recommend_list_keeps_the_highest_amounts() {
+	new_test_ext().execute_with(|| {
+		register(1, 10);
+		register(2, 20);
+		register(3, 30);
+
+		assert_ok!(PocStaking::request_up_to_list(Origin::signed(1), 100));
+		assert_ok!(PocStaking::request_up_to_list(Origin::signed(2), 300));
+		assert_eq!(PocStaking::recommend_list(), vec![(2, 300), (1, 100)]);
+
+		// the list is full, the lowest one drops and its amount is locked.
+		assert_ok!(PocStaking::request_up_to_list(Origin::signed(3), 200));
+		assert_eq!(PocStaking::recommend_list(), vec![(2, 300), (3, 200)]);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(locked(1), 100);
+		assert_noop!(
+			PocStaking::request_up_to_list(Origin::signed(1), 100),
+			Error::<Test>::AmountTooLow
+		);
+
+		assert_ok!(PocStaking::request_down_from_list(Origin::signed(2)));
+		assert_eq!(PocStaking::recommend_list(), vec![(3, 200)]);
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(locked(2), 300);
+		assert_noop!(
+			PocStaking::request_down_from_list(Origin::signed(2)),
+			Error::<Test>::NotInList
+		);
+	});
+}
@@ -0,0 +1,388 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The price of the native token in a reference currency (e.g. USD).
+//!
+//! The whitelisted oracles submit the price, either by hand or by the offchain worker which
+//! fetches it from a local HTTP endpoint and signs it with the oracle key (`pric`) in the
+//! keystore. The price is the median of the fresh submissions, a submission that deviates too
+//! much from the current price is rejected and a price that is not updated in time is stale.
+//!
+//! The price is how many of the smallest native units one reference unit is worth, the
+//! reference unit is one millionth of the reference currency (e.g. a micro dollar).
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate frame_system as system;
+
+use alt_serde::Deserialize;
+use codec::{Decode, Encode};
+use frame_support::traits::{EnsureOrigin, Get};
+use frame_support::{
+	debug, decl_error, decl_event, decl_module, decl_storage, ensure, weights::Weight, StorageMap,
+	StorageValue,
+};
+use frame_system::offchain::{AppCrypto, CreateSignedTransaction, SendSignedTransaction, Signer};
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::{
+	offchain::{http, Duration, StorageKind},
+	traits::{Saturating, Zero},
+	Permill,
+};
+use sp_std::vec::Vec;
+use system::ensure_signed;
+
+use crate::ipse_traits::PriceProvider;
+use crate::ocw_common::{de_string_to_bytes, StdResult};
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+/// The key type of the oracles, the offchain worker signs the price with it.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"pric");
+
+// the local storage key of the endpoint that the offchain worker fetches the price from
+pub const PRICE_URL_KEY: &[u8] = b"price-feed::url";
+// the endpoint when it is not set in the local storage
+pub const DEFAULT_PRICE_URL: &str = "http://localhost:8422/price";
+// how long the offchain worker waits for the endpoint (ms)
+pub const FETCH_TIMEOUT: u64 = 2_000;
+
+pub mod crypto {
+	use super::KEY_TYPE;
+	use sp_runtime::{
+		app_crypto::{app_crypto, sr25519},
+		MultiSignature, MultiSigner,
+	};
+	app_crypto!(sr25519, KEY_TYPE);
+
+	/// The oracle key used by the offchain worker.
+	pub struct OracleId;
+	impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for OracleId {
+		type RuntimeAppPublic = Public;
+		type GenericSignature = sp_core::sr25519::Signature;
+		type GenericPublic = sp_core::sr25519::Public;
+	}
+}
+
+/// The native units that one reference unit is worth.
+pub type Price = u128;
+
+/// `o` is the number of oracles, every call walks through them.
+pub trait WeightInfo {
+	fn add_oracle(o: u32) -> Weight;
+	fn remove_oracle(o: u32) -> Weight;
+	fn submit_price(o: u32) -> Weight;
+}
+
+impl WeightInfo for () {
+	fn add_oracle(_o: u32) -> Weight {
+		1_000_000_000
+	}
+	fn remove_oracle(_o: u32) -> Weight {
+		1_000_000_000
+	}
+	fn submit_price(_o: u32) -> Weight {
+		1_000_000_000
+	}
+}
+
+pub trait Trait: CreateSignedTransaction<Call<Self>> {
+	/// default event
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The key that the offchain worker signs the price with.
+	type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+
+	/// The origin that adds and removes the oracles.
+	type OracleOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The max number of oracles.
+	type MaxOracles: Get<u32>;
+
+	/// How many blocks a price or a submission stays fresh.
+	type MaxPriceAge: Get<Self::BlockNumber>;
+
+	/// How much a submission may deviate from the current price.
+	type MaxDeviation: Get<Permill>;
+
+	/// How many blocks between two fetches of the offchain worker, 0 fetches at every block.
+	type FetchInterval: Get<Self::BlockNumber>;
+
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
+}
+
+/// The aggregated price.
+#[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PricePoint<BlockNumber> {
+	pub price: Price,
+	// the block when it is updated
+	pub updated: BlockNumber,
+}
+
+/// What the endpoint returns, e.g. `{"price": "10000000000"}`.
+#[serde(crate = "alt_serde")]
+#[derive(Deserialize)]
+struct PriceResponse {
+	// a decimal string, the json numbers can't hold u128
+	#[serde(deserialize_with = "de_string_to_bytes")]
+	price: Vec<u8>,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as PriceFeed {
+		/// the accounts that may submit the price.
+		pub Oracles get(fn oracles): Vec<T::AccountId>;
+
+		/// the latest submission of every oracle, (price, block).
+		pub Submissions get(fn submission): map hasher(twox_64_concat) T::AccountId => Option<(Price, T::BlockNumber)>;
+
+		/// the median of the fresh submissions.
+		pub CurrentPrice get(fn current_price): Option<PricePoint<T::BlockNumber>>;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// the max number of oracles.
+		const MaxOracles: u32 = T::MaxOracles::get();
+
+		/// how many blocks a price stays fresh.
+		const MaxPriceAge: T::BlockNumber = T::MaxPriceAge::get();
+
+		/// how much a submission may deviate from the current price.
+		const MaxDeviation: Permill = T::MaxDeviation::get();
+
+		/// add an oracle.
+		#[weight = <T as Trait>::WeightInfo::add_oracle(T::MaxOracles::get())]
+		fn add_oracle(origin, oracle: T::AccountId) {
+			T::OracleOrigin::ensure_origin(origin)?;
+
+			Oracles::<T>::try_mutate(|oracles| {
+				ensure!(!oracles.contains(&oracle), Error::<T>::AlreadyOracle);
+				ensure!(oracles.len() < T::MaxOracles::get() as usize, Error::<T>::TooManyOracles);
+				oracles.push(oracle.clone());
+				Ok::<_, Error<T>>(())
+			})?;
+			Self::deposit_event(RawEvent::AddedOracle(oracle));
+		}
+
+		/// remove an oracle, the price is the median of the others' fresh submissions then.
+		#[weight = <T as Trait>::WeightInfo::remove_oracle(T::MaxOracles::get())]
+		fn remove_oracle(origin, oracle: T::AccountId) {
+			T::OracleOrigin::ensure_origin(origin)?;
+
+			Oracles::<T>::try_mutate(|oracles| {
+				let pos = oracles.iter().position(|o| o == &oracle).ok_or(Error::<T>::NotOracle)?;
+				oracles.remove(pos);
+				Ok::<_, Error<T>>(())
+			})?;
+			Submissions::<T>::remove(&oracle);
+			Self::deposit_event(RawEvent::RemovedOracle(oracle));
+
+			Self::update_price(<system::Module<T>>::block_number());
+		}
+
+		/// the oracle submits the price.
+		#[weight = <T as Trait>::WeightInfo::submit_price(T::MaxOracles::get())]
+		fn submit_price(origin, price: Price) {
+			let oracle = ensure_signed(origin)?;
+
+			ensure!(Self::oracles().contains(&oracle), Error::<T>::NotOracle);
+			ensure!(!price.is_zero(), Error::<T>::ZeroPrice);
+
+			let now = <system::Module<T>>::block_number();
+			// a stale price can't hold the new ones back.
+			if let Some(current) = Self::fresh_price(now) {
+				ensure!(
+					within_deviation(current, price, T::MaxDeviation::get()),
+					Error::<T>::TooMuchDeviation
+				);
+			}
+
+			Submissions::<T>::insert(&oracle, (price, now));
+			Self::deposit_event(RawEvent::SubmittedPrice(oracle, price));
+
+			Self::update_price(now);
+		}
+
+		fn offchain_worker(n: T::BlockNumber) {
+			let interval = T::FetchInterval::get();
+			if !interval.is_zero() && !(n % interval).is_zero() {
+				return
+			}
+			if let Err(e) = Self::fetch_and_submit() {
+				debug::info!("failed to feed the price: {}", e);
+			}
+		}
+	}
+}
+
+decl_event! {
+	pub enum Event<T>
+	where
+		AccountId = <T as system::Trait>::AccountId,
+	{
+		AddedOracle(AccountId),
+		RemovedOracle(AccountId),
+		SubmittedPrice(AccountId, Price),
+		UpdatedPrice(Price),
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		AlreadyOracle,
+		NotOracle,
+		TooManyOracles,
+		ZeroPrice,
+		/// the submission deviates too much from the current price
+		TooMuchDeviation,
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The current price if it is not stale.
+	pub fn fresh_price(now: T::BlockNumber) -> Option<Price> {
+		Self::current_price()
+			.filter(|p| now.saturating_sub(p.updated) <= T::MaxPriceAge::get())
+			.map(|p| p.price)
+	}
+
+	/// Set the price to the median of the fresh submissions, it is removed if there are none.
+	fn update_price(now: T::BlockNumber) {
+		match Self::median_of_fresh(now) {
+			Some(median) => {
+				CurrentPrice::<T>::put(PricePoint { price: median, updated: now });
+				Self::deposit_event(RawEvent::UpdatedPrice(median));
+			},
+			None => CurrentPrice::<T>::kill(),
+		}
+	}
+
+	/// The median of the fresh submissions of the oracles.
+	fn median_of_fresh(now: T::BlockNumber) -> Option<Price> {
+		let mut prices: Vec<Price> = Self::oracles()
+			.iter()
+			.filter_map(Self::submission)
+			.filter(|(_, at)| now.saturating_sub(*at) <= T::MaxPriceAge::get())
+			.map(|(price, _)| price)
+			.collect();
+		median(&mut prices)
+	}
+
+	/// Fetch the price from the local endpoint, and submit it with the oracle key.
+	fn fetch_and_submit() -> StdResult<()> {
+		let signer = Signer::<T, T::AuthorityId>::any_account();
+		if !signer.can_sign() {
+			// this node is not an oracle.
+			return Ok(())
+		}
+		let price = Self::fetch_price()?;
+		match signer.send_signed_transaction(|_| Call::submit_price(price)) {
+			Some((_, Ok(()))) => Ok(()),
+			_ => Err("failed to submit the price"),
+		}
+	}
+
+	fn fetch_price() -> StdResult<Price> {
+		// set by `offchain_localStorageSet`, so it is not encoded.
+		let url = sp_io::offchain::local_storage_get(StorageKind::PERSISTENT, PRICE_URL_KEY);
+		let url = match &url {
+			Some(url) => core::str::from_utf8(url).map_err(|_| "invalid price url")?,
+			None => DEFAULT_PRICE_URL,
+		};
+
+		let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(FETCH_TIMEOUT));
+		let pending = http::Request::get(url)
+			.deadline(deadline)
+			.send()
+			.map_err(|_| "failed to send the price request")?;
+		let response = pending
+			.try_wait(deadline)
+			.map_err(|_| "price request timeout")?
+			.map_err(|_| "price request failed")?;
+		if response.code != 200 {
+			return Err("unexpected status code of the price request")
+		}
+
+		let body = response.body().collect::<Vec<u8>>();
+		let response: PriceResponse =
+			serde_json::from_slice(&body).map_err(|_| "invalid price response")?;
+		core::str::from_utf8(&response.price)
+			.ok()
+			.and_then(|price| price.parse::<Price>().ok())
+			.ok_or("invalid price")
+	}
+}
+
+impl<T: Trait> PriceProvider for Module<T> {
+	fn price() -> Option<Price> {
+		Self::fresh_price(<system::Module<T>>::block_number())
+	}
+}
+
+/// Whether `price` deviates from `current` by at most `max`.
+pub fn within_deviation(current: Price, price: Price, max: Permill) -> bool {
+	let diff = if price > current { price - current } else { current - price };
+	diff <= max * current
+}
+
+/// The median of `prices`, the mean of the middle two if there is an even number of them.
+pub fn median(prices: &mut [Price]) -> Option<Price> {
+	if prices.is_empty() {
+		return None
+	}
+	prices.sort_unstable();
+	let mid = prices.len() / 2;
+	if prices.len() % 2 == 1 {
+		Some(prices[mid])
+	} else {
+		let (a, b) = (prices[mid - 1], prices[mid]);
+		Some(a / 2 + b / 2 + (a % 2 + b % 2) / 2)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn median_works() {
+		assert_eq!(median(&mut []), None);
+		assert_eq!(median(&mut [7]), Some(7));
+		assert_eq!(median(&mut [9, 1, 5]), Some(5));
+		assert_eq!(median(&mut [10, 2, 4, 100]), Some(7));
+		assert_eq!(median(&mut [Price::MAX, Price::MAX]), Some(Price::MAX));
+		assert_eq!(median(&mut [3, 4]), Some(3));
+	}
+
+	#[test]
+	fn deviation_works() {
+		let max = Permill::from_percent(10);
+		assert!(within_deviation(1_000, 1_000, max));
+		assert!(within_deviation(1_000, 1_100, max));
+		assert!(within_deviation(1_000, 900, max));
+		assert!(!within_deviation(1_000, 1_101, max));
+		assert!(!within_deviation(1_000, 899, max));
+		assert!(within_deviation(Price::MAX, Price::MAX - 1, max));
+	}
+}
@@ -0,0 +1,76 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks of the price feed module.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_system::RawOrigin;
+
+const SEED: u32 = 0;
+const PRICE: Price = 1_000_000;
+
+/// `o` oracles, every one of them has a fresh submission.
+fn oracles<T: Trait>(o: u32) -> Vec<T::AccountId> {
+	let now = <system::Module<T>>::block_number();
+	let oracles = (0..o).map(|i| account("oracle", i, SEED)).collect::<Vec<T::AccountId>>();
+	for oracle in oracles.iter() {
+		Submissions::<T>::insert(oracle, (PRICE, now));
+	}
+	Oracles::<T>::put(&oracles);
+	oracles
+}
+
+benchmarks! {
+	_ { }
+
+	add_oracle {
+		let o in 0 .. T::MaxOracles::get() - 1;
+		oracles::<T>(o);
+		let oracle: T::AccountId = account("oracle", o, SEED);
+	}: _(RawOrigin::Root, oracle.clone())
+	verify {
+		assert!(Module::<T>::oracles().contains(&oracle));
+	}
+
+	// the last oracle is the slowest to find, and the median of the others' fresh submissions is
+	// recomputed.
+	remove_oracle {
+		let o in 1 .. T::MaxOracles::get();
+		let oracle = oracles::<T>(o).pop().expect("there is at least one oracle");
+	}: _(RawOrigin::Root, oracle.clone())
+	verify {
+		assert!(!Module::<T>::oracles().contains(&oracle));
+		assert!(Module::<T>::submission(&oracle).is_none());
+		assert_eq!(Module::<T>::current_price().is_some(), o > 1);
+	}
+
+	// the price is checked against the current one, and the median of all the fresh
+	// submissions is recomputed.
+	submit_price {
+		let o in 1 .. T::MaxOracles::get();
+		let caller: T::AccountId = whitelisted_caller();
+		let mut oracles = oracles::<T>(o - 1);
+		oracles.push(caller.clone());
+		Oracles::<T>::put(&oracles);
+		let now = <system::Module<T>>::block_number();
+		CurrentPrice::<T>::put(PricePoint { price: PRICE, updated: now });
+	}: _(RawOrigin::Signed(caller.clone()), PRICE + 1)
+	verify {
+		assert_eq!(Module::<T>::submission(&caller), Some((PRICE + 1, now)));
+	}
+}
@@ -0,0 +1,448 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The search index of the content stored in ipse.
+//!
+//! The users who store the content attach the metadata to its CID, the indexers stake and
+//! publish the segments of the keyword index (keyword -> CIDs), the search portals query
+//! them through `SearchIndexApi`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate frame_system as system;
+
+use codec::{Codec, Decode, Encode};
+use frame_support::traits::{Currency, EnsureOrigin, Get, OnUnbalanced, ReservableCurrency};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage, ensure, weights::Weight,
+	IterableStorageDoubleMap, StorageDoubleMap, StorageMap,
+};
+use sp_std::vec::Vec;
+use system::ensure_signed;
+
+use crate::cid::Cid;
+use crate::ipse;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+// the max length of a keyword
+pub const MAX_KEYWORD_LEN: usize = 64;
+// the max length of the title, mime type and language
+pub const MAX_FIELD_LEN: usize = 256;
+
+pub type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+
+pub type NegativeImbalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+
+pub trait WeightInfo {
+	fn set_metadata(k: u32) -> Weight;
+	fn clear_metadata(k: u32) -> Weight;
+	fn register_indexer() -> Weight;
+	fn unregister_indexer(k: u32) -> Weight;
+	fn publish_segment(c: u32) -> Weight;
+	fn remove_segment() -> Weight;
+	fn slash_indexer(k: u32) -> Weight;
+}
+
+impl WeightInfo for () {
+	fn set_metadata(_k: u32) -> Weight {
+		1_000_000_000
+	}
+	fn clear_metadata(_k: u32) -> Weight {
+		1_000_000_000
+	}
+	fn register_indexer() -> Weight {
+		1_000_000_000
+	}
+	fn unregister_indexer(_k: u32) -> Weight {
+		1_000_000_000
+	}
+	fn publish_segment(_c: u32) -> Weight {
+		1_000_000_000
+	}
+	fn remove_segment() -> Weight {
+		1_000_000_000
+	}
+	fn slash_indexer(_k: u32) -> Weight {
+		1_000_000_000
+	}
+}
+
+pub trait Trait: system::Trait + ipse::Trait {
+	/// default event
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// How much an indexer stakes.
+	type IndexerStake: Get<BalanceOf<Self>>;
+
+	/// The max number of keywords of the metadata.
+	type MaxKeywords: Get<u32>;
+
+	/// The max number of CIDs in a segment.
+	type MaxSegmentLen: Get<u32>;
+
+	/// The max number of keywords that an indexer publishes segments under.
+	type MaxKeywordsPerIndexer: Get<u32>;
+
+	/// The origin that removes the indexers who publish bad segments.
+	type SlashOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Handler for the slashed staking of indexers.
+	type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
+}
+
+/// The metadata of the content, attached by the user who stores it.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ContentMeta<AccountId, BlockNumber> {
+	pub owner: AccountId,
+	pub title: Vec<u8>,
+	// lowercase
+	pub keywords: Vec<Vec<u8>>,
+	// e.g. `text/html`
+	pub mime: Vec<u8>,
+	// e.g. `en`
+	pub language: Vec<u8>,
+	// the block when it is updated
+	pub updated: BlockNumber,
+}
+
+/// The CIDs that an indexer publishes under a keyword.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct IndexSegment<BlockNumber> {
+	pub cids: Vec<Cid>,
+	// the block when it is published
+	pub published: BlockNumber,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as SearchIndex {
+		/// the metadata of the content.
+		pub Metadata get(fn metadata): map hasher(blake2_128_concat) Cid => Option<ContentMeta<T::AccountId, T::BlockNumber>>;
+
+		/// the content tagged with the keyword by its owner.
+		pub Tagged get(fn tagged): double_map hasher(blake2_128_concat) Vec<u8>, hasher(blake2_128_concat) Cid => ();
+
+		/// the staking of the indexers.
+		pub Indexers get(fn indexer): map hasher(twox_64_concat) T::AccountId => Option<BalanceOf<T>>;
+
+		/// the segments of the keyword index, keyword => indexer => segment.
+		pub Segments get(fn segment): double_map hasher(blake2_128_concat) Vec<u8>, hasher(twox_64_concat) T::AccountId => Option<IndexSegment<T::BlockNumber>>;
+
+		/// the keywords that the indexer publishes segments under.
+		pub IndexerKeywords get(fn indexer_keywords): map hasher(twox_64_concat) T::AccountId => Vec<Vec<u8>>;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// how much an indexer stakes.
+		const IndexerStake: BalanceOf<T> = T::IndexerStake::get();
+
+		/// the max number of keywords of the metadata.
+		const MaxKeywords: u32 = T::MaxKeywords::get();
+
+		/// the max number of CIDs in a segment.
+		const MaxSegmentLen: u32 = T::MaxSegmentLen::get();
+
+		/// the max number of keywords that an indexer publishes segments under.
+		const MaxKeywordsPerIndexer: u32 = T::MaxKeywordsPerIndexer::get();
+
+		/// the user who stores the content attaches the metadata to it, only its owner
+		/// updates it then.
+		///
+		/// the old keywords are untagged, there are `MaxKeywords` of them at most.
+		#[weight = <T as Trait>::WeightInfo::set_metadata(
+			(keywords.len() as u32).max(T::MaxKeywords::get())
+		)]
+		fn set_metadata(origin, cid: Vec<u8>, title: Vec<u8>, keywords: Vec<Vec<u8>>, mime: Vec<u8>, language: Vec<u8>) {
+			let who = ensure_signed(origin)?;
+
+			let cid = Cid::parse(&cid).map_err(|_| Error::<T>::InvalidCid)?;
+			ensure!(Self::is_stored_by(&cid, &who), Error::<T>::NotContentOwner);
+
+			ensure!(keywords.len() <= T::MaxKeywords::get() as usize, Error::<T>::TooManyKeywords);
+			ensure!(title.len() <= MAX_FIELD_LEN, Error::<T>::TooLong);
+			ensure!(mime.len() <= MAX_FIELD_LEN && language.len() <= MAX_FIELD_LEN, Error::<T>::TooLong);
+			let keywords = Self::normalize(keywords)?;
+
+			if let Some(old) = Self::metadata(&cid) {
+				ensure!(old.owner == who, Error::<T>::NotContentOwner);
+				for keyword in old.keywords.iter() {
+					Tagged::remove(keyword, &cid);
+				}
+			}
+			for keyword in keywords.iter() {
+				Tagged::insert(keyword, &cid, ());
+			}
+			Metadata::<T>::insert(&cid, ContentMeta {
+				owner: who.clone(),
+				title,
+				keywords,
+				mime,
+				language,
+				updated: <system::Module<T>>::block_number(),
+			});
+
+			Self::deposit_event(RawEvent::UpdatedMetadata(who, cid));
+		}
+
+
+		/// the owner removes the metadata.
+		#[weight = <T as Trait>::WeightInfo::clear_metadata(T::MaxKeywords::get())]
+		fn clear_metadata(origin, cid: Vec<u8>) {
+			let who = ensure_signed(origin)?;
+
+			let cid = Cid::parse(&cid).map_err(|_| Error::<T>::InvalidCid)?;
+			let meta = Self::metadata(&cid).ok_or(Error::<T>::MetadataNotFound)?;
+			ensure!(meta.owner == who, Error::<T>::NotContentOwner);
+
+			for keyword in meta.keywords.iter() {
+				Tagged::remove(keyword, &cid);
+			}
+			Metadata::<T>::remove(&cid);
+
+			Self::deposit_event(RawEvent::ClearedMetadata(who, cid));
+		}
+
+
+		/// stake to become an indexer.
+		#[weight = <T as Trait>::WeightInfo::register_indexer()]
+		fn register_indexer(origin) {
+			let who = ensure_signed(origin)?;
+
+			ensure!(!Indexers::<T>::contains_key(&who), Error::<T>::AlreadyIndexer);
+
+			let stake = T::IndexerStake::get();
+			<T as Trait>::Currency::reserve(&who, stake).map_err(|_| Error::<T>::CannotStake)?;
+			Indexers::<T>::insert(&who, stake);
+
+			Self::deposit_event(RawEvent::RegisteredIndexer(who, stake));
+		}
+
+
+		/// the indexer leaves, its segments are removed and the staking is unreserved.
+		///
+		/// there are `MaxKeywordsPerIndexer` segments at most.
+		#[weight = <T as Trait>::WeightInfo::unregister_indexer(T::MaxKeywordsPerIndexer::get())]
+		fn unregister_indexer(origin) {
+			let who = ensure_signed(origin)?;
+
+			let stake = Self::indexer(&who).ok_or(Error::<T>::IndexerNotFound)?;
+			Self::remove_indexer(&who);
+			<T as Trait>::Currency::unreserve(&who, stake);
+
+			Self::deposit_event(RawEvent::UnregisteredIndexer(who));
+		}
+
+
+		/// the indexer publishes the CIDs of the keyword, it replaces the old segment.
+		///
+		/// every CID must be stored in ipse, the indexer publishes under `MaxKeywordsPerIndexer`
+		/// keywords at most.
+		#[weight = <T as Trait>::WeightInfo::publish_segment(cids.len() as u32)]
+		fn publish_segment(origin, keyword: Vec<u8>, cids: Vec<Vec<u8>>) {
+			let who = ensure_signed(origin)?;
+
+			ensure!(Indexers::<T>::contains_key(&who), Error::<T>::IndexerNotFound);
+			ensure!(!cids.is_empty(), Error::<T>::EmptySegment);
+			ensure!(cids.len() <= T::MaxSegmentLen::get() as usize, Error::<T>::SegmentTooLong);
+
+			let keyword = Self::normalize_keyword(keyword)?;
+			let mut parsed = Vec::with_capacity(cids.len());
+			for cid in cids.iter() {
+				let cid = Cid::parse(cid).map_err(|_| Error::<T>::InvalidCid)?;
+				ensure!(!<ipse::Module<T>>::hash_orders(&cid).is_empty(), Error::<T>::ContentNotStored);
+				parsed.push(cid);
+			}
+
+			if !Segments::<T>::contains_key(&keyword, &who) {
+				IndexerKeywords::<T>::try_mutate(&who, |keywords| -> Result<(), Error<T>> {
+					ensure!(
+						keywords.len() < T::MaxKeywordsPerIndexer::get() as usize,
+						Error::<T>::TooManySegments
+					);
+					keywords.push(keyword.clone());
+					Ok(())
+				})?;
+			}
+			Segments::<T>::insert(&keyword, &who, IndexSegment {
+				cids: parsed,
+				published: <system::Module<T>>::block_number(),
+			});
+
+			Self::deposit_event(RawEvent::PublishedSegment(who, keyword));
+		}
+
+
+		/// the indexer withdraws its segment of the keyword.
+		#[weight = <T as Trait>::WeightInfo::remove_segment()]
+		fn remove_segment(origin, keyword: Vec<u8>) {
+			let who = ensure_signed(origin)?;
+
+			let keyword = Self::normalize_keyword(keyword)?;
+			ensure!(Segments::<T>::contains_key(&keyword, &who), Error::<T>::SegmentNotFound);
+			Segments::<T>::remove(&keyword, &who);
+			IndexerKeywords::<T>::mutate(&who, |keywords| keywords.retain(|k| *k != keyword));
+
+			Self::deposit_event(RawEvent::RemovedSegment(who, keyword));
+		}
+
+
+		/// remove the indexer who publishes bad segments, and slash its staking.
+		#[weight = <T as Trait>::WeightInfo::slash_indexer(T::MaxKeywordsPerIndexer::get())]
+		fn slash_indexer(origin, indexer: T::AccountId) {
+			T::SlashOrigin::ensure_origin(origin)?;
+
+			let stake = Self::indexer(&indexer).ok_or(Error::<T>::IndexerNotFound)?;
+			Self::remove_indexer(&indexer);
+			let (imbalance, _) = <T as Trait>::Currency::slash_reserved(&indexer, stake);
+			T::Slash::on_unbalanced(imbalance);
+
+			Self::deposit_event(RawEvent::SlashedIndexer(indexer, stake));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The content tagged with the keyword by the owners, none for an invalid keyword.
+	pub fn search_tagged(keyword: Vec<u8>) -> Vec<Cid> {
+		match Self::normalize_keyword(keyword) {
+			Ok(keyword) => Tagged::iter_prefix(keyword).map(|(cid, _)| cid).collect(),
+			Err(_) => Vec::new(),
+		}
+	}
+
+	/// The segments that the indexers publish under the keyword, none for an invalid keyword.
+	pub fn search_segments(keyword: Vec<u8>) -> Vec<(T::AccountId, Vec<Cid>)> {
+		match Self::normalize_keyword(keyword) {
+			Ok(keyword) => Segments::<T>::iter_prefix(keyword)
+				.map(|(indexer, segment)| (indexer, segment.cids))
+				.collect(),
+			Err(_) => Vec::new(),
+		}
+	}
+
+	/// Whether the account stores the content by a live order.
+	fn is_stored_by(cid: &Cid, who: &T::AccountId) -> bool {
+		<ipse::Module<T>>::hash_orders(cid).into_iter().any(|order_id| {
+			<ipse::Module<T>>::order(order_id)
+				.map_or(false, |order| order.user == *who && order.status.is_live())
+		})
+	}
+
+	/// Check and lowercase the keywords, the duplicates are dropped.
+	fn normalize(keywords: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>, Error<T>> {
+		let mut normalized: Vec<Vec<u8>> = Vec::with_capacity(keywords.len());
+		for keyword in keywords {
+			let keyword = Self::normalize_keyword(keyword)?;
+			if !normalized.contains(&keyword) {
+				normalized.push(keyword);
+			}
+		}
+		Ok(normalized)
+	}
+
+	fn normalize_keyword(keyword: Vec<u8>) -> Result<Vec<u8>, Error<T>> {
+		ensure!(!keyword.is_empty(), Error::<T>::EmptyKeyword);
+		ensure!(keyword.len() <= MAX_KEYWORD_LEN, Error::<T>::TooLong);
+		Ok(keyword.to_ascii_lowercase())
+	}
+
+	fn remove_indexer(indexer: &T::AccountId) {
+		for keyword in IndexerKeywords::<T>::take(indexer) {
+			Segments::<T>::remove(&keyword, indexer);
+		}
+		Indexers::<T>::remove(indexer);
+	}
+}
+
+decl_event! {
+	pub enum Event<T>
+		where
+		AccountId = <T as system::Trait>::AccountId,
+		Balance = BalanceOf<T>,
+		{
+			/// (owner, cid).
+			UpdatedMetadata(AccountId, Cid),
+			ClearedMetadata(AccountId, Cid),
+			RegisteredIndexer(AccountId, Balance),
+			UnregisteredIndexer(AccountId),
+			/// (indexer, keyword).
+			PublishedSegment(AccountId, Vec<u8>),
+			RemovedSegment(AccountId, Vec<u8>),
+			SlashedIndexer(AccountId, Balance),
+		}
+}
+
+decl_error! {
+	/// Error for the search index module.
+	pub enum Error for Module<T: Trait> {
+		/// The CID is malformed.
+		InvalidCid,
+		/// The content is not stored by the account.
+		NotContentOwner,
+		/// The content is not stored in ipse.
+		ContentNotStored,
+		/// The metadata not found.
+		MetadataNotFound,
+		TooManyKeywords,
+		EmptyKeyword,
+		/// The keyword or a field is too long.
+		TooLong,
+		/// Balance is not enough to stake.
+		CannotStake,
+		AlreadyIndexer,
+		IndexerNotFound,
+		EmptySegment,
+		SegmentTooLong,
+		SegmentNotFound,
+		/// The indexer publishes under `MaxKeywordsPerIndexer` keywords already.
+		TooManySegments,
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// The api to query the search index.
+	pub trait SearchIndexApi<AccountId, BlockNumber> where
+		AccountId: Codec,
+		BlockNumber: Codec,
+	{
+		/// The content tagged with the keyword by the owners.
+		fn search_tagged(keyword: Vec<u8>) -> Vec<Cid>;
+
+		/// The CIDs that the indexers publish under the keyword.
+		fn search_segments(keyword: Vec<u8>) -> Vec<(AccountId, Vec<Cid>)>;
+
+		/// The metadata of the content.
+		fn metadata(cid: Cid) -> Option<ContentMeta<AccountId, BlockNumber>>;
+	}
+}
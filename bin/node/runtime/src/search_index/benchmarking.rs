@@ -0,0 +1,208 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks of the search index module.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use crate::cid::{Multihash, IDENTITY, MAX_DIGEST_LEN, RAW};
+use crate::ipse::{DataClass, RetrievalFrequency, GB};
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_system::RawOrigin;
+use sp_runtime::traits::Bounded;
+
+const SEED: u32 = 0;
+
+/// the `i`th CID, the longest one in base58btc, whose decoding is the slowest.
+fn cid(i: u32) -> Vec<u8> {
+	let mut digest = vec![0xff; MAX_DIGEST_LEN];
+	digest[..4].copy_from_slice(&i.to_le_bytes());
+	Cid::new_v1(RAW, Multihash { code: IDENTITY, digest }).to_base58()
+}
+
+fn fund<T: Trait>(who: &T::AccountId) {
+	<T as Trait>::Currency::make_free_balance_be(who, BalanceOf::<T>::max_value() / 2u32.into());
+	<T as ipse::Trait>::StakingCurrency::make_free_balance_be(
+		who,
+		ipse::BalanceOf::<T>::max_value() / 2u32.into(),
+	);
+}
+
+/// the user stores the content of the CID `cid(0)` with a miner.
+fn store<T: Trait>(user: &T::AccountId) -> Result<(), &'static str> {
+	let miner: T::AccountId = account("miner", 0, SEED);
+	fund::<T>(&miner);
+	fund::<T>(user);
+	<ipse::Module<T>>::register_miner(
+		RawOrigin::Signed(miner.clone()).into(),
+		b"miner".to_vec(),
+		b"region".to_vec(),
+		miner.encode(),
+		vec![0; 32],
+		miner.clone(),
+		GB as u128,
+		1u32.into(),
+	)?;
+	<ipse::Module<T>>::create_order(
+		RawOrigin::Signed(user.clone()).into(),
+		vec![miner],
+		1,
+		b"label".to_vec(),
+		cid(0),
+		1024,
+		[0; 32],
+		None,
+		1,
+		DataClass::Cold,
+		RetrievalFrequency::Low,
+		1u32.into(),
+	)?;
+	Ok(())
+}
+
+/// `k` keywords of the max length, which start with `prefix`.
+fn keywords(k: u32, prefix: u8) -> Vec<Vec<u8>> {
+	(0..k)
+		.map(|i| {
+			let mut keyword = vec![prefix; MAX_KEYWORD_LEN];
+			keyword[1..5].copy_from_slice(&i.to_le_bytes());
+			keyword
+		})
+		.collect()
+}
+
+/// the caller is an indexer.
+fn indexer<T: Trait>() -> Result<T::AccountId, &'static str> {
+	let caller: T::AccountId = whitelisted_caller();
+	fund::<T>(&caller);
+	Module::<T>::register_indexer(RawOrigin::Signed(caller.clone()).into())?;
+	Ok(caller)
+}
+
+/// the indexer publishes `k` segments of `cid(0)`.
+fn publish<T: Trait>(indexer: &T::AccountId, k: u32) -> Result<(), &'static str> {
+	ipse::HashOrders::insert(Cid::parse(&cid(0)).unwrap(), vec![0]);
+	for keyword in keywords(k, b'k') {
+		Module::<T>::publish_segment(
+			RawOrigin::Signed(indexer.clone()).into(),
+			keyword,
+			vec![cid(0)],
+		)?;
+	}
+	Ok(())
+}
+
+benchmarks! {
+	_ { }
+
+	// the `k` old keywords are replaced by `k` new ones.
+	set_metadata {
+		let k in 1 .. T::MaxKeywords::get();
+		let caller: T::AccountId = whitelisted_caller();
+		store::<T>(&caller)?;
+		let origin = RawOrigin::Signed(caller.clone());
+		Module::<T>::set_metadata(
+			origin.clone().into(),
+			cid(0),
+			vec![b't'; MAX_FIELD_LEN],
+			keywords(k, b'a'),
+			b"text/html".to_vec(),
+			b"en".to_vec(),
+		)?;
+	}: _(
+		origin,
+		cid(0),
+		vec![b't'; MAX_FIELD_LEN],
+		keywords(k, b'b'),
+		b"text/html".to_vec(),
+		b"en".to_vec()
+	)
+	verify {
+		let cid = Cid::parse(&cid(0)).unwrap();
+		assert_eq!(Module::<T>::metadata(&cid).map(|m| m.keywords.len()), Some(k as usize));
+	}
+
+	clear_metadata {
+		let k in 1 .. T::MaxKeywords::get();
+		let caller: T::AccountId = whitelisted_caller();
+		store::<T>(&caller)?;
+		Module::<T>::set_metadata(
+			RawOrigin::Signed(caller.clone()).into(),
+			cid(0),
+			vec![b't'; MAX_FIELD_LEN],
+			keywords(k, b'a'),
+			b"text/html".to_vec(),
+			b"en".to_vec(),
+		)?;
+	}: _(RawOrigin::Signed(caller), cid(0))
+	verify {
+		assert!(!Metadata::<T>::contains_key(Cid::parse(&cid(0)).unwrap()));
+	}
+
+	register_indexer {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(Indexers::<T>::contains_key(&caller));
+	}
+
+	// the `k` segments of the indexer are removed.
+	unregister_indexer {
+		let k in 1 .. T::MaxKeywordsPerIndexer::get();
+		let caller = indexer::<T>()?;
+		publish::<T>(&caller, k)?;
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(!Indexers::<T>::contains_key(&caller));
+		assert!(Module::<T>::indexer_keywords(&caller).is_empty());
+	}
+
+	// every CID is stored by an order.
+	publish_segment {
+		let c in 1 .. T::MaxSegmentLen::get();
+		let caller = indexer::<T>()?;
+		let cids = (0..c).map(cid).collect::<Vec<_>>();
+		for text in cids.iter() {
+			ipse::HashOrders::insert(Cid::parse(text).unwrap(), vec![0]);
+		}
+	}: _(RawOrigin::Signed(caller.clone()), vec![b'k'; MAX_KEYWORD_LEN], cids)
+	verify {
+		assert!(Segments::<T>::contains_key(vec![b'k'; MAX_KEYWORD_LEN], &caller));
+	}
+
+	remove_segment {
+		let caller = indexer::<T>()?;
+		ipse::HashOrders::insert(Cid::parse(&cid(0)).unwrap(), vec![0]);
+		Module::<T>::publish_segment(
+			RawOrigin::Signed(caller.clone()).into(),
+			b"keyword".to_vec(),
+			vec![cid(0)],
+		)?;
+	}: _(RawOrigin::Signed(caller.clone()), b"keyword".to_vec())
+	verify {
+		assert!(Module::<T>::indexer_keywords(&caller).is_empty());
+	}
+
+	slash_indexer {
+		let k in 1 .. T::MaxKeywordsPerIndexer::get();
+		let indexer = indexer::<T>()?;
+		publish::<T>(&indexer, k)?;
+	}: _(RawOrigin::Root, indexer.clone())
+	verify {
+		assert!(!Indexers::<T>::contains_key(&indexer));
+	}
+}
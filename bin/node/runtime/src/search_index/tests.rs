@@ -0,0 +1,190 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests of the search index module.
+
+use super::mock::*;
+use super::{Error, MAX_KEYWORD_LEN};
+use crate::cid::Cid;
+use crate::ipse::{DataClass, RetrievalFrequency};
+use frame_support::{assert_noop, assert_ok, dispatch::DispatchResult};
+
+const CID: &[u8] = b"QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+
+/// not stored by any order.
+const OTHER_CID: &[u8] = b"bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku";
+
+fn cid() -> Cid {
+	Cid::parse(CID).unwrap()
+}
+
+/// the miner `1` stores up to 1000 bytes at 1 per byte.
+fn register_miner() {
+	assert_ok!(Ipse::register_miner(
+		Origin::signed(1),
+		b"miner".to_vec(),
+		b"region".to_vec(),
+		b"url".to_vec(),
+		vec![],
+		1,
+		1_000,
+		1,
+	));
+}
+
+/// the user stores the content of `CID` with the miner `1`.
+fn store(user: u64) {
+	assert_ok!(Ipse::create_order(
+		Origin::signed(user),
+		vec![1],
+		1,
+		b"label".to_vec(),
+		CID.to_vec(),
+		100,
+		[0; 32],
+		None,
+		1,
+		DataClass::Cold,
+		RetrievalFrequency::Low,
+		1,
+	));
+}
+
+fn set_metadata(who: u64, keywords: &[&str]) -> DispatchResult {
+	SearchIndex::set_metadata(
+		Origin::signed(who),
+		CID.to_vec(),
+		b"title".to_vec(),
+		keywords.iter().map(|k| k.as_bytes().to_vec()).collect(),
+		b"text/html".to_vec(),
+		b"en".to_vec(),
+	)
+}
+
+#[test]
+fn only_the_owner_updates_the_metadata() {
+	new_test_ext().execute_with(|| {
+		register_miner();
+		store(2);
+		store(3);
+
+		assert_noop!(set_metadata(4, &["rust"]), Error::<Test>::NotContentOwner);
+		assert_ok!(set_metadata(2, &["rust"]));
+		// the other user who stores the content can't take the metadata over.
+		assert_noop!(set_metadata(3, &["spam"]), Error::<Test>::NotContentOwner);
+		assert_noop!(
+			SearchIndex::clear_metadata(Origin::signed(3), CID.to_vec()),
+			Error::<Test>::NotContentOwner
+		);
+
+		assert_ok!(set_metadata(2, &["ipfs"]));
+		let meta = SearchIndex::metadata(cid()).unwrap();
+		assert_eq!(meta.owner, 2);
+		assert_eq!(meta.keywords, vec![b"ipfs".to_vec()]);
+
+		// the content is free to describe once the owner clears its metadata.
+		assert_ok!(SearchIndex::clear_metadata(Origin::signed(2), CID.to_vec()));
+		assert_ok!(set_metadata(3, &["rust"]));
+		assert_eq!(SearchIndex::metadata(cid()).unwrap().owner, 3);
+	});
+}
+
+#[test]
+fn keywords_are_normalized() {
+	new_test_ext().execute_with(|| {
+		register_miner();
+		store(2);
+
+		assert_ok!(set_metadata(2, &["Rust", "rust", "IPFS"]));
+		assert_eq!(
+			SearchIndex::metadata(cid()).unwrap().keywords,
+			vec![b"rust".to_vec(), b"ipfs".to_vec()]
+		);
+		assert_eq!(SearchIndex::search_tagged(b"RUST".to_vec()), vec![cid()]);
+
+		// the old keywords are untagged.
+		assert_ok!(set_metadata(2, &["wasm"]));
+		assert!(SearchIndex::search_tagged(b"rust".to_vec()).is_empty());
+		assert_eq!(SearchIndex::search_tagged(b"wasm".to_vec()), vec![cid()]);
+
+		assert_noop!(set_metadata(2, &[""]), Error::<Test>::EmptyKeyword);
+		let long = "a".repeat(MAX_KEYWORD_LEN + 1);
+		assert_noop!(set_metadata(2, &[long.as_str()]), Error::<Test>::TooLong);
+		assert_noop!(set_metadata(2, &["a", "b", "c", "d"]), Error::<Test>::TooManyKeywords);
+	});
+}
+
+#[test]
+fn segments_are_limited() {
+	new_test_ext().execute_with(|| {
+		register_miner();
+		store(2);
+		let publish = |cids: Vec<&[u8]>| {
+			SearchIndex::publish_segment(
+				Origin::signed(4),
+				b"Rust".to_vec(),
+				cids.into_iter().map(|c| c.to_vec()).collect(),
+			)
+		};
+
+		assert_noop!(publish(vec![CID]), Error::<Test>::IndexerNotFound);
+		assert_ok!(SearchIndex::register_indexer(Origin::signed(4)));
+		assert_eq!(Balances::reserved_balance(4), 100);
+
+		assert_noop!(publish(vec![]), Error::<Test>::EmptySegment);
+		assert_noop!(publish(vec![CID, CID, CID]), Error::<Test>::SegmentTooLong);
+		assert_noop!(publish(vec![CID, OTHER_CID]), Error::<Test>::ContentNotStored);
+		assert_noop!(publish(vec![&b"not a cid"[..]]), Error::<Test>::InvalidCid);
+
+		assert_ok!(publish(vec![CID]));
+		assert_eq!(SearchIndex::search_segments(b"rust".to_vec()), vec![(4, vec![cid()])]);
+		assert_eq!(SearchIndex::indexer_keywords(4u64), vec![b"rust".to_vec()]);
+
+		assert_ok!(SearchIndex::remove_segment(Origin::signed(4), b"RUST".to_vec()));
+		assert!(SearchIndex::search_segments(b"rust".to_vec()).is_empty());
+		assert!(SearchIndex::indexer_keywords(4u64).is_empty());
+	});
+}
+
+#[test]
+fn keywords_of_an_indexer_are_limited() {
+	new_test_ext().execute_with(|| {
+		register_miner();
+		store(2);
+		assert_ok!(SearchIndex::register_indexer(Origin::signed(4)));
+		let publish = |keyword: &[u8]| {
+			SearchIndex::publish_segment(Origin::signed(4), keyword.to_vec(), vec![CID.to_vec()])
+		};
+
+		assert_ok!(publish(b"rust"));
+		assert_ok!(publish(b"wasm"));
+		assert_noop!(publish(b"ipfs"), Error::<Test>::TooManySegments);
+		// the old segments are replaced.
+		assert_ok!(publish(b"RUST"));
+
+		assert_noop!(
+			SearchIndex::remove_segment(Origin::signed(4), b"".to_vec()),
+			Error::<Test>::EmptyKeyword
+		);
+		assert_ok!(SearchIndex::remove_segment(Origin::signed(4), b"Wasm".to_vec()));
+		assert_ok!(publish(b"ipfs"));
+
+		assert_ok!(SearchIndex::unregister_indexer(Origin::signed(4)));
+		assert!(SearchIndex::search_segments(b"rust".to_vec()).is_empty());
+		assert!(SearchIndex::search_segments(b"ipfs".to_vec()).is_empty());
+		assert!(SearchIndex::indexer_keywords(4u64).is_empty());
+		assert_eq!(Balances::reserved_balance(4), 0);
+	});
+}
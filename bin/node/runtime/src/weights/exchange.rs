@@ -0,0 +1,62 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Weights for the exchange module.
+//!
+//! The storage accesses are counted from the benchmarks in `exchange/benchmarking.rs`, the
+//! execution times are estimated. Regenerate them on the reference hardware with
+//! `make weights`, which runs for each module:
+//!
+//! ./target/release/IPSE benchmark --chain=dev --steps=50 --repeat=20 --pallet=exchange
+//! --extrinsic='*' --execution=wasm --wasm-execution=compiled --heap-pages=4096
+//! --header=<license> --output=./bin/node/runtime/src/weights/
+
+use frame_support::{traits::Get, weights::Weight};
+use sp_std::marker::PhantomData;
+
+pub struct WeightInfo<T>(PhantomData<T>);
+impl<T: frame_system::Trait> crate::exchange::WeightInfo for WeightInfo<T> {
+	fn set_exchange_deadline() -> Weight {
+		(12000000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn set_notary_keys(k: u32) -> Weight {
+		(21000000 as Weight)
+			.saturating_add((95000 as Weight).saturating_mul(k as Weight))
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn del_notary_keys(k: u32) -> Weight {
+		(21000000 as Weight)
+			.saturating_add((110000 as Weight).saturating_mul(k as Weight))
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn exchange() -> Weight {
+		(40000000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	fn record_suc_verify() -> Weight {
+		(96000000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(7 as Weight))
+			.saturating_add(T::DbWeight::get().writes(7 as Weight))
+	}
+	fn record_fail_verify() -> Weight {
+		(64000000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+}
@@ -15,7 +15,9 @@
 
 //! A list of the different weight modules for our runtime.
 
+pub mod exchange;
 pub mod frame_system;
+pub mod ipse;
 pub mod pallet_balances;
 pub mod pallet_collective;
 pub mod pallet_democracy;
@@ -32,3 +34,7 @@ pub mod pallet_timestamp;
 pub mod pallet_treasury;
 pub mod pallet_utility;
 pub mod pallet_vesting;
+pub mod poc;
+pub mod poc_staking;
+pub mod price_feed;
+pub mod search_index;
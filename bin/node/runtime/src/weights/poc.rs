@@ -0,0 +1,71 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Weights for the poc module.
+//!
+//! The storage accesses are counted from the benchmarks in `poc/benchmarking.rs`, the
+//! execution times are estimated. Regenerate them on the reference hardware with
+//! `make weights`, which runs for each module:
+//!
+//! ./target/release/IPSE benchmark --chain=dev --steps=50 --repeat=20 --pallet=poc
+//! --extrinsic='*' --execution=wasm --wasm-execution=compiled --heap-pages=4096
+//! --header=<license> --output=./bin/node/runtime/src/weights/
+//!
+//! `mining` and `submit_deadline` generate the whole nonce (8192 shabal256 hashes over 256 KiB)
+//! to verify the deadline, `NONCE_GENERATION` is added to their execution times.
+
+use frame_support::{traits::Get, weights::Weight};
+use sp_std::marker::PhantomData;
+
+/// the generation of a nonce by the poc host function of the node.
+#[cfg(not(feature = "wasm-poc"))]
+const NONCE_GENERATION: Weight = 150_000_000_000;
+
+/// the generation of a nonce in Wasm, about three times slower than natively.
+#[cfg(feature = "wasm-poc")]
+const NONCE_GENERATION: Weight = 450_000_000_000;
+
+pub struct WeightInfo<T>(PhantomData<T>);
+impl<T: frame_system::Trait> crate::poc::WeightInfo for WeightInfo<T> {
+	fn set_difficulty() -> Weight {
+		(24000000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn set_adjust_difficulty_duration() -> Weight {
+		(12000000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn set_capacity_price() -> Weight {
+		(12000000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn set_capacity_of_per_difficulty() -> Weight {
+		(12000000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn mining() -> Weight {
+		(50000000 as Weight)
+			.saturating_add(NONCE_GENERATION)
+			.saturating_add(T::DbWeight::get().reads(8 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	fn submit_deadline() -> Weight {
+		(50000000 as Weight)
+			.saturating_add(NONCE_GENERATION)
+			.saturating_add(T::DbWeight::get().reads(9 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+}
@@ -0,0 +1,108 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Weights for the poc staking module.
+//!
+//! The storage accesses are counted from the benchmarks in `poc_staking/benchmarking.rs`, the
+//! execution times are estimated. Regenerate them on the reference hardware with
+//! `make weights`, which runs for each module:
+//!
+//! ./target/release/IPSE benchmark --chain=dev --steps=50 --repeat=20 --pallet=poc_staking
+//! --extrinsic='*' --execution=wasm --wasm-execution=compiled --heap-pages=4096
+//! --header=<license> --output=./bin/node/runtime/src/weights/
+
+use frame_support::{traits::Get, weights::Weight};
+use sp_std::marker::PhantomData;
+
+pub struct WeightInfo<T>(PhantomData<T>);
+impl<T: frame_system::Trait> crate::poc_staking::WeightInfo for WeightInfo<T> {
+	fn register() -> Weight {
+		(68000000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(7 as Weight))
+	}
+	fn request_up_to_list(l: u32) -> Weight {
+		(62000000 as Weight)
+			.saturating_add((410000 as Weight).saturating_mul(l as Weight))
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	fn request_down_from_list(l: u32) -> Weight {
+		(71000000 as Weight)
+			.saturating_add((240000 as Weight).saturating_mul(l as Weight))
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	fn update_reward_dest() -> Weight {
+		(30000000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn update_numeric_id() -> Weight {
+		(42000000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	fn update_plot_size() -> Weight {
+		(48000000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	fn stop_mining() -> Weight {
+		(38000000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	fn restart_mining() -> Weight {
+		(43000000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	fn remove_staker(s: u32) -> Weight {
+		(72000000 as Weight)
+			.saturating_add((520000 as Weight).saturating_mul(s as Weight))
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	fn staking(s: u32) -> Weight {
+		(64000000 as Weight)
+			.saturating_add((480000 as Weight).saturating_mul(s as Weight))
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+	}
+	fn update_staking(s: u32) -> Weight {
+		(76000000 as Weight)
+			.saturating_add((530000 as Weight).saturating_mul(s as Weight))
+			.saturating_add(T::DbWeight::get().reads(7 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	fn unlock() -> Weight {
+		(45000000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	fn exit_staking(s: u32) -> Weight {
+		(74000000 as Weight)
+			.saturating_add((520000 as Weight).saturating_mul(s as Weight))
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+	}
+	fn update_proportion(s: u32) -> Weight {
+		(36000000 as Weight)
+			.saturating_add((450000 as Weight).saturating_mul(s as Weight))
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+}
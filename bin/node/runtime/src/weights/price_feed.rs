@@ -0,0 +1,51 @@
+// Copyright 2021 IPSE  Developer.
+// This file is part of IPSE
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Weights for the price feed module.
+//!
+//! The storage accesses are counted from the benchmarks in `price_feed/benchmarking.rs`,
+//! the execution times are estimated. Regenerate them on the reference hardware with
+//! `make weights`, which runs for each module:
+//!
+//! ./target/release/IPSE benchmark --chain=dev --steps=50 --repeat=20 --pallet=price_feed
+//! --extrinsic='*' --execution=wasm --wasm-execution=compiled --heap-pages=4096
+//! --header=<license> --output=./bin/node/runtime/src/weights/
+
+use frame_support::{traits::Get, weights::Weight};
+use sp_std::marker::PhantomData;
+
+pub struct WeightInfo<T>(PhantomData<T>);
+impl<T: frame_system::Trait> crate::price_feed::WeightInfo for WeightInfo<T> {
+	fn add_oracle(o: u32) -> Weight {
+		(24000000 as Weight)
+			.saturating_add((110000 as Weight).saturating_mul(o as Weight))
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	fn remove_oracle(o: u32) -> Weight {
+		(39000000 as Weight)
+			.saturating_add((1400000 as Weight).saturating_mul(o as Weight))
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().reads((1 as Weight).saturating_mul(o as Weight)))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	fn submit_price(o: u32) -> Weight {
+		(43000000 as Weight)
+			.saturating_add((1400000 as Weight).saturating_mul(o as Weight))
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().reads((1 as Weight).saturating_mul(o as Weight)))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+}
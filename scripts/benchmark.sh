@@ -0,0 +1,32 @@
+#!/usr/bin/env bash
+
+set -e
+
+PALLETS="ipse poc poc_staking exchange price_feed search_index"
+HEADER=$(mktemp)
+trap 'rm -f $HEADER' EXIT
+
+head -n 14 ./bin/node/runtime/src/weights/ipse.rs > $HEADER
+
+echo "*** Building the node with the runtime benchmarks"
+
+cargo build --release --features runtime-benchmarks --manifest-path ./bin/node/cli/Cargo.toml
+
+for PALLET in $PALLETS ; do
+	echo "*** Benchmarking $PALLET"
+
+	./target/release/IPSE benchmark \
+		--chain=dev \
+		--steps=50 \
+		--repeat=20 \
+		--pallet=$PALLET \
+		--extrinsic='*' \
+		--execution=wasm \
+		--wasm-execution=compiled \
+		--heap-pages=4096 \
+		--header=$HEADER \
+		--output=./bin/node/runtime/src/weights/
+
+	# the pallets of the runtime are modules of the runtime crate
+	sed -i "s/> $PALLET::WeightInfo/> crate::$PALLET::WeightInfo/" ./bin/node/runtime/src/weights/$PALLET.rs
+done